@@ -0,0 +1,43 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec3 f_pos;
+layout(location = 1) flat in int face_index;
+
+// +X, -X, +Y, -Y, +Z, -Z
+layout(set = 0, binding = 0) uniform texture2D face_pos_x;
+layout(set = 0, binding = 1) uniform texture2D face_neg_x;
+layout(set = 0, binding = 2) uniform texture2D face_pos_y;
+layout(set = 0, binding = 3) uniform texture2D face_neg_y;
+layout(set = 0, binding = 4) uniform texture2D face_pos_z;
+layout(set = 0, binding = 5) uniform texture2D face_neg_z;
+layout(set = 0, binding = 6) uniform sampler face_sampler;
+
+layout(location = 0) out vec4 color;
+
+void main() {
+    // Same face orientation as the cube map lookup, the top of each image is v = 0.
+    vec3 dir = f_pos;
+    vec2 uv;
+    vec3 col;
+    if (face_index == 0) {
+        uv = vec2(-dir.z, -dir.y) / abs(dir.x);
+        col = texture(sampler2D(face_pos_x, face_sampler), uv * 0.5 + 0.5).rgb;
+    } else if (face_index == 1) {
+        uv = vec2(dir.z, -dir.y) / abs(dir.x);
+        col = texture(sampler2D(face_neg_x, face_sampler), uv * 0.5 + 0.5).rgb;
+    } else if (face_index == 2) {
+        uv = vec2(dir.x, dir.z) / abs(dir.y);
+        col = texture(sampler2D(face_pos_y, face_sampler), uv * 0.5 + 0.5).rgb;
+    } else if (face_index == 3) {
+        uv = vec2(dir.x, -dir.z) / abs(dir.y);
+        col = texture(sampler2D(face_neg_y, face_sampler), uv * 0.5 + 0.5).rgb;
+    } else if (face_index == 4) {
+        uv = vec2(dir.x, -dir.y) / abs(dir.z);
+        col = texture(sampler2D(face_pos_z, face_sampler), uv * 0.5 + 0.5).rgb;
+    } else {
+        uv = vec2(-dir.x, -dir.y) / abs(dir.z);
+        col = texture(sampler2D(face_neg_z, face_sampler), uv * 0.5 + 0.5).rgb;
+    }
+    color = vec4(col, 1.0);
+}
@@ -0,0 +1,6 @@
+(
+    stages: [
+        (stage: Vertex, path: "equirectangular_to_cube_faces_vert.glsl"),
+        (stage: Fragment, path: "gradient_to_cubemap_frag.glsl"),
+    ],
+)
@@ -0,0 +1,25 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec3 f_pos;
+layout(location = 1) flat in int face_index;
+
+layout(set = 0, binding = 0) uniform Gradient {
+    // w: blend exponent.
+    vec4 top;
+    vec4 horizon;
+    vec4 bottom;
+};
+
+layout(location = 0) out vec4 color;
+
+void main() {
+    float height = normalize(f_pos).y;
+    vec3 col;
+    if (height >= 0.0) {
+        col = mix(horizon.rgb, top.rgb, pow(height, top.w));
+    } else {
+        col = mix(horizon.rgb, bottom.rgb, pow(-height, top.w));
+    }
+    color = vec4(col, 1.0);
+}
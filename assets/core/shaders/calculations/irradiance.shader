@@ -1,2 +1,6 @@
-equirectangular_to_cube_faces_vert.glsl
-env_irradiance_frag.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "equirectangular_to_cube_faces_vert.glsl"),
+        (stage: Fragment, path: "env_irradiance_frag.glsl"),
+    ],
+)
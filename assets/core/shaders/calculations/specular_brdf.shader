@@ -1,2 +1,6 @@
-full_screen_quad_vert.glsl
-specular_brdf_frag.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "full_screen_quad_vert.glsl"),
+        (stage: Fragment, path: "specular_brdf_frag.glsl"),
+    ],
+)
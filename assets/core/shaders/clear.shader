@@ -0,0 +1,6 @@
+(
+    stages: [
+        (stage: Vertex, path: "clear_vertex.glsl"),
+        (stage: Fragment, path: "clear_fragment.glsl"),
+    ],
+)
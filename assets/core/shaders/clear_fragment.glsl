@@ -0,0 +1,11 @@
+#version 450
+
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform Globals {
+    vec4 clear_color;
+};
+
+void main() {
+    outColor = clear_color;
+}
@@ -0,0 +1,6 @@
+(
+    stages: [
+        (stage: Vertex, path: "debug_vertex.glsl"),
+        (stage: Fragment, path: "debug_fragment.glsl"),
+    ],
+)
@@ -1,2 +1,6 @@
-gui_quad.frag
-gui_quad.vert
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "gui_quad.vert"),
+        (stage: Fragment, path: "gui_quad.frag"),
+    ],
+)
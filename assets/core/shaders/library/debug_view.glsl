@@ -0,0 +1,67 @@
+#ifndef DEBUG_VIEW_INCLUDES
+#define DEBUG_VIEW_INCLUDES
+
+// Has to match DebugViewMode in debug_view_mode.rs.
+const int DEBUG_VIEW_LIT = 0;
+const int DEBUG_VIEW_ALBEDO = 1;
+const int DEBUG_VIEW_NORMALS = 2;
+const int DEBUG_VIEW_UVS = 3;
+const int DEBUG_VIEW_METALLIC_ROUGHNESS = 4;
+const int DEBUG_VIEW_LIGHT_COUNT = 5;
+const int DEBUG_VIEW_DEPTH = 6;
+const int DEBUG_VIEW_WIREFRAME = 7;
+
+// debug_view x: debug view mode, y: z near, z: z far, w: 1 for orthographic cameras.
+layout(set = 1, binding = 0) uniform Globals {
+    mat4 view_projection;
+    vec4 debug_view;
+    vec4 camera_position;
+};
+
+int get_debug_view_mode() {
+    return int(debug_view.x);
+}
+
+// Wireframe keeps the regular shading, the edges are drawn as debug lines.
+bool is_debug_view_shaded() {
+    int mode = get_debug_view_mode();
+    return mode == DEBUG_VIEW_LIT || mode == DEBUG_VIEW_WIREFRAME;
+}
+
+// Linear 0..1 depth between the near and far planes from the fragment's depth.
+float get_linear_depth(float depth) {
+    float z_near = debug_view.y;
+    float z_far = debug_view.z;
+    float view_z = debug_view.w > 0.0
+        ? (depth + 1.0) * (z_far - z_near) * 0.5 + z_near
+        : 2.0 * z_far * z_near / ((z_far + z_near) - depth * (z_far - z_near));
+    return clamp((view_z - z_near) / (z_far - z_near), 0.0, 1.0);
+}
+
+// Blue for no lights through green and yellow to red at max_count or more.
+vec3 get_heatmap(float count, float max_count) {
+    float t = clamp(count / max_count, 0.0, 1.0);
+    vec3 cold = mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0), clamp(t * 2.0, 0.0, 1.0));
+    return mix(cold, vec3(1.0, 0.0, 0.0), clamp(t * 2.0 - 1.0, 0.0, 1.0));
+}
+
+// Everything but the final shading, returns vec4(0.0) for modes that are shaded.
+vec4 get_debug_view_color(vec4 albedo, vec3 normal, vec2 uv, float metallic, float roughness, float light_count) {
+    int mode = get_debug_view_mode();
+    if (mode == DEBUG_VIEW_ALBEDO) {
+        return vec4(albedo.xyz, 1.0);
+    } else if (mode == DEBUG_VIEW_NORMALS) {
+        return vec4(normal * 0.5 + 0.5, 1.0);
+    } else if (mode == DEBUG_VIEW_UVS) {
+        return vec4(fract(uv), 0.0, 1.0);
+    } else if (mode == DEBUG_VIEW_METALLIC_ROUGHNESS) {
+        return vec4(metallic, roughness, 0.0, 1.0);
+    } else if (mode == DEBUG_VIEW_LIGHT_COUNT) {
+        return vec4(get_heatmap(light_count, 16.0), 1.0);
+    } else if (mode == DEBUG_VIEW_DEPTH) {
+        return vec4(vec3(get_linear_depth(gl_FragCoord.z)), 1.0);
+    }
+    return vec4(0.0);
+}
+
+#endif
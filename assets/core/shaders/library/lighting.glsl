@@ -1,31 +1,98 @@
 #ifndef LIGHTING_INCLUDES
 #define LIGHTING_INCLUDES
 
-const int MAX_LIGHTS = 10;
+const int MAX_DIRECTIONAL_LIGHTS = 5;
 
 struct DirectionalLight {
     vec4 direction;
     vec4 color;
 };
 
+// position.w: range, color.w: intensity
 struct PointLight {
     vec4 position;
     vec4 color;
-    vec4 attenuation;
+};
+
+// position.w: range, color.w: intensity, cone.x: cos(inner angle), cone.y: cos(outer angle)
+struct SpotLight {
+    vec4 position;
+    vec4 direction;
+    vec4 color;
+    vec4 cone;
 };
 
 layout (set = 1, binding = 1) uniform LightingData {
     vec4 light_num;
-    DirectionalLight directional_lights[MAX_LIGHTS / 2];
-    PointLight point_lights[MAX_LIGHTS / 2];
+    vec4 cluster_count;
+    vec4 cluster_depth;
+    DirectionalLight directional_lights[MAX_DIRECTIONAL_LIGHTS];
+};
+
+layout (std430, set = 1, binding = 2) readonly buffer PointLights {
+    PointLight point_lights[];
+};
+
+layout (std430, set = 1, binding = 3) readonly buffer SpotLights {
+    SpotLight spot_lights[];
+};
+
+// x: offset into light_indices, y: point light count, z: spot light count.
+layout (std430, set = 1, binding = 4) readonly buffer Clusters {
+    uvec4 clusters[];
+};
+
+// Point light indices of a cluster followed by its spot light indices.
+layout (std430, set = 1, binding = 5) readonly buffer LightIndices {
+    uint light_indices[];
 };
 
 DirectionalLight get_directional_light(int index) {
     return directional_lights[index];
 }
 
-PointLight get_point_light(int index) {
+PointLight get_point_light(uint index) {
     return point_lights[index];
 }
 
+SpotLight get_spot_light(uint index) {
+    return spot_lights[index];
+}
+
+// Has to match LightClusters in clusters.rs.
+uvec4 get_cluster(vec4 clip_position) {
+    vec3 ndc = clip_position.xyz / clip_position.w;
+    uvec2 tile = uvec2(clamp(floor((ndc.xy * 0.5 + 0.5) * cluster_count.xy), vec2(0.0), cluster_count.xy - 1.0));
+    // For a perspective camera clip w is view space depth, orthographic depth is linear in ndc.
+    float view_z = cluster_depth.z > 0.0
+        ? (ndc.z * (cluster_depth.y - cluster_depth.x) + cluster_depth.y + cluster_depth.x) * 0.5
+        : clip_position.w;
+    // Orthographic slices are linear, their near plane can be at or behind the eye.
+    float slice = cluster_depth.z > 0.0
+        ? (view_z - cluster_depth.x) / (cluster_depth.y - cluster_depth.x)
+        : log(max(view_z, cluster_depth.x) / cluster_depth.x) / log(cluster_depth.y / cluster_depth.x);
+    slice = floor(slice * cluster_count.z);
+    uint z = uint(clamp(slice, 0.0, cluster_count.z - 1.0));
+    uint index = tile.x + tile.y * uint(cluster_count.x) + z * uint(cluster_count.x) * uint(cluster_count.y);
+    return clusters[index];
+}
+
+// Inverse square falloff that smoothly reaches zero at the light's range.
+// A range of zero or less means the light never fades out.
+float get_distance_attenuation(vec3 light_vector, float range) {
+    float distance_squared = dot(light_vector, light_vector);
+    float attenuation = 1.0 / max(distance_squared, 0.0001);
+    if (range <= 0.0) {
+        return attenuation;
+    }
+    float factor = distance_squared / (range * range);
+    float window = clamp(1.0 - factor * factor, 0.0, 1.0);
+    return attenuation * window * window;
+}
+
+float get_spot_attenuation(vec3 light_dir, vec3 spot_direction, float cos_inner, float cos_outer) {
+    float cd = dot(-light_dir, normalize(spot_direction));
+    return smoothstep(cos_outer, cos_inner, cd);
+}
+
 #endif
\ No newline at end of file
@@ -1,2 +1,7 @@
-pbr_fragment.glsl
-pbr_vertex.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "pbr_vertex.glsl"),
+        (stage: Fragment, path: "pbr_fragment.glsl"),
+    ],
+    features: ["ALPHA_TEST"],
+)
@@ -4,21 +4,48 @@
 layout(location = 0) in vec2 v_TexCoord;
 layout(location = 1) in vec3 i_normal;
 layout(location = 2) in vec4 i_position;
+layout(location = 3) in vec4 i_clip_position;
+layout(location = 4) in vec4 i_color;
+layout(location = 5) in vec4 i_tangent;
 layout(location = 0) out vec4 outColor;
 
+// Has to match PBRMaterialUniform in pbr_material.rs.
 layout(set = 2, binding = 0) uniform Locals {
-    vec3 color;
+    vec4 material_color;
+    // xyz: emissive color.
+    vec4 emissive;
+    // x: metallic, y: roughness, z: normal scale, w: occlusion strength.
+    vec4 factors;
+    // x: alpha cutoff, zero if nothing should be discarded.
+    // y: one when there is a normal texture.
+    vec4 flags;
+    // Two rows of a 2x3 uv matrix for each texture below.
+    vec4 texture_transforms[10];
 };
 layout(set = 2, binding = 1) uniform texture2D t_Color;
 layout(set = 2, binding = 2) uniform sampler s_Color;
+layout(set = 2, binding = 3) uniform texture2D t_MetallicRoughness;
+layout(set = 2, binding = 4) uniform sampler s_MetallicRoughness;
+layout(set = 2, binding = 5) uniform texture2D t_Normal;
+layout(set = 2, binding = 6) uniform sampler s_Normal;
+layout(set = 2, binding = 7) uniform texture2D t_Occlusion;
+layout(set = 2, binding = 8) uniform sampler s_Occlusion;
+layout(set = 2, binding = 9) uniform texture2D t_Emissive;
+layout(set = 2, binding = 10) uniform sampler s_Emissive;
 
 layout(set = 3, binding = 0) uniform textureCube spec_cube_map;
+layout(set = 3, binding = 1) uniform sampler spec_cube_sampler;
 layout(set = 3, binding = 2) uniform textureCube irradiance_cube_map;
+layout(set = 3, binding = 3) uniform sampler irradiance_cube_sampler;
 layout(set = 3, binding = 4) uniform texture2D spec_brdf_map;
+layout(set = 3, binding = 5) uniform sampler spec_brdf_sampler;
 
 #include "library/lighting.glsl"
+#include "library/debug_view.glsl"
 
-const float MAX_SPEC_LOD = 4.0;
+// The last mip of the specular map, which is prefiltered for a roughness of one.
+const float MAX_SPEC_LOD = 5.0;
+const float PI = 3.1415926535;
 
 vec3 f_schlick(const vec3 f0, const float vh) {
 	return f0 + (1.0 - f0) * exp2((-5.55473 * vh - 6.98316) * vh);
@@ -31,7 +58,7 @@ float v_smithschlick(const float nl, const float nv, const float a) {
 float d_ggx(const float nh, const float a) {
 	float a2 = a * a;
 	float denom = pow(nh * nh * (a2 - 1.0) + 1.0, 2.0);
-	return a2 * (1.0 / 3.1415926535) / denom;
+	return a2 * (1.0 / PI) / denom;
 }
 
 vec3 specularBRDF(const vec3 f0, const float roughness, const float nl, const float nh, const float nv, const float vh) {
@@ -51,26 +78,97 @@ float saturate(float v) {
     return clamp(v, 0.0, 1.0);
 }
 
+vec2 get_uv(int slot) {
+    vec3 uv = vec3(v_TexCoord, 1.0);
+    return vec2(dot(texture_transforms[slot * 2].xyz, uv), dot(texture_transforms[slot * 2 + 1].xyz, uv));
+}
+
+// Light colors are the irradiance of a surface facing the light, so pi is folded into them
+// like it was for the lambert shading.
+vec3 get_light_contribution(vec3 light_dir, vec3 radiance, vec3 normal, vec3 view_dir, vec3 f0, vec3 diffuse_color, float roughness) {
+    vec3 half_dir = normalize(light_dir + view_dir);
+    float nl = max(dot(normal, light_dir), 0.0);
+    float nh = max(dot(normal, half_dir), 0.0);
+    float nv = max(dot(normal, view_dir), 0.0001);
+    float vh = max(dot(view_dir, half_dir), 0.0);
+    vec3 specular = specularBRDF(f0, roughness, nl, nh, nv, vh) * PI * nl;
+    vec3 diffuse = lambertDiffuseBRDF(diffuse_color, nl) * (1.0 - f_schlick(f0, vh));
+    return (diffuse + specular) * radiance;
+}
+
 void main() {
-    vec4 main_color = texture(sampler2D(t_Color, s_Color), v_TexCoord);
-    vec3 ambient = vec3(0.05, 0.05, 0.05);
+    vec4 main_color = texture(sampler2D(t_Color, s_Color), get_uv(0)) * material_color * i_color;
+#ifdef ALPHA_TEST
+    if (flags.x > 0.0 && main_color.w < flags.x) {
+        discard;
+    }
+#endif
+    vec4 metallic_roughness = texture(sampler2D(t_MetallicRoughness, s_MetallicRoughness), get_uv(1));
+    float metallic = saturate(factors.x * metallic_roughness.b);
+    float roughness = clamp(factors.y * metallic_roughness.g, 0.04, 1.0);
+
     vec3 normal = normalize(i_normal);
+    vec3 tangent = i_tangent.xyz - normal * dot(normal, i_tangent.xyz);
+    vec3 bitangent = cross(normal, tangent) * (i_tangent.w < 0.0 ? -1.0 : 1.0);
+    // Double sided materials light back faces as if they were front faces, the whole basis
+    // flips so normal maps aren't mirrored.
+    if (!gl_FrontFacing) {
+        normal = -normal;
+        tangent = -tangent;
+        bitangent = -bitangent;
+    }
+    if (flags.y > 0.0 && dot(tangent, tangent) > 0.0) {
+        tangent = normalize(tangent);
+        bitangent = normalize(bitangent);
+        vec3 tangent_normal = texture(sampler2D(t_Normal, s_Normal), get_uv(2)).xyz * 2.0 - 1.0;
+        tangent_normal.xy *= factors.z;
+        normal = normalize(mat3(tangent, bitangent, normal) * tangent_normal);
+    }
 
-    // accumulate color
-    vec3 color = ambient;
-    for (int i=0; i < int(light_num.x) && i < MAX_LIGHTS; ++i) {
+    uvec4 cluster = get_cluster(i_clip_position);
+
+    if (!is_debug_view_shaded()) {
+        outColor = get_debug_view_color(main_color, normal, v_TexCoord, metallic, roughness, float(cluster.y + cluster.z));
+        return;
+    }
+
+    vec3 view_dir = normalize(camera_position.xyz - i_position.xyz);
+    float nv = max(dot(normal, view_dir), 0.0001);
+    vec3 f0 = mix(vec3(0.04), main_color.xyz, metallic);
+    vec3 diffuse_color = main_color.xyz * (1.0 - metallic);
+
+    // Image based lighting from the skybox, the only light occlusion applies to.
+    float occlusion = 1.0 + factors.w * (texture(sampler2D(t_Occlusion, s_Occlusion), get_uv(3)).r - 1.0);
+    vec3 irradiance = texture(samplerCube(irradiance_cube_map, irradiance_cube_sampler), normal).xyz;
+    vec3 reflection = reflect(-view_dir, normal);
+    vec3 prefiltered = textureLod(samplerCube(spec_cube_map, spec_cube_sampler), reflection, roughness * MAX_SPEC_LOD).xyz;
+    vec2 brdf = texture(sampler2D(spec_brdf_map, spec_brdf_sampler), vec2(nv, roughness)).xy;
+    vec3 color = (irradiance * diffuse_color + prefiltered * (f0 * brdf.x + brdf.y)) * occlusion;
+
+    for (int i=0; i < int(light_num.x) && i < MAX_DIRECTIONAL_LIGHTS; ++i) {
         DirectionalLight light = get_directional_light(i);
-        float dot_product =  max(0.0, dot(normal, light.direction.xyz));
-        color += dot_product * light.color.xyz;
+        color += get_light_contribution(normalize(light.direction.xyz), light.color.xyz, normal, view_dir, f0, diffuse_color, roughness);
+    }
+
+    // Only the lights that reach this pixel's cluster.
+    for (uint i = 0; i < cluster.y; ++i) {
+        PointLight light = get_point_light(light_indices[cluster.x + i]);
+        vec3 light_vector = light.position.xyz - i_position.xyz;
+        vec3 light_dir = normalize(light_vector);
+        float attenuation = get_distance_attenuation(light_vector, light.position.w);
+        color += get_light_contribution(light_dir, light.color.xyz * light.color.w * attenuation, normal, view_dir, f0, diffuse_color, roughness);
     }
 
-    // TODO: calculate attenuation.
-    for (int i=0; i < int(light_num.y) && i < MAX_LIGHTS; ++i) {
-        PointLight light = get_point_light(i);
-        vec3 light_dir = normalize(light.position.xyz - i_position.xyz);
-        float dot_product =  max(0.0, dot(normal, light_dir));
-        color += dot_product * light.color.xyz;
+    for (uint i = 0; i < cluster.z; ++i) {
+        SpotLight light = get_spot_light(light_indices[cluster.x + cluster.y + i]);
+        vec3 light_vector = light.position.xyz - i_position.xyz;
+        vec3 light_dir = normalize(light_vector);
+        float attenuation = get_distance_attenuation(light_vector, light.position.w)
+            * get_spot_attenuation(light_dir, light.direction.xyz, light.cone.x, light.cone.y);
+        color += get_light_contribution(light_dir, light.color.xyz * light.color.w * attenuation, normal, view_dir, f0, diffuse_color, roughness);
     }
 
-    outColor = vec4(color * main_color.xyz, main_color.w);
+    color += texture(sampler2D(t_Emissive, s_Emissive), get_uv(4)).xyz * emissive.xyz;
+
+    outColor = vec4(color, main_color.w);
 }
\ No newline at end of file
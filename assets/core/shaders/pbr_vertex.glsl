@@ -7,18 +7,27 @@ layout(location = 3) in vec4 i_tangent;
 layout(location = 0) out vec2 v_TexCoord;
 layout(location = 1) out vec3 o_normal;
 layout(location = 2) out vec4 o_position;
+layout(location = 3) out vec4 o_clip_position;
+layout(location = 4) out vec4 o_color;
+layout(location = 5) out vec4 o_tangent;
 
 layout(set = 1, binding = 0) uniform Globals {
     mat4 view_projection;
+    vec4 debug_view;
+    vec4 camera_position;
 };
 
 layout(set = 0, binding = 0) uniform Locals {
     mat4 world;
+    vec4 color;
 };
 
 void main() {
     v_TexCoord = i_uv;
+    o_color = color;
     o_normal = mat3(world) * i_normal.xyz;
+    o_tangent = vec4(mat3(world) * i_tangent.xyz, i_tangent.w);
     o_position = world * vec4(i_Pos, 1.0);
-    gl_Position = view_projection * o_position;
+    o_clip_position = view_projection * o_position;
+    gl_Position = o_clip_position;
 }
\ No newline at end of file
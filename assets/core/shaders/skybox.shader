@@ -1,2 +1,6 @@
-skybox_vert.glsl
-skybox_frag.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "skybox_vert.glsl"),
+        (stage: Fragment, path: "skybox_frag.glsl"),
+    ],
+)
@@ -0,0 +1,6 @@
+(
+    stages: [
+        (stage: Vertex, path: "sprite_vertex.glsl"),
+        (stage: Fragment, path: "sprite_fragment.glsl"),
+    ],
+)
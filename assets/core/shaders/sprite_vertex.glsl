@@ -0,0 +1,27 @@
+#version 450
+
+layout(location = 0) in vec2 i_Pos;
+layout(location = 1) in vec4 i_world_0;
+layout(location = 2) in vec4 i_world_1;
+layout(location = 3) in vec4 i_world_2;
+layout(location = 4) in vec4 i_world_3;
+// xy: uv offset, zw: uv scale
+layout(location = 5) in vec4 i_uv_rect;
+layout(location = 6) in vec4 i_tint;
+
+layout(location = 0) out vec2 v_TexCoord;
+layout(location = 1) out vec4 v_tint;
+
+layout(set = 0, binding = 0) uniform Globals {
+    mat4 view_projection;
+    vec4 debug_view;
+    vec4 camera_position;
+};
+
+void main() {
+    mat4 world = mat4(i_world_0, i_world_1, i_world_2, i_world_3);
+    // The quad goes from (0, 0) at the bottom left to (1, 1) while images start at the top.
+    v_TexCoord = i_uv_rect.xy + vec2(i_Pos.x, 1.0 - i_Pos.y) * i_uv_rect.zw;
+    v_tint = i_tint;
+    gl_Position = view_projection * world * vec4(i_Pos, 0.0, 1.0);
+}
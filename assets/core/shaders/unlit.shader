@@ -1,2 +1,7 @@
-unlit_fragment.glsl
-unlit_vertex.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "unlit_vertex.glsl"),
+        (stage: Fragment, path: "unlit_fragment.glsl"),
+    ],
+    features: ["ALPHA_TEST"],
+)
@@ -1,15 +1,32 @@
 #version 450
+#extension GL_GOOGLE_include_directive : enable
 
 layout(location = 0) in vec2 v_TexCoord;
+layout(location = 1) in vec3 i_normal;
+layout(location = 2) in vec4 i_color;
 layout(location = 0) out vec4 outColor;
 
 layout(set = 2, binding = 0) uniform Locals {
-    vec3 color;
+    vec4 material_color;
+    // x: alpha cutoff, zero if nothing should be discarded.
+    vec4 alpha_cutoff;
 };
 layout(set = 2, binding = 1) uniform texture2D t_Color;
 layout(set = 2, binding = 2) uniform sampler s_Color;
 
+#include "library/debug_view.glsl"
+
 void main() {
-    vec4 tex = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+    vec4 tex = texture(sampler2D(t_Color, s_Color), v_TexCoord) * material_color * i_color;
+#ifdef ALPHA_TEST
+    if (alpha_cutoff.x > 0.0 && tex.a < alpha_cutoff.x) {
+        discard;
+    }
+#endif
+    if (!is_debug_view_shaded()) {
+        // Unlit materials have no metallic or roughness and aren't lit.
+        outColor = get_debug_view_color(tex, normalize(i_normal), v_TexCoord, 0.0, 0.0, 0.0);
+        return;
+    }
     outColor = tex;
 }
\ No newline at end of file
@@ -5,16 +5,23 @@ layout(location = 1) in vec3 i_normal;
 layout(location = 2) in vec2 i_uv;
 layout(location = 3) in vec4 i_tangent;
 layout(location = 0) out vec2 v_TexCoord;
+layout(location = 1) out vec3 o_normal;
+layout(location = 2) out vec4 o_color;
 
 layout(set = 1, binding = 0) uniform Globals {
     mat4 view_projection;
+    vec4 debug_view;
+    vec4 camera_position;
 };
 
 layout(set = 0, binding = 0) uniform Locals {
     mat4 world;
+    vec4 color;
 };
 
 void main() {
     v_TexCoord = i_uv;
+    o_color = color;
+    o_normal = mat3(world) * i_normal;
     gl_Position = view_projection * world * vec4(i_Pos, 1.0);
 }
\ No newline at end of file
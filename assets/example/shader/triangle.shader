@@ -1,2 +1,6 @@
-triangle_vert.glsl
-triangle_frag.glsl
\ No newline at end of file
+(
+    stages: [
+        (stage: Vertex, path: "triangle_vert.glsl"),
+        (stage: Fragment, path: "triangle_frag.glsl"),
+    ],
+)
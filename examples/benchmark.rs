@@ -70,7 +70,7 @@ impl harmony::AppState for AppState {
         let scale = 3.0;
         for x in 0..size {
             for y in 0..size {
-                let mut transform = Transform::new(app);
+                let mut transform = Transform::new();
                 transform.position.x = x as f32 * scale;
                 transform.position.y = y as f32 * scale;
                 scene
@@ -96,14 +96,14 @@ impl harmony::AppState for AppState {
                 direction: Vec3::new(0.0, 1.0, -0.5),
                 color: Vec3::new(1.0, 1.0, 1.0),
             }),
-            Transform::new(app),
+            Transform::new(),
         );
 
         let actual_window_size = app.get_window_actual_size();
 
-        // TODO: Add other camera types other than perspective.
         // We can't render anything without a camera. Add one here.
         // Thankfully we have a method to help that makes it easy!
+        // For 2D use `CameraData::new_orthographic` instead.
         let mut camera_data = CameraData::new_perspective(
             70.0,
             actual_window_size.width,
@@ -9,7 +9,7 @@ use winit::{
 };
 
 use harmony::scene::components::{
-    CameraData, DirectionalLightData, LightType, Material, Mesh, Transform,
+    CameraData, DirectionalLightData, LightType, Material, Mesh, OrbitCamera, Transform,
 };
 use harmony::scene::Scene;
 use harmony::WinitState;
@@ -56,7 +56,14 @@ impl<'a> System<'a> for RotateSystem {
 
 impl harmony::AppState for AppState {
     fn load(&mut self, app: &mut harmony::Application) {
-        let dispatch_builder = DispatcherBuilder::default().with(RotateSystem, "RotateSystem", &[]);
+        // UpdateOrbitCameras lets us drag around the cube with the mouse.
+        let dispatch_builder = DispatcherBuilder::default()
+            .with(RotateSystem, "RotateSystem", &[])
+            .with(
+                harmony::scene::systems::UpdateOrbitCameras,
+                "UpdateOrbitCameras",
+                &[],
+            );
 
         let mut scene = Scene::new(None, Some(dispatch_builder));
         // Here we create our game entity that contains 3 components.
@@ -71,7 +78,7 @@ impl harmony::AppState for AppState {
             .create_entity()
             .with(Mesh::new("cube.gltf"))
             .with(Material::new(0)) // Need to be an index to the material.
-            .with(Transform::new(app))
+            .with(Transform::new())
             .build();
 
         // Here we create our skybox entity and populate it with a HDR skybox texture.
@@ -87,40 +94,43 @@ impl harmony::AppState for AppState {
                 direction: Vec3::new(0.0, 1.0, -0.5),
                 color: Vec3::new(1.0, 1.0, 1.0),
             }),
-            Transform::new(app),
+            Transform::new(),
         );
 
         // Add red point light to our scene.
         // Uncomment this code to see point light.
-        // let mut transform = Transform::new(app);
+        // let mut transform = Transform::new();
         // transform.position = Vec3::new(-5.0, 0.0, 0.0);
         // harmony::scene::entities::light::create(
         //     &mut scene.world,
         //     LightType::Point(PointLightData {
         //         color: Vec3::new(1.0, 0.0, 0.0),
-        //         attenuation: 10.0,
+        //         intensity: 10.0,
+        //         range: 10.0,
         //     }),
         //     transform,
         // );
 
         let actual_window_size = app.get_window_actual_size();
 
-        // TODO: Add other camera types other than perspective.
         // We can't render anything without a camera. Add one here.
         // Thankfully we have a method to help that makes it easy!
-        let mut camera_data = CameraData::new_perspective(
+        // For 2D use `CameraData::new_orthographic` instead.
+        let camera_data = CameraData::new_perspective(
             70.0,
             actual_window_size.width,
             actual_window_size.height,
             0.01,
             10.0,
         );
-        camera_data.update_view(
-            Vec3::new(0.0, 0.0, -5.0), // This is our camera's "position".
-            Vec3::new(0.0, 0.0, 0.0),  // Where the camera is looking at.
-            Vec3::new(0.0, 1.0, 0.0),  // Our camera's up vector.
-        );
-        harmony::scene::entities::camera::create(&mut scene.world, camera_data);
+        // The orbit camera keeps the view pointed at the cube from 5 units away.
+        // Left drag orbits, right drag pans and scrolling zooms. Use `FlyCamera` to fly around instead.
+        scene
+            .world
+            .create_entity()
+            .with(camera_data)
+            .with(OrbitCamera::new(Vec3::new(0.0, 0.0, 0.0), 5.0))
+            .build();
 
         // You can access the scene here once we store it.
         app.current_scene = scene;
@@ -27,7 +27,7 @@ impl SimplePipeline for TrianglePipeline {
         _depth: Option<&wgpu::TextureView>,
         _device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         _output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -35,7 +35,7 @@ impl SimplePipeline for TrianglePipeline {
     ) -> Option<RenderTarget> {
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &frame.as_ref().unwrap().view,
+                attachment: frame.unwrap(),
                 resolve_target: None,
                 load_op: wgpu::LoadOp::Clear,
                 store_op: wgpu::StoreOp::Store,
@@ -64,7 +64,12 @@ impl SimplePipelineDesc for TrianglePipelineDesc {
         asset_manager.get_shader("triangle.shader")
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
+    // The triangle shader has no bindings, this shows how to write layouts by hand.
+    fn create_layout(
+        &self,
+        device: &mut wgpu::Device,
+        _reflected_entries: &[Vec<wgpu::BindGroupLayoutEntry>],
+    ) -> Vec<wgpu::BindGroupLayout> {
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             bindings: &[],
             label: None,
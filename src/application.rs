@@ -1,4 +1,5 @@
-use std::time::Instant;
+use specs::RunNow;
+use std::{collections::HashSet, time::Instant};
 use winit::{
     dpi::LogicalSize,
     event::Event,
@@ -8,11 +9,20 @@ use winit::{
 use crate::{
     core::input::Input,
     graphics::{
-        pipelines::{PBRPipelineDesc, SkyboxPipelineDesc, UnlitPipelineDesc},
+        pipelines::{
+            ClearPipelineDesc, DebugPipelineDesc, PBRPipelineDesc, SkyboxPipelineDesc,
+            SpritePipelineDesc, UnlitPipelineDesc,
+        },
+        resources::{DynamicUniformBuffer, LightBuffers},
         RenderGraph, Renderer,
     },
     gui::Scene as GuiScene,
-    scene::Scene,
+    scene::{
+        components::{transform::LocalUniform, CameraData},
+        resources::{CurrentCamera, DebugDraw},
+        systems::{DrawWireframes, PrepareTransforms, UpdateLightClusters, UpdateRenderQueue},
+        Scene,
+    },
     AssetManager,
 };
 
@@ -46,6 +56,7 @@ pub struct Application {
     pub input: Input,
     pub current_scene: Scene<'static>,
     pub render_graph: Option<RenderGraph>,
+    warned_cycles: HashSet<specs::Entity>,
 }
 
 impl Application {
@@ -89,6 +100,7 @@ impl Application {
             input: Input::new(),
             current_scene: Scene::new(None, None),
             render_graph: None,
+            warned_cycles: HashSet::new(),
         }
     }
 
@@ -127,7 +139,19 @@ impl Application {
         );
         self.console.load(&self.asset_manager);
 
-        self.render_graph = Some(RenderGraph::new(&self.renderer.device));
+        self.render_graph = Some(RenderGraph::new());
+        // Clears each camera's viewport before anything else draws to it.
+        let clear_pipeline_desc = ClearPipelineDesc::default();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "camera_clear",
+            clear_pipeline_desc,
+            vec![],
+            false,
+            None,
+            false,
+        );
         // Skybox pipeline
         let skybox_pipeline_desc = SkyboxPipelineDesc::default();
         self.render_graph.as_mut().unwrap().add(
@@ -135,7 +159,7 @@ impl Application {
             &mut self.renderer,
             "skybox",
             skybox_pipeline_desc,
-            vec![],
+            vec!["camera_clear"],
             false,
             None,
             false,
@@ -164,6 +188,93 @@ impl Application {
             None,
             false,
         );
+        let pbr_double_sided_pipeline_desc = PBRPipelineDesc::default().double_sided();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "pbr_double_sided",
+            pbr_double_sided_pipeline_desc,
+            vec!["skybox"],
+            true,
+            None,
+            false,
+        );
+        // Transparent pipelines blend on top of everything opaque so they go last.
+        let unlit_transparent_pipeline_desc = UnlitPipelineDesc::transparent();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "unlit_transparent",
+            unlit_transparent_pipeline_desc,
+            vec!["unlit", "pbr", "pbr_double_sided"],
+            true,
+            None,
+            false,
+        );
+        let pbr_transparent_pipeline_desc = PBRPipelineDesc::transparent();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "pbr_transparent",
+            pbr_transparent_pipeline_desc,
+            vec!["unlit", "pbr", "pbr_double_sided"],
+            true,
+            None,
+            false,
+        );
+        let pbr_transparent_double_sided_pipeline_desc =
+            PBRPipelineDesc::transparent().double_sided();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "pbr_transparent_double_sided",
+            pbr_transparent_double_sided_pipeline_desc,
+            vec!["unlit", "pbr", "pbr_double_sided"],
+            true,
+            None,
+            false,
+        );
+        // Sprite pipeline, 2D goes on top of the 3D scene.
+        let sprite_pipeline_desc = SpritePipelineDesc::default();
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "sprite",
+            sprite_pipeline_desc,
+            vec![
+                "unlit_transparent",
+                "pbr_transparent",
+                "pbr_transparent_double_sided",
+            ],
+            false,
+            None,
+            false,
+        );
+        // Debug lines, the depth tested ones sit in the scene and the rest go on top of everything.
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "debug",
+            DebugPipelineDesc::depth_tested(),
+            vec![
+                "unlit_transparent",
+                "pbr_transparent",
+                "pbr_transparent_double_sided",
+            ],
+            false,
+            None,
+            false,
+        );
+        self.render_graph.as_mut().unwrap().add(
+            &self.asset_manager,
+            &mut self.renderer,
+            "debug_on_top",
+            DebugPipelineDesc::on_top(),
+            vec!["sprite", "debug"],
+            false,
+            None,
+            false,
+        );
 
         app_state.load(self);
 
@@ -172,56 +283,47 @@ impl Application {
             self.asset_manager.materials.values_mut().collect();
         {
             let images = &self.asset_manager.images;
-            let unlit_bind_group_layout = &self
-                .render_graph
-                .as_ref()
-                .unwrap()
-                .get("unlit")
-                .pipeline
-                .bind_group_layouts[1];
-            let pbr_bind_group_layout = &self
-                .render_graph
-                .as_ref()
-                .unwrap()
-                .get("pbr")
-                .pipeline
-                .bind_group_layouts[1];
+            let render_graph = self.render_graph.as_ref().unwrap();
             for material in materials {
-                match material {
-                    super::graphics::material::Material::Unlit(unlit_material) => {
-                        unlit_material.create_bind_group(
-                            images,
-                            &self.renderer.device,
-                            unlit_bind_group_layout,
-                        );
-                    }
-                    super::graphics::material::Material::PBR(pbr_material) => {
-                        pbr_material.create_bind_group(
-                            images,
-                            &self.renderer.device,
-                            pbr_bind_group_layout,
-                        );
-                    }
-                }
+                // Bind groups have to be created from the layout of the pipeline that draws them.
+                let material_bind_group_layout = render_graph
+                    .get(material.pipeline_name())
+                    .pipeline
+                    .bind_group_layout(2);
+                material.material_type_mut().create_bind_group(
+                    images,
+                    &self.renderer.device,
+                    material_bind_group_layout,
+                );
             }
         }
 
-        let pbr_bind_group_layout = &self
-            .render_graph
-            .as_ref()
-            .unwrap()
-            .get("pbr")
-            .pipeline
-            .bind_group_layouts[2];
-
         let world = &mut self.current_scene.world;
-        let skybox_pipeline = self.render_graph.as_ref().unwrap().get("skybox");
-        let material_layout = &skybox_pipeline.pipeline.bind_group_layouts[1];
+        let render_graph = self.render_graph.as_ref().unwrap();
+        let skybox_pipeline = render_graph.get("skybox");
+        let material_layout = skybox_pipeline.pipeline.bind_group_layout(1);
         let skybox = world.try_fetch_mut::<super::graphics::material::Skybox>();
         if skybox.is_some() {
             let mut skybox = skybox.unwrap();
             skybox.create_bind_group(&self.renderer.device, material_layout);
-            skybox.create_pbr_bind_group(&self.renderer.device, pbr_bind_group_layout);
+            for pipeline_name in [
+                "pbr",
+                "pbr_double_sided",
+                "pbr_transparent",
+                "pbr_transparent_double_sided",
+            ]
+            .iter()
+            {
+                let pbr_bind_group_layout = render_graph
+                    .get(*pipeline_name)
+                    .pipeline
+                    .bind_group_layout(3);
+                skybox.create_pbr_bind_group(
+                    &self.renderer.device,
+                    *pipeline_name,
+                    pbr_bind_group_layout,
+                );
+            }
         }
 
         let size = self.renderer.window.inner_size();
@@ -278,8 +380,11 @@ impl Application {
                             .collect()
                     }
                     self.console.update(&self.input, self.delta_time);
+                    if let Some(view_mode) = self.console.take_view_mode() {
+                        self.current_scene.world.insert(view_mode);
+                    }
 
-                    self.current_scene.update(self.delta_time);
+                    self.current_scene.update(self.delta_time, &self.input);
 
                     self.input.clear();
                     frame_time -= self.delta_time;
@@ -293,15 +398,151 @@ impl Application {
                 let output = self.renderer.render();
                 let mut command_buffers = Vec::new();
 
-                // Render the graph.
+                // Upload every transform once, all pipelines index into the same buffer.
+                {
+                    let world = &mut self.current_scene.world;
+                    if !world.has_value::<DynamicUniformBuffer<LocalUniform>>() {
+                        let local_buffer = DynamicUniformBuffer::<LocalUniform>::new(
+                            &self.renderer.device,
+                            &self.renderer.local_bind_group_layout,
+                        );
+                        world.insert(local_buffer);
+                    }
+                    if !world.has_value::<LightBuffers>() {
+                        world.insert(LightBuffers::new(&self.renderer.device));
+                    }
+
+                    let mut encoder = self.renderer.device.create_command_encoder(
+                        &wgpu::CommandEncoderDescriptor {
+                            label: Some("transforms"),
+                        },
+                    );
+                    let mut prepare_transforms = PrepareTransforms {
+                        device: &self.renderer.device,
+                        encoder: &mut encoder,
+                        local_bind_group_layout: &self.renderer.local_bind_group_layout,
+                        warned_cycles: &mut self.warned_cycles,
+                    };
+                    RunNow::setup(&mut prepare_transforms, world);
+                    prepare_transforms.run_now(world);
+                    command_buffers.push(encoder.finish());
+
+                    let mut draw_wireframes = DrawWireframes {
+                        asset_manager: &self.asset_manager,
+                    };
+                    RunNow::setup(&mut draw_wireframes, world);
+                    draw_wireframes.run_now(world);
+                }
+
+                // Render the graph once per camera, lowest priority first.
                 if self.render_graph.is_some() {
-                    let render_graph = self.render_graph.as_mut().unwrap();
-                    command_buffers.push(render_graph.render(
-                        &mut self.renderer,
-                        &mut self.asset_manager,
-                        &mut self.current_scene.world,
-                        Some(&output),
-                    ))
+                    let mut cameras: Vec<(specs::Entity, i32)> = {
+                        use specs::{Join, WorldExt};
+                        let entities = self.current_scene.world.entities();
+                        let camera_data = self.current_scene.world.read_component::<CameraData>();
+                        (&entities, &camera_data)
+                            .join()
+                            .filter(|(_, data)| data.active)
+                            .map(|(entity, data)| (entity, data.priority))
+                            .collect()
+                    };
+                    cameras.sort_by_key(|(_, priority)| *priority);
+
+                    // Without a camera the graph still runs once so custom nodes can draw to the window.
+                    if cameras.is_empty() {
+                        self.current_scene.world.remove::<CurrentCamera>();
+                        let render_graph = self.render_graph.as_mut().unwrap();
+                        command_buffers.push(render_graph.render(
+                            &mut self.renderer,
+                            &mut self.asset_manager,
+                            &mut self.current_scene.world,
+                            Some(&output.view),
+                        ));
+                    }
+
+                    for (entity, _) in cameras {
+                        // Render textures get a view of their own so the asset manager isn't
+                        // borrowed while the graph draws.
+                        let texture_view;
+                        let (frame, current_camera) = {
+                            use specs::WorldExt;
+                            let camera_data =
+                                self.current_scene.world.read_component::<CameraData>();
+                            let camera_data = camera_data.get(entity).unwrap();
+                            let (frame, target_size) = match &camera_data.target {
+                                Some(target) => match self.asset_manager.images.get(target) {
+                                    Some(image) => {
+                                        texture_view = image.texture.create_default_view();
+                                        (&texture_view, (image.extent.width, image.extent.height))
+                                    }
+                                    None => {
+                                        log::warn!(
+                                            "Camera: render texture {} doesn't exist.",
+                                            target
+                                        );
+                                        continue;
+                                    }
+                                },
+                                None => (
+                                    &output.view,
+                                    (self.renderer.size.width, self.renderer.size.height),
+                                ),
+                            };
+                            let current_camera = CurrentCamera {
+                                entity,
+                                viewport: camera_data.pixel_viewport(target_size.0, target_size.1),
+                                target_size,
+                            };
+                            (frame, current_camera)
+                        };
+                        self.current_scene.world.insert(current_camera);
+
+                        // Pick up mesh and material changes and sort what this camera is about to draw.
+                        {
+                            let mut update_render_queue = UpdateRenderQueue {
+                                asset_manager: &self.asset_manager,
+                            };
+                            RunNow::setup(&mut update_render_queue, &mut self.current_scene.world);
+                            update_render_queue.run_now(&self.current_scene.world);
+                        }
+
+                        // Shared by every PBR pipeline this camera draws with.
+                        {
+                            let mut encoder = self.renderer.device.create_command_encoder(
+                                &wgpu::CommandEncoderDescriptor {
+                                    label: Some("lights"),
+                                },
+                            );
+                            let mut update_light_clusters = UpdateLightClusters {
+                                device: &self.renderer.device,
+                                encoder: &mut encoder,
+                            };
+                            RunNow::setup(
+                                &mut update_light_clusters,
+                                &mut self.current_scene.world,
+                            );
+                            update_light_clusters.run_now(&self.current_scene.world);
+                            command_buffers.push(encoder.finish());
+                        }
+
+                        let render_graph = self.render_graph.as_mut().unwrap();
+                        command_buffers.push(render_graph.render(
+                            &mut self.renderer,
+                            &mut self.asset_manager,
+                            &mut self.current_scene.world,
+                            Some(frame),
+                        ));
+                    }
+
+                    self.render_graph.as_mut().unwrap().finish_frame();
+                }
+                // Every camera's render queue has picked up the edited materials by now.
+                self.asset_manager.edited_materials.clear();
+
+                // Every camera has drawn the debug lines, drop the ones that are done.
+                if let Some(mut debug_draw) = self.current_scene.world.try_fetch_mut::<DebugDraw>()
+                {
+                    debug_draw.end_frame();
                 }
 
                 // Gather console components
@@ -1,30 +1,50 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
 use walkdir::WalkDir;
 
+use super::{AtlasRegion, GltfScene, TextureAtlas};
 use crate::graphics::{
-    material::{Image, Material, Shader},
+    material::{Image, ImageSettings, Material, Shader},
     mesh::Mesh,
 };
 use crate::gui::core::Font;
+use specs::{Entity, World};
 
 pub struct AssetManager {
     path: String,
     shaders: HashMap<String, Shader>,
     fonts: HashMap<String, Font>,
     meshes: HashMap<String, Mesh>,
+    gltf_scenes: HashMap<String, GltfScene>,
     pub(crate) images: HashMap<String, Image>,
+    image_paths: HashMap<String, String>,
+    image_settings: HashMap<String, ImageSettings>,
     pub(crate) materials: HashMap<i32, Material>,
+    // Materials handed out through `get_material_mut` since the last frame was drawn. Their
+    // pipeline can change, so the render queue refreshes the items that use them.
+    pub(crate) edited_materials: HashSet<i32>,
+    atlas_regions: HashMap<String, AtlasRegion>,
+    cache_path: Option<PathBuf>,
 }
 
 impl AssetManager {
     pub fn new(path: String) -> Self {
         AssetManager {
-            path,
             shaders: HashMap::new(),
             fonts: HashMap::new(),
             meshes: HashMap::new(),
+            gltf_scenes: HashMap::new(),
             images: HashMap::new(),
+            image_paths: HashMap::new(),
+            image_settings: HashMap::new(),
             materials: HashMap::new(),
+            edited_materials: HashSet::new(),
+            atlas_regions: HashMap::new(),
+            cache_path: Some(Path::new(&path).join(".cache")),
+            path,
         }
     }
 
@@ -37,6 +57,10 @@ impl AssetManager {
         let mut init_encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
+        // Images are loaded last so meshes can mark the textures they sample as data first.
+        let mut image_files = Vec::new();
+        let mut linear_textures = Vec::new();
+
         for entry in WalkDir::new(&self.path) {
             let entry = entry.expect("Error: Could not access file.");
             let file_name = entry.file_name().to_str().unwrap();
@@ -52,7 +76,13 @@ impl AssetManager {
             );
             //let full_path = format!("{}{}", full_file_path, file_name);
             if file_name.ends_with(".shader") {
-                let shader = Shader::new(device, full_file_path.to_string(), file_name.to_string());
+                let shader_cache_path = self.get_cache_path().map(|path| path.join("shaders"));
+                let shader = Shader::new(
+                    device,
+                    full_file_path.to_string(),
+                    file_name.to_string(),
+                    shader_cache_path.as_deref(),
+                );
                 self.shaders.insert(file_name.to_string(), shader);
                 console.info(
                     crate::gui::components::default::ModuleType::Asset,
@@ -72,42 +102,93 @@ impl AssetManager {
             }
             if file_name.ends_with(".gltf") {
                 let current_index = self.materials.len() as i32;
-                let (mesh, materials) = Mesh::new(
+                let import = Mesh::load_gltf(
                     device,
                     format!("{}{}", full_file_path, file_name),
+                    file_name,
                     current_index,
                 );
+                linear_textures.extend(import.linear_textures);
                 let mut index = current_index;
-                for material in materials {
+                for material in import.materials {
                     self.materials.insert(index, material);
                     index += 1;
                 }
-                self.meshes.insert(file_name.to_string(), mesh);
+                for (mesh_index, mesh) in import.meshes.into_iter().enumerate() {
+                    self.meshes
+                        .insert(Mesh::gltf_mesh_name(file_name, mesh_index), mesh);
+                }
+                self.gltf_scenes.insert(file_name.to_string(), import.scene);
                 console.info(
                     crate::gui::components::default::ModuleType::Asset,
                     format!("Loaded mesh: {}", file_name),
                 );
             }
-            if file_name.ends_with(".png")
-                || file_name.ends_with(".jpg")
-                || file_name.ends_with(".hdr")
-            {
-                let image = Image::new(
-                    device,
-                    &mut init_encoder,
-                    format!("{}{}", full_file_path, file_name),
-                    file_name.to_string(),
-                );
-                self.images.insert(file_name.to_string(), image);
+            // Atlas pages are loaded along with their metadata.
+            if file_name.ends_with(".atlas.ron") {
+                let atlas = TextureAtlas::load(format!("{}{}", full_file_path, file_name));
+                self.insert_atlas(device, &mut init_encoder, atlas);
                 console.info(
                     crate::gui::components::default::ModuleType::Asset,
-                    format!("Loaded image: {}", file_name),
+                    format!("Loaded atlas: {}", file_name),
                 );
             }
+            if (file_name.ends_with(".png") && !file_name.ends_with(".atlas.png"))
+                || file_name.ends_with(".jpg")
+                || file_name.ends_with(".hdr")
+                || file_name.ends_with(".ktx2")
+                || file_name.ends_with(".dds")
+            {
+                image_files.push((full_file_path.to_string(), file_name.to_string()));
+            }
+        }
+
+        for (full_file_path, file_name) in image_files {
+            let settings = self.get_image_settings(
+                &full_file_path,
+                &file_name,
+                linear_textures.contains(&file_name),
+            );
+            let image_path = format!("{}{}", full_file_path, file_name);
+            let image = Image::new_with_settings(
+                device,
+                &mut init_encoder,
+                image_path.clone(),
+                file_name.clone(),
+                settings,
+            );
+            self.images.insert(file_name.clone(), image);
+            self.image_paths.insert(file_name.clone(), image_path);
+            console.info(
+                crate::gui::components::default::ModuleType::Asset,
+                format!("Loaded image: {}", file_name),
+            );
         }
         queue.submit(&[init_encoder.finish()]);
     }
 
+    // Settings from `set_image_settings` win over a `.meta` file next to the image.
+    // A `.meta` file replaces the linear default of glTF data textures, so it has to set
+    // `srgb: false` itself.
+    fn get_image_settings(
+        &self,
+        full_file_path: &str,
+        file_name: &str,
+        linear: bool,
+    ) -> ImageSettings {
+        if let Some(settings) = self.image_settings.get(file_name) {
+            return *settings;
+        }
+
+        let meta_path = format!("{}{}.meta", full_file_path, file_name);
+        match fs::read_to_string(&meta_path) {
+            Ok(contents) => ron::de::from_str(&contents)
+                .unwrap_or_else(|error| panic!("Image: Unable to parse {}: {}", meta_path, error)),
+            Err(_) if linear => ImageSettings::linear(),
+            Err(_) => ImageSettings::default(),
+        }
+    }
+
     pub fn get_shader<'a, T>(&'a self, key: T) -> &'a Shader
     where
         T: Into<String>,
@@ -129,6 +210,37 @@ impl AssetManager {
             .expect(&format!("Asset Error: Could not find {} mesh asset!", &key))
     }
 
+    pub fn get_mesh_option<T>(&self, key: T) -> Option<&Mesh>
+    where
+        T: Into<String>,
+    {
+        let key = key.into();
+        self.meshes.get(&key)
+    }
+
+    /// The node hierarchy loaded from a glTF file.
+    pub fn get_gltf_scene<T>(&self, key: T) -> &GltfScene
+    where
+        T: Into<String>,
+    {
+        let key = key.into();
+        self.gltf_scenes.get(&key).expect(&format!(
+            "Asset Error: Could not find {} glTF scene asset!",
+            &key
+        ))
+    }
+
+    /// Creates one entity per node of a glTF file's scene with a `Transform`, and a `Mesh` for
+    /// nodes with a mesh. Sub meshes are drawn with the materials the file gives them. Child
+    /// nodes keep their place in the hierarchy through the `Parent` component. Returns the
+    /// entities of the root nodes.
+    pub fn spawn_gltf_scene<T>(&self, world: &mut World, key: T) -> Vec<Entity>
+    where
+        T: Into<String>,
+    {
+        self.get_gltf_scene(key).spawn(world)
+    }
+
     pub fn get_meshes(&self) -> Vec<&Mesh> {
         self.meshes.values().collect()
     }
@@ -144,7 +256,50 @@ impl AssetManager {
         ))
     }
 
+    /// Adds a material, for example a custom `MaterialType`, and returns its index for the
+    /// `Material` component. Materials can be added at any time, the bind group is created
+    /// when the material's pipeline prepares the next frame.
+    pub fn add_material(&mut self, material: Material) -> i32 {
+        let index = self.materials.len() as i32;
+        self.materials.insert(index, material);
+        index
+    }
+
+    /// Materials can be edited through this at any time, changes are uploaded before the next
+    /// frame is drawn.
+    pub fn get_material_mut(&mut self, index: i32) -> &mut Material {
+        self.edited_materials.insert(index);
+        self.materials.get_mut(&index).expect(&format!(
+            "Asset Error: Could not find material @index {} asset!",
+            index
+        ))
+    }
+
+    // Uploads changes to the materials drawn by a pipeline, the layout is the pipeline's material
+    // layout.
+    pub(crate) fn update_materials(
+        &mut self,
+        pipeline_name: &str,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        material_layout: &wgpu::BindGroupLayout,
+    ) {
+        let images = &self.images;
+        for material in self.materials.values_mut() {
+            if material.pipeline_name() != pipeline_name {
+                continue;
+            }
+            material.material_type_mut().update_bind_group(
+                images,
+                device,
+                encoder,
+                material_layout,
+            );
+        }
+    }
+
     pub fn get_materials_mut(&mut self) -> Vec<&mut Material> {
+        self.edited_materials.extend(self.materials.keys());
         self.materials.values_mut().collect()
     }
 
@@ -175,6 +330,105 @@ impl AssetManager {
         self.images.values().collect()
     }
 
+    // The file an image was loaded from.
+    pub(crate) fn get_image_path<T>(&self, key: T) -> Option<&String>
+    where
+        T: Into<String>,
+    {
+        let key = key.into();
+        self.image_paths.get(&key)
+    }
+
+    /// Sets the folder for files computed from assets, like compiled shaders or a skybox's
+    /// lighting maps, so they can be reused on the next run. Defaults to `.cache` in the asset
+    /// folder, `None` turns caching off. Has to be called before the files are created.
+    pub fn set_cache_path(&mut self, path: Option<PathBuf>) {
+        self.cache_path = path;
+    }
+
+    pub(crate) fn get_cache_path(&self) -> Option<&Path> {
+        self.cache_path.as_deref()
+    }
+
+    /// Overrides how an image is loaded, for example to skip mips for UI and pixel art.
+    /// Takes priority over the image's `.meta` file.
+    /// Has to be called before `Application::load` loads the asset folder.
+    pub fn set_image_settings<T>(&mut self, file_name: T, settings: ImageSettings)
+    where
+        T: Into<String>,
+    {
+        self.image_settings.insert(file_name.into(), settings);
+    }
+
+    /// Creates an image a camera can render to by setting its `target` to `name`.
+    /// Materials can use the same name as a texture, the image is updated every frame.
+    /// Create render textures before `AppState::load` returns so materials can bind them.
+    pub fn create_render_texture<T>(
+        &mut self,
+        device: &wgpu::Device,
+        name: T,
+        width: u32,
+        height: u32,
+    ) where
+        T: Into<String>,
+    {
+        let name = name.into();
+        let image = Image::new_render_texture(device, name.clone(), width, height);
+        self.images.insert(name, image);
+    }
+
+    /// Uploads the atlas pages as images and makes its regions available through
+    /// `get_atlas_region`. Page images are named after their file names.
+    pub fn add_atlas(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &mut wgpu::Queue,
+        atlas: TextureAtlas,
+    ) {
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.insert_atlas(device, &mut encoder, atlas);
+        queue.submit(&[encoder.finish()]);
+    }
+
+    /// Finds a region in any of the loaded atlases by the name its image was packed under, see
+    /// `AtlasBuilder::add_directory` for how files are named.
+    pub fn get_atlas_region<T>(&self, key: T) -> Option<&AtlasRegion>
+    where
+        T: Into<String>,
+    {
+        let key = key.into();
+        self.atlas_regions.get(&key)
+    }
+
+    fn insert_atlas(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        atlas: TextureAtlas,
+    ) {
+        for (page_name, page) in atlas.pages.iter() {
+            let image = Image::from_rgba(
+                device,
+                encoder,
+                page_name.clone(),
+                page,
+                atlas.generate_mips,
+            );
+            self.images.insert(page_name.clone(), image);
+        }
+        for (name, region) in atlas.regions {
+            if self.atlas_regions.contains_key(&name) {
+                log::warn!(
+                    "Atlas: {} replaces the region {} of an atlas loaded before.",
+                    atlas.name,
+                    name
+                );
+            }
+            self.atlas_regions.insert(name, region);
+        }
+    }
+
     pub fn get_font<T>(&self, key: T) -> &Font
     where
         T: Into<String>,
@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::Path};
+use walkdir::WalkDir;
+
+/// A named image inside of an atlas page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    /// Name of the page image in the asset manager.
+    pub page: String,
+    /// Position and size on the page in pixels.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// xy: uv offset, zw: uv size.
+    pub uv_rect: [f32; 4],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AtlasMetadata {
+    generate_mips: bool,
+    pages: Vec<String>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+/// Packed atlas pages and the regions inside of them.
+/// Hand it to `AssetManager::add_atlas` to upload it, or `save` it next to your other assets
+/// and the asset manager will pick it up on load.
+pub struct TextureAtlas {
+    pub name: String,
+    pub generate_mips: bool,
+    pub(crate) pages: Vec<(String, image::RgbaImage)>,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// Writes every page as `<name>_<index>.atlas.png` and the regions to `<name>.atlas.ron`.
+    pub fn save<T>(&self, directory: T) -> io::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+
+        for (page_name, page) in self.pages.iter() {
+            page.save(directory.join(page_name))
+                .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+        }
+
+        let metadata = AtlasMetadata {
+            generate_mips: self.generate_mips,
+            pages: self.pages.iter().map(|(name, _)| name.clone()).collect(),
+            regions: self.regions.clone(),
+        };
+        let metadata = ron::ser::to_string_pretty(&metadata, ron::ser::PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+        fs::write(directory.join(format!("{}.atlas.ron", self.name)), metadata)
+    }
+
+    /// Loads an atlas saved with `save`. Pages are expected to be next to the metadata file.
+    pub fn load<T>(path: T) -> Self
+    where
+        T: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Atlas: Unable to open the file: {}", path.display()));
+        let metadata: AtlasMetadata = ron::de::from_str(&contents)
+            .unwrap_or_else(|error| panic!("Atlas: Unable to parse {}: {}", path.display(), error));
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let pages = metadata
+            .pages
+            .into_iter()
+            .map(|page_name| {
+                let page_path = directory.join(&page_name);
+                let page = image::open(&page_path)
+                    .unwrap_or_else(|_| {
+                        panic!("Atlas: Unable to open the file: {}", page_path.display())
+                    })
+                    .to_rgba();
+                (page_name, page)
+            })
+            .collect();
+
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        Self {
+            name: file_name.trim_end_matches(".atlas.ron").to_string(),
+            generate_mips: metadata.generate_mips,
+            pages,
+            regions: metadata.regions,
+        }
+    }
+}
+
+/// Packs images into one or more atlas pages using rows of shelves.
+///
+/// Images are placed tallest first. Each one gets `padding` pixels around it that repeat its
+/// border texels, so filtering neither bleeds into its neighbours nor fades out its edges.
+/// When mipmaps are generated the padding should be at least as large as the smallest mip
+/// level you expect to sample.
+pub struct AtlasBuilder {
+    name: String,
+    page_size: u32,
+    padding: u32,
+    generate_mips: bool,
+    images: Vec<(String, image::RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new<T>(name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            page_size: 2048,
+            padding: 2,
+            generate_mips: false,
+            images: Vec::new(),
+        }
+    }
+
+    /// Maximum width and height of a page. Pages are shrunk to the next power of two that fits
+    /// what's on them, or to `page_size` when that's smaller.
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_mipmaps(mut self, generate_mips: bool) -> Self {
+        self.generate_mips = generate_mips;
+        self
+    }
+
+    /// Region names have to be unique within an atlas.
+    pub fn add_image<T>(&mut self, name: T, image: image::RgbaImage) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        let name = name.into();
+        if self.images.iter().any(|(existing, _)| *existing == name) {
+            panic!("Atlas: {} already has a region named {}", self.name, name);
+        }
+        self.images.push((name, image));
+        self
+    }
+
+    /// Adds an image file. The region is named after the file name.
+    pub fn add_file<T>(&mut self, path: T) -> &mut Self
+    where
+        T: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        self.add_file_named(path, name)
+    }
+
+    /// Adds every png and jpg in a directory and its sub directories. Regions are named after
+    /// the file's path relative to `path` with `/` separators, like `buttons/ok.png`, so files
+    /// with the same name in different folders don't clash.
+    pub fn add_directory<T>(&mut self, path: T) -> &mut Self
+    where
+        T: AsRef<Path>,
+    {
+        let root = path.as_ref();
+        for entry in WalkDir::new(root) {
+            let entry = entry.expect("Error: Could not access file.");
+            let file_name = entry.file_name().to_str().unwrap();
+            if file_name.ends_with(".png") || file_name.ends_with(".jpg") {
+                let name = entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .iter()
+                    .map(|component| component.to_str().unwrap())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                self.add_file_named(entry.path(), name);
+            }
+        }
+        self
+    }
+
+    fn add_file_named(&mut self, path: &Path, name: String) -> &mut Self {
+        let image = image::open(path)
+            .unwrap_or_else(|_| panic!("Atlas: Unable to open the file: {}", path.display()))
+            .to_rgba();
+        self.add_image(name, image)
+    }
+
+    pub fn build(mut self) -> TextureAtlas {
+        self.images
+            .sort_by(|(_, a), (_, b)| b.height().cmp(&a.height()));
+
+        let padding = self.padding;
+        let mut placements: Vec<Vec<(String, image::RgbaImage, u32, u32)>> = vec![Vec::new()];
+        let mut page_sizes = vec![(0, 0)];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for (name, image) in self.images.drain(..) {
+            let width = image.width() + padding * 2;
+            let height = image.height() + padding * 2;
+
+            if width > self.page_size || height > self.page_size {
+                log::warn!(
+                    "Atlas: {} is bigger than the page size of {}, it gets a page of its own.",
+                    name,
+                    self.page_size
+                );
+                placements.push(vec![(name, image, padding, padding)]);
+                page_sizes.push((width, height));
+                placements.push(Vec::new());
+                page_sizes.push((0, 0));
+                x = 0;
+                y = 0;
+                shelf_height = 0;
+                continue;
+            }
+
+            // Start a new shelf when the current one is full, and a new page when the shelves are.
+            if x + width > self.page_size {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            if y + height > self.page_size {
+                placements.push(Vec::new());
+                page_sizes.push((0, 0));
+                x = 0;
+                y = 0;
+                shelf_height = 0;
+            }
+
+            let page_size = page_sizes.last_mut().unwrap();
+            page_size.0 = page_size.0.max(x + width);
+            page_size.1 = page_size.1.max(y + height);
+            placements
+                .last_mut()
+                .unwrap()
+                .push((name, image, x + padding, y + padding));
+
+            x += width;
+            shelf_height = shelf_height.max(height);
+        }
+
+        let mut pages = Vec::new();
+        let mut regions = HashMap::new();
+        for (placement, (width, height)) in placements.into_iter().zip(page_sizes.into_iter()) {
+            if placement.is_empty() {
+                continue;
+            }
+
+            let page_name = format!("{}_{}.atlas.png", self.name, pages.len());
+            let (width, height) = (self.fit_page(width), self.fit_page(height));
+            let mut page = image::RgbaImage::new(width, height);
+            for (name, image, x, y) in placement {
+                Self::copy_extruded(&mut page, &image, x, y, padding);
+                regions.insert(
+                    name,
+                    AtlasRegion {
+                        page: page_name.clone(),
+                        x,
+                        y,
+                        width: image.width(),
+                        height: image.height(),
+                        uv_rect: [
+                            x as f32 / width as f32,
+                            y as f32 / height as f32,
+                            image.width() as f32 / width as f32,
+                            image.height() as f32 / height as f32,
+                        ],
+                    },
+                );
+            }
+            pages.push((page_name, page));
+        }
+
+        TextureAtlas {
+            name: self.name,
+            generate_mips: self.generate_mips,
+            pages,
+            regions,
+        }
+    }
+
+    // Only images bigger than the page size go past it.
+    fn fit_page(&self, size: u32) -> u32 {
+        if size > self.page_size {
+            size.next_power_of_two()
+        } else {
+            size.next_power_of_two().min(self.page_size)
+        }
+    }
+
+    // Copies `image` to `x`, `y` and fills the `padding` pixels around it with the closest
+    // border texel.
+    fn copy_extruded(
+        page: &mut image::RgbaImage,
+        image: &image::RgbaImage,
+        x: u32,
+        y: u32,
+        padding: u32,
+    ) {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+        for page_y in y - padding..y + height + padding {
+            for page_x in x - padding..x + width + padding {
+                let image_x = page_x.max(x).min(x + width - 1) - x;
+                let image_y = page_y.max(y).min(y + height - 1) - y;
+                page.put_pixel(page_x, page_y, *image.get_pixel(image_x, image_y));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(page_size: u32, sizes: &[(u32, u32)]) -> TextureAtlas {
+        let mut builder = AtlasBuilder::new("test").with_page_size(page_size);
+        for (index, (width, height)) in sizes.iter().enumerate() {
+            builder.add_image(index.to_string(), image::RgbaImage::new(*width, *height));
+        }
+        builder.build()
+    }
+
+    fn page_sizes(atlas: &TextureAtlas) -> Vec<(u32, u32)> {
+        atlas
+            .pages
+            .iter()
+            .map(|(_, page)| page.dimensions())
+            .collect()
+    }
+
+    fn position(atlas: &TextureAtlas, name: &str) -> (String, u32, u32) {
+        let region = &atlas.regions[name];
+        (region.page.clone(), region.x, region.y)
+    }
+
+    #[test]
+    fn shelves_fill_left_to_right_then_top_to_bottom() {
+        // With the default padding of 2 every image takes up 32 by 32 pixels.
+        let atlas = build(64, &[(28, 28); 4]);
+        assert_eq!(page_sizes(&atlas), vec![(64, 64)]);
+        let page = "test_0.atlas.png".to_string();
+        assert_eq!(position(&atlas, "0"), (page.clone(), 2, 2));
+        assert_eq!(position(&atlas, "1"), (page.clone(), 34, 2));
+        assert_eq!(position(&atlas, "2"), (page.clone(), 2, 34));
+        assert_eq!(position(&atlas, "3"), (page, 34, 34));
+        assert_eq!(
+            atlas.regions["3"].uv_rect,
+            [34.0 / 64.0, 34.0 / 64.0, 28.0 / 64.0, 28.0 / 64.0]
+        );
+    }
+
+    #[test]
+    fn tallest_images_are_placed_first() {
+        let atlas = build(64, &[(12, 12), (28, 28)]);
+        assert_eq!(position(&atlas, "1").1, 2);
+        assert_eq!(position(&atlas, "0").1, 34);
+        assert_eq!(page_sizes(&atlas), vec![(64, 32)]);
+    }
+
+    #[test]
+    fn full_pages_start_a_new_one() {
+        let atlas = build(64, &[(28, 28); 5]);
+        assert_eq!(page_sizes(&atlas), vec![(64, 64), (32, 32)]);
+        assert_eq!(
+            position(&atlas, "4"),
+            ("test_1.atlas.png".to_string(), 2, 2)
+        );
+    }
+
+    #[test]
+    fn oversized_images_get_a_page_of_their_own() {
+        let atlas = build(64, &[(100, 20), (28, 10), (28, 10)]);
+        assert_eq!(page_sizes(&atlas), vec![(128, 32), (64, 16)]);
+        assert_eq!(atlas.regions["0"].page, "test_0.atlas.png");
+        assert_eq!(atlas.regions["1"].page, "test_1.atlas.png");
+        assert_eq!(atlas.regions["2"].page, "test_1.atlas.png");
+    }
+
+    #[test]
+    fn pages_stay_within_a_page_size_that_isnt_a_power_of_two() {
+        let atlas = build(48, &[(20, 20); 4]);
+        assert_eq!(page_sizes(&atlas), vec![(48, 48)]);
+        assert_eq!(
+            atlas.regions["3"].uv_rect,
+            [26.0 / 48.0, 26.0 / 48.0, 20.0 / 48.0, 20.0 / 48.0]
+        );
+    }
+
+    #[test]
+    fn padding_repeats_the_border_texels() {
+        let mut image = image::RgbaImage::new(2, 2);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, image::Rgba([0, 255, 0, 255]));
+        image.put_pixel(0, 1, image::Rgba([0, 0, 255, 255]));
+        image.put_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+        let mut builder = AtlasBuilder::new("test");
+        builder.add_image("image", image.clone());
+        let atlas = builder.build();
+
+        let page = &atlas.pages[0].1;
+        assert_eq!(page.dimensions(), (8, 8));
+        for y in 0..6 {
+            for x in 0..6 {
+                let expected = image.get_pixel(x.max(2).min(3) - 2, y.max(2).min(3) - 2);
+                assert_eq!(page.get_pixel(x, y), expected, "pixel {}, {}", x, y);
+            }
+        }
+        assert_eq!(page.get_pixel(6, 6), &image::Rgba([0, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{
+    graphics::mesh::Mesh as MeshAsset,
+    scene::components::{Mesh, Parent, Transform},
+};
+use nalgebra_glm::{Quat, Vec3};
+use specs::{Builder, Entity, World, WorldExt};
+
+/// A node of a glTF file along with its local transform.
+#[derive(Debug, Clone)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    /// Name of the mesh in the asset manager, see `Mesh::gltf_mesh_name`. Each of its sub
+    /// meshes is drawn with the material of its glTF primitive.
+    pub mesh_name: Option<String>,
+    /// Indices into `GltfScene::nodes`.
+    pub children: Vec<usize>,
+}
+
+/// The node hierarchy of a glTF file's default scene, or of its first scene when it doesn't
+/// name one.
+#[derive(Debug, Clone, Default)]
+pub struct GltfScene {
+    /// Indices into `nodes` of the nodes without a parent.
+    pub roots: Vec<usize>,
+    /// Every node of the file in file order.
+    pub nodes: Vec<GltfNode>,
+}
+
+impl GltfScene {
+    pub(crate) fn new(document: &gltf::Document, file_name: &str) -> Self {
+        let nodes = document
+            .nodes()
+            .map(|node| {
+                let (position, rotation, scale) = node.transform().decomposed();
+                GltfNode {
+                    name: node.name().map(|name| name.to_string()),
+                    position: Vec3::from(position),
+                    rotation: nalgebra_glm::quat(
+                        rotation[0],
+                        rotation[1],
+                        rotation[2],
+                        rotation[3],
+                    ),
+                    scale: Vec3::from(scale),
+                    mesh_name: node
+                        .mesh()
+                        .map(|mesh| MeshAsset::gltf_mesh_name(file_name, mesh.index())),
+                    children: node.children().map(|child| child.index()).collect(),
+                }
+            })
+            .collect();
+
+        let roots = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .map(|scene| scene.nodes().map(|node| node.index()).collect())
+            .unwrap_or_default();
+
+        Self { roots, nodes }
+    }
+
+    /// Creates an entity with a `Transform` for every node, plus a `Mesh` for the ones with a
+    /// mesh. They don't get a `Material` so every sub mesh keeps its own. Children get a
+    /// `Parent` pointing at their parent's entity. Returns the entities of the root nodes.
+    pub fn spawn(&self, world: &mut World) -> Vec<Entity> {
+        self.roots
+            .iter()
+            .map(|root| self.spawn_node(world, *root, None))
+            .collect()
+    }
+
+    fn spawn_node(&self, world: &mut World, index: usize, parent: Option<Entity>) -> Entity {
+        let node = &self.nodes[index];
+        let mut transform = Transform::new();
+        transform.position = node.position;
+        transform.rotation = node.rotation;
+        transform.scale = node.scale;
+
+        let mut builder = world.create_entity().with(transform);
+        if let Some(mesh_name) = node.mesh_name.as_ref() {
+            builder = builder.with(Mesh::new(mesh_name.clone()));
+        }
+        if let Some(parent) = parent {
+            builder = builder.with(Parent::new(parent));
+        }
+        let entity = builder.build();
+
+        for child in node.children.iter() {
+            self.spawn_node(world, *child, Some(entity));
+        }
+        entity
+    }
+}
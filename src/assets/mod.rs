@@ -1,2 +1,8 @@
 mod asset_manager;
 pub use asset_manager::AssetManager;
+
+mod atlas;
+pub use atlas::{AtlasBuilder, AtlasRegion, TextureAtlas};
+
+mod gltf_scene;
+pub use gltf_scene::{GltfNode, GltfScene};
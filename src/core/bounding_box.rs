@@ -0,0 +1,80 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+/// An axis aligned box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    /// A box that contains nothing. Growing it by any point gives a box around that point.
+    pub fn empty() -> Self {
+        Self {
+            min: Vec3::new(std::f32::MAX, std::f32::MAX, std::f32::MAX),
+            max: Vec3::new(std::f32::MIN, std::f32::MIN, std::f32::MIN),
+        }
+    }
+
+    pub fn from_points<'a, T>(points: T) -> Self
+    where
+        T: IntoIterator<Item = &'a Vec3>,
+    {
+        let mut bounds = Self::empty();
+        for point in points {
+            bounds.grow(point);
+        }
+        bounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn grow(&mut self, point: &Vec3) {
+        self.min = nalgebra_glm::min2(&self.min, point);
+        self.max = nalgebra_glm::max2(&self.max, point);
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: nalgebra_glm::min2(&self.min, &other.min),
+            max: nalgebra_glm::max2(&self.max, &other.max),
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The box around this box once it has been moved by `matrix`.
+    pub fn transform(&self, matrix: &Mat4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut bounds = Self::empty();
+        for corner in 0..8 {
+            let point = Vec4::new(
+                if corner & 1 == 0 {
+                    self.min.x
+                } else {
+                    self.max.x
+                },
+                if corner & 2 == 0 {
+                    self.min.y
+                } else {
+                    self.max.y
+                },
+                if corner & 4 == 0 {
+                    self.min.z
+                } else {
+                    self.max.z
+                },
+                1.0,
+            );
+            let point = matrix * point;
+            bounds.grow(&point.xyz());
+        }
+        bounds
+    }
+}
@@ -17,16 +17,18 @@ pub enum MouseButton {
     X2,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Input {
     keys_down: HashSet<VirtualKeyCode>,
     keys_pressed: HashSet<VirtualKeyCode>,
+    keys_just_pressed: HashSet<VirtualKeyCode>,
     keys_released: HashSet<VirtualKeyCode>,
 
     mouse_buttons_down: HashSet<MouseButton>,
     mouse_buttons_pressed: HashSet<MouseButton>,
     mouse_buttons_released: HashSet<MouseButton>,
     mouse_position: Vec2,
+    mouse_delta: Vec2,
     mouse_wheel_movement: Vec2,
     // current_text_input: Option<String>,
 
@@ -38,12 +40,14 @@ impl Input {
         Self {
             keys_down: HashSet::new(),
             keys_pressed: HashSet::new(),
+            keys_just_pressed: HashSet::new(),
             keys_released: HashSet::new(),
 
             mouse_buttons_down: HashSet::new(),
             mouse_buttons_pressed: HashSet::new(),
             mouse_buttons_released: HashSet::new(),
             mouse_position: Vec2::zeros(),
+            mouse_delta: Vec2::zeros(),
             mouse_wheel_movement: Vec2::zeros(),
             // current_text_input: None,
 
@@ -55,39 +59,122 @@ impl Input {
         self.keys_down.contains(&key)
     }
 
+    /// Includes the presses key repeat sends while the key is held, which suits text input.
     pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
         self.keys_pressed.contains(&key)
     }
 
+    /// Only true on the update the key went down, key repeat is ignored.
+    pub fn is_key_just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
     pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
         self.keys_released.contains(&key)
     }
 
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&button)
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_released.contains(&button)
+    }
+
+    /// Cursor position in physical pixels from the top left of the window.
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// Raw mouse movement since the last update. Keeps working when the cursor is grabbed.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Scroll since the last update in lines, positive y scrolls up.
+    pub fn mouse_wheel_movement(&self) -> Vec2 {
+        self.mouse_wheel_movement
+    }
+
     pub(crate) fn update_events(&mut self, event: &winit::event::Event<'_, ()>) {
         match event {
             winit::event::Event::WindowEvent { event, .. } => match event {
                 winit::event::WindowEvent::KeyboardInput { input, .. } => {
                     if input.state == winit::event::ElementState::Pressed {
                         if input.virtual_keycode.is_some() {
-                            self.keys_pressed.insert(input.virtual_keycode.unwrap());
+                            let key = input.virtual_keycode.unwrap();
+                            self.keys_pressed.insert(key);
+                            // Key repeat sends more presses while the key is held.
+                            if self.keys_down.insert(key) {
+                                self.keys_just_pressed.insert(key);
+                            }
                         }
                     } else if input.state == winit::event::ElementState::Released {
                         if input.virtual_keycode.is_some() {
-                            self.keys_released.insert(input.virtual_keycode.unwrap());
+                            let key = input.virtual_keycode.unwrap();
+                            self.keys_down.remove(&key);
+                            self.keys_released.insert(key);
                         }
                     }
                 }
+                winit::event::WindowEvent::MouseInput { state, button, .. } => {
+                    let button = match button {
+                        winit::event::MouseButton::Left => MouseButton::Left,
+                        winit::event::MouseButton::Middle => MouseButton::Middle,
+                        winit::event::MouseButton::Right => MouseButton::Right,
+                        winit::event::MouseButton::Other(1) => MouseButton::X1,
+                        winit::event::MouseButton::Other(2) => MouseButton::X2,
+                        winit::event::MouseButton::Other(_) => return,
+                    };
+                    if *state == winit::event::ElementState::Pressed {
+                        self.mouse_buttons_down.insert(button);
+                        self.mouse_buttons_pressed.insert(button);
+                    } else {
+                        self.mouse_buttons_down.remove(&button);
+                        self.mouse_buttons_released.insert(button);
+                    }
+                }
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    self.mouse_position = Vec2::new(position.x as f32, position.y as f32);
+                }
+                winit::event::WindowEvent::MouseWheel { delta, .. } => match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                        self.mouse_wheel_movement += Vec2::new(*x, *y);
+                    }
+                    winit::event::MouseScrollDelta::PixelDelta(position) => {
+                        // Roughly one line per 20 pixels, trackpads report pixels.
+                        self.mouse_wheel_movement +=
+                            Vec2::new(position.x as f32, position.y as f32) / 20.0;
+                    }
+                },
+                // Nothing is held down anymore once the window loses focus.
+                winit::event::WindowEvent::Focused(false) => {
+                    self.keys_down.clear();
+                    self.mouse_buttons_down.clear();
+                }
                 _ => (),
             },
+            winit::event::Event::DeviceEvent {
+                event: winit::event::DeviceEvent::MouseMotion { delta },
+                ..
+            } => {
+                self.mouse_delta += Vec2::new(delta.0 as f32, delta.1 as f32);
+            }
             _ => (),
         }
     }
 
     pub(crate) fn clear(&mut self) {
         self.keys_pressed.clear();
+        self.keys_just_pressed.clear();
         self.keys_released.clear();
         self.mouse_buttons_pressed.clear();
         self.mouse_buttons_released.clear();
+        self.mouse_delta = Vec2::zeros();
         self.mouse_wheel_movement = Vec2::zeros();
     }
 }
@@ -1 +1,7 @@
 pub mod input;
+
+mod bounding_box;
+pub use bounding_box::BoundingBox;
+
+mod ray;
+pub use ray::Ray;
@@ -0,0 +1,86 @@
+use super::BoundingBox;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Always normalized.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn point_at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Moves the ray by `matrix`. The direction is normalized again, so distances along the
+    /// new ray are in the new space.
+    pub fn transform(&self, matrix: &Mat4) -> Ray {
+        let origin = matrix * Vec4::new(self.origin.x, self.origin.y, self.origin.z, 1.0);
+        let direction =
+            matrix * Vec4::new(self.direction.x, self.direction.y, self.direction.z, 0.0);
+        Ray::new(origin.xyz(), direction.xyz())
+    }
+
+    /// Distance to where the ray enters the box, zero when it starts inside of it.
+    pub fn intersect_bounding_box(&self, bounds: &BoundingBox) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = std::f32::MAX;
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / self.direction[axis];
+            let mut t0 = (bounds.min[axis] - self.origin[axis]) * inverse_direction;
+            let mut t1 = (bounds.max[axis] - self.origin[axis]) * inverse_direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN happens when the ray is parallel to a slab and starts on its edge.
+            if !t0.is_nan() {
+                near = near.max(t0);
+            }
+            if !t1.is_nan() {
+                far = far.min(t1);
+            }
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+
+    /// Distance to the triangle, both sides of the triangle are hit.
+    pub fn intersect_triangle(&self, a: &Vec3, b: &Vec3, c: &Vec3) -> Option<f32> {
+        // Möller–Trumbore
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+        let p = self.direction.cross(&edge_2);
+        let determinant = edge_1.dot(&p);
+        if determinant.abs() < std::f32::EPSILON {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let t = self.origin - a;
+        let u = t.dot(&p) * inverse_determinant;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+        let q = t.cross(&edge_1);
+        let v = self.direction.dot(&q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_2.dot(&q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+        Some(distance)
+    }
+}
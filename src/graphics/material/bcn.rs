@@ -0,0 +1,1367 @@
+//! Cpu decoders for block compressed (BCn) textures. Used when block compressed data can't be
+//! uploaded as is, every format decodes to 8 bit rgba except BC6H which decodes to 32 bit floats.
+
+/// The block compressed formats KTX2 and DDS files can carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BlockFormat {
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4 { signed: bool },
+    Bc5 { signed: bool },
+    Bc6h { signed: bool },
+    Bc7,
+}
+
+impl BlockFormat {
+    /// Bytes in one 4x4 block.
+    pub(crate) fn block_size(&self) -> usize {
+        match self {
+            BlockFormat::Bc1 | BlockFormat::Bc4 { .. } => 8,
+            _ => 16,
+        }
+    }
+
+    /// Bytes needed for a whole level of the given size.
+    pub(crate) fn level_size(&self, width: u32, height: u32) -> usize {
+        let blocks_x = ((width + 3) / 4).max(1) as usize;
+        let blocks_y = ((height + 3) / 4).max(1) as usize;
+        blocks_x * blocks_y * self.block_size()
+    }
+
+    /// The texture format `decode` produces. Only color formats can be srgb.
+    pub(crate) fn decoded_format(&self, srgb: bool) -> wgpu::TextureFormat {
+        match self {
+            BlockFormat::Bc6h { .. } => wgpu::TextureFormat::Rgba32Float,
+            BlockFormat::Bc4 { signed: true } | BlockFormat::Bc5 { signed: true } => {
+                wgpu::TextureFormat::Rgba8Snorm
+            }
+            BlockFormat::Bc4 { .. } | BlockFormat::Bc5 { .. } => wgpu::TextureFormat::Rgba8Unorm,
+            _ if srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            _ => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    pub(crate) fn decoded_bytes_per_pixel(&self) -> usize {
+        match self {
+            BlockFormat::Bc6h { .. } => 4 * 4,
+            _ => 4,
+        }
+    }
+}
+
+/// Decodes one level of block compressed data into tightly packed rows of pixels.
+/// Signed BC4 and BC5 data comes out as `i8` values stored in the bytes.
+pub(crate) fn decode(format: BlockFormat, data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let bytes_per_pixel = format.decoded_bytes_per_pixel();
+    let (width, height) = (width as usize, height as usize);
+    let blocks_x = ((width + 3) / 4).max(1);
+    let mut pixels = vec![0u8; width * height * bytes_per_pixel];
+
+    for (block_index, block) in data
+        .chunks_exact(format.block_size())
+        .take(format.level_size(width as u32, height as u32) / format.block_size())
+        .enumerate()
+    {
+        let block_x = (block_index % blocks_x) * 4;
+        let block_y = (block_index / blocks_x) * 4;
+
+        let mut ldr = [[0u8; 4]; 16];
+        let mut hdr = [[0f32; 4]; 16];
+        match format {
+            BlockFormat::Bc1 => decode_color_block(block, false, &mut ldr),
+            BlockFormat::Bc2 => {
+                decode_color_block(&block[8..], true, &mut ldr);
+                let alpha = u64::from_le_bytes(read_8(&block[..8]));
+                for (i, pixel) in ldr.iter_mut().enumerate() {
+                    pixel[3] = ((alpha >> (4 * i)) & 0xF) as u8 * 17;
+                }
+            }
+            BlockFormat::Bc3 => {
+                decode_color_block(&block[8..], true, &mut ldr);
+                let alpha = decode_alpha_block(&block[..8], false);
+                for (pixel, alpha) in ldr.iter_mut().zip(alpha.iter()) {
+                    pixel[3] = *alpha;
+                }
+            }
+            BlockFormat::Bc4 { signed } => {
+                let red = decode_alpha_block(block, signed);
+                for (pixel, red) in ldr.iter_mut().zip(red.iter()) {
+                    *pixel = [*red, 0, 0, opaque(signed)];
+                }
+            }
+            BlockFormat::Bc5 { signed } => {
+                let red = decode_alpha_block(&block[..8], signed);
+                let green = decode_alpha_block(&block[8..], signed);
+                for (i, pixel) in ldr.iter_mut().enumerate() {
+                    *pixel = [red[i], green[i], 0, opaque(signed)];
+                }
+            }
+            BlockFormat::Bc6h { signed } => decode_bc6h_block(block, signed, &mut hdr),
+            BlockFormat::Bc7 => decode_bc7_block(block, &mut ldr),
+        }
+
+        // Blocks on the right and bottom edges can hang over the image.
+        for y in 0..4 {
+            for x in 0..4 {
+                let (pixel_x, pixel_y) = (block_x + x, block_y + y);
+                if pixel_x >= width || pixel_y >= height {
+                    continue;
+                }
+                let offset = (pixel_y * width + pixel_x) * bytes_per_pixel;
+                let target = &mut pixels[offset..offset + bytes_per_pixel];
+                match format {
+                    BlockFormat::Bc6h { .. } => {
+                        target.copy_from_slice(bytemuck::cast_slice(&hdr[y * 4 + x]))
+                    }
+                    _ => target.copy_from_slice(&ldr[y * 4 + x]),
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+fn opaque(signed: bool) -> u8 {
+    if signed {
+        127
+    } else {
+        255
+    }
+}
+
+fn read_8(bytes: &[u8]) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&bytes[..8]);
+    out
+}
+
+fn expand_565(color: u16) -> [u8; 4] {
+    let r = ((color >> 11) & 31) as u8;
+    let g = ((color >> 5) & 63) as u8;
+    let b = (color & 31) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+        255,
+    ]
+}
+
+// BC2 and BC3 always use four colors, BC1 switches to three colors and transparent black
+// when the first endpoint isn't larger than the second.
+fn decode_color_block(block: &[u8], always_four_colors: bool, pixels: &mut [[u8; 4]; 16]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let e0 = expand_565(c0);
+    let e1 = expand_565(c1);
+
+    let mut palette = [e0, e1, [0; 4], [0; 4]];
+    for channel in 0..3 {
+        let (a, b) = (e0[channel] as u32, e1[channel] as u32);
+        if always_four_colors || c0 > c1 {
+            palette[2][channel] = ((2 * a + b) / 3) as u8;
+            palette[3][channel] = ((a + 2 * b) / 3) as u8;
+        } else {
+            palette[2][channel] = ((a + b) / 2) as u8;
+        }
+    }
+    palette[2][3] = 255;
+    if always_four_colors || c0 > c1 {
+        palette[3][3] = 255;
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (2 * i)) & 3) as usize];
+    }
+}
+
+// The BC3 alpha block, also used for every channel of BC4 and BC5.
+fn decode_alpha_block(block: &[u8], signed: bool) -> [u8; 16] {
+    let (a0, a1) = if signed {
+        (
+            (block[0] as i8).max(-127) as i32,
+            (block[1] as i8).max(-127) as i32,
+        )
+    } else {
+        (block[0] as i32, block[1] as i32)
+    };
+    let (min, max) = if signed { (-127, 127) } else { (0, 255) };
+
+    let mut palette = [a0, a1, 0, 0, 0, 0, min, max];
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i as i32) * a0 + i as i32 * a1) / 7;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i as i32) * a0 + i as i32 * a1) / 5;
+        }
+    }
+
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    let mut values = [0u8; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * i)) & 7) as usize] as u8;
+    }
+    values
+}
+
+struct BitReader {
+    bits: u128,
+    offset: u32,
+}
+
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&block[..16]);
+        Self {
+            bits: u128::from_le_bytes(bytes),
+            offset: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = (self.bits >> self.offset) & ((1u128 << count) - 1);
+        self.offset += count;
+        value as u32
+    }
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+fn subset_of(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => ((PARTITIONS_2[partition] >> pixel) & 1) as usize,
+        3 => PARTITIONS_3[partition][pixel] as usize,
+        _ => 0,
+    }
+}
+
+// Anchor pixels store their index with one bit less, the missing top bit is always zero.
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    match (subsets, subset_of(subsets, partition, pixel)) {
+        (_, 0) => pixel == 0,
+        (2, _) => pixel == ANCHORS_2[partition],
+        (_, 1) => pixel == ANCHORS_3_SECOND[partition],
+        _ => pixel == ANCHORS_3_THIRD[partition],
+    }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 4,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 4,
+        alpha_bits: 0,
+        endpoint_p_bits: true,
+        shared_p_bits: false,
+        index_bits: 3,
+        secondary_index_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 6,
+        alpha_bits: 0,
+        endpoint_p_bits: false,
+        shared_p_bits: true,
+        index_bits: 3,
+        secondary_index_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 0,
+        endpoint_p_bits: false,
+        shared_p_bits: false,
+        index_bits: 2,
+        secondary_index_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 0,
+        endpoint_p_bits: true,
+        shared_p_bits: false,
+        index_bits: 2,
+        secondary_index_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 1,
+        color_bits: 5,
+        alpha_bits: 6,
+        endpoint_p_bits: false,
+        shared_p_bits: false,
+        index_bits: 2,
+        secondary_index_bits: 3,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 8,
+        endpoint_p_bits: false,
+        shared_p_bits: false,
+        index_bits: 2,
+        secondary_index_bits: 2,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 7,
+        endpoint_p_bits: true,
+        shared_p_bits: false,
+        index_bits: 4,
+        secondary_index_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 5,
+        endpoint_p_bits: true,
+        shared_p_bits: false,
+        index_bits: 2,
+        secondary_index_bits: 0,
+    },
+];
+
+fn expand_bits(value: u32, bits: u32) -> u8 {
+    if bits >= 8 {
+        return value as u8;
+    }
+    let value = value << (8 - bits);
+    (value | (value >> bits)) as u8
+}
+
+fn interpolate(e0: u32, e1: u32, weight: u32) -> u8 {
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+fn decode_bc7_block(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    let mut bits = BitReader::new(block);
+    // The mode is the number of zero bits before the first set bit, eight zeros is invalid.
+    let mode = match (0..8).find(|_| bits.read(1) == 1) {
+        Some(mode) => &BC7_MODES[mode],
+        None => {
+            *pixels = [[0; 4]; 16];
+            return;
+        }
+    };
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        endpoint[3] = bits.read(mode.alpha_bits);
+    }
+
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let mut p_bit = 0;
+        for (i, endpoint) in endpoints.iter_mut().take(endpoint_count).enumerate() {
+            if mode.endpoint_p_bits || i % 2 == 0 {
+                p_bit = bits.read(1);
+            }
+            for value in endpoint.iter_mut() {
+                *value = (*value << 1) | p_bit;
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for channel in 0..3 {
+            endpoint[channel] = expand_bits(endpoint[channel], color_bits) as u32;
+        }
+        endpoint[3] = if alpha_bits > 0 {
+            expand_bits(endpoint[3], alpha_bits) as u32
+        } else {
+            255
+        };
+    }
+
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, pixel) as u32;
+        *index = bits.read(mode.index_bits - anchor);
+    }
+    let mut secondary_indices = [0u32; 16];
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - (pixel == 0) as u32);
+        }
+    }
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let subset = subset_of(mode.subsets, partition, i);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let (color_weight, alpha_weight) = if mode.secondary_index_bits == 0 {
+            let weight = weights(mode.index_bits)[indices[i] as usize];
+            (weight, weight)
+        } else if index_selection == 0 {
+            (
+                weights(mode.index_bits)[indices[i] as usize],
+                weights(mode.secondary_index_bits)[secondary_indices[i] as usize],
+            )
+        } else {
+            (
+                weights(mode.secondary_index_bits)[secondary_indices[i] as usize],
+                weights(mode.index_bits)[indices[i] as usize],
+            )
+        };
+
+        for channel in 0..3 {
+            pixel[channel] = interpolate(e0[channel], e1[channel], color_weight);
+        }
+        pixel[3] = interpolate(e0[3], e1[3], alpha_weight);
+
+        // Modes 4 and 5 can store one of the color channels in the alpha slot.
+        match rotation {
+            1 => pixel.swap(0, 3),
+            2 => pixel.swap(1, 3),
+            3 => pixel.swap(2, 3),
+            _ => {}
+        }
+    }
+}
+
+// Endpoint fields in the order BC6H stores them: w and x are the first subset's endpoints,
+// y and z the second subset's.
+const RW: usize = 0;
+const GW: usize = 1;
+const BW: usize = 2;
+const RX: usize = 3;
+const GX: usize = 4;
+const BX: usize = 5;
+const RY: usize = 6;
+const GY: usize = 7;
+const BY: usize = 8;
+const RZ: usize = 9;
+const GZ: usize = 10;
+const BZ: usize = 11;
+
+struct Bc6hMode {
+    /// Endpoints other than the first are stored as deltas from it.
+    transformed: bool,
+    subsets: usize,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// (field, high bit, low bit) in the order the bits are stored.
+    layout: &'static [(usize, u32, u32)],
+}
+
+fn bc6h_mode(mode: u32) -> Option<Bc6hMode> {
+    let (transformed, subsets, endpoint_bits, delta_bits, layout) = match mode {
+        0x00 => (true, 2, 10, [5, 5, 5], BC6H_LAYOUT_0),
+        0x01 => (true, 2, 7, [6, 6, 6], BC6H_LAYOUT_1),
+        0x02 => (true, 2, 11, [5, 4, 4], BC6H_LAYOUT_2),
+        0x06 => (true, 2, 11, [4, 5, 4], BC6H_LAYOUT_3),
+        0x0A => (true, 2, 11, [4, 4, 5], BC6H_LAYOUT_4),
+        0x0E => (true, 2, 9, [5, 5, 5], BC6H_LAYOUT_5),
+        0x12 => (true, 2, 8, [6, 5, 5], BC6H_LAYOUT_6),
+        0x16 => (true, 2, 8, [5, 6, 5], BC6H_LAYOUT_7),
+        0x1A => (true, 2, 8, [5, 5, 6], BC6H_LAYOUT_8),
+        0x1E => (false, 2, 6, [6, 6, 6], BC6H_LAYOUT_9),
+        0x03 => (false, 1, 10, [10, 10, 10], BC6H_LAYOUT_10),
+        0x07 => (true, 1, 11, [9, 9, 9], BC6H_LAYOUT_11),
+        0x0B => (true, 1, 12, [8, 8, 8], BC6H_LAYOUT_12),
+        0x0F => (true, 1, 16, [4, 4, 4], BC6H_LAYOUT_13),
+        _ => return None,
+    };
+    Some(Bc6hMode {
+        transformed,
+        subsets,
+        endpoint_bits,
+        delta_bits,
+        layout,
+    })
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 || value == 0 {
+            value
+        } else if value == (1 << bits) - 1 {
+            0xFFFF
+        } else {
+            ((value << 16) + 0x8000) >> bits
+        }
+    } else {
+        if bits >= 16 {
+            return value;
+        }
+        let magnitude = value.abs();
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if value < 0 {
+            -unquantized
+        } else {
+            unquantized
+        }
+    }
+}
+
+// Scales an interpolated value to the bits of a half float.
+fn bc6h_finish(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        (((-value * 31) >> 5) as u16) | 0x8000
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+fn half_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        (0, _) => {
+            // Subnormal halves are normal floats.
+            let shift = mantissa.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | (((mantissa << shift) & 0x3FF) << 13)
+        }
+        (0x1F, _) => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn decode_bc6h_block(block: &[u8], signed: bool, pixels: &mut [[f32; 4]; 16]) {
+    let mut bits = BitReader::new(block);
+    let mut mode_bits = bits.read(2);
+    if mode_bits > 1 {
+        mode_bits |= bits.read(3) << 2;
+    }
+    // Reserved modes decode to black.
+    let mode = match bc6h_mode(mode_bits) {
+        Some(mode) => mode,
+        None => {
+            *pixels = [[0.0, 0.0, 0.0, 1.0]; 16];
+            return;
+        }
+    };
+
+    let mut fields = [0i32; 12];
+    for &(field, high, low) in mode.layout {
+        for bit in low..=high {
+            fields[field] |= (bits.read(1) as i32) << bit;
+        }
+    }
+    let partition = if mode.subsets == 2 {
+        bits.read(5) as usize
+    } else {
+        0
+    };
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0i32; 3]; 4];
+    for (i, endpoint) in endpoints.iter_mut().take(endpoint_count).enumerate() {
+        for channel in 0..3 {
+            let mut value = fields[i * 3 + channel];
+            if i == 0 || !mode.transformed {
+                if signed {
+                    value = sign_extend(value, mode.endpoint_bits);
+                }
+            } else {
+                value = sign_extend(value, mode.delta_bits[channel]);
+                value = (value + fields[channel]) & ((1 << mode.endpoint_bits) - 1);
+                if signed {
+                    value = sign_extend(value, mode.endpoint_bits);
+                }
+            }
+            endpoint[channel] = bc6h_unquantize(value, mode.endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if mode.subsets == 2 { 3 } else { 4 };
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, i) as u32;
+        let weight = weights(index_bits)[bits.read(index_bits - anchor) as usize] as i32;
+        let subset = subset_of(mode.subsets, partition, i);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        for channel in 0..3 {
+            let value = ((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6;
+            pixel[channel] = half_to_f32(bc6h_finish(value, signed));
+        }
+        pixel[3] = 1.0;
+    }
+}
+
+// Bit i is set when pixel i belongs to the second subset.
+const PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+const ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const ANCHORS_3_SECOND: [usize; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+const ANCHORS_3_THIRD: [usize; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+const BC6H_LAYOUT_0: &[(usize, u32, u32)] = &[
+    (GY, 4, 4),
+    (BY, 4, 4),
+    (BZ, 4, 4),
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 4, 0),
+    (GZ, 4, 4),
+    (GY, 3, 0),
+    (GX, 4, 0),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 4, 0),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 4, 0),
+    (BZ, 2, 2),
+    (RZ, 4, 0),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_1: &[(usize, u32, u32)] = &[
+    (GY, 5, 5),
+    (GZ, 4, 4),
+    (GZ, 5, 5),
+    (RW, 6, 0),
+    (BZ, 0, 0),
+    (BZ, 1, 1),
+    (BY, 4, 4),
+    (GW, 6, 0),
+    (BY, 5, 5),
+    (BZ, 2, 2),
+    (GY, 4, 4),
+    (BW, 6, 0),
+    (BZ, 3, 3),
+    (BZ, 5, 5),
+    (BZ, 4, 4),
+    (RX, 5, 0),
+    (GY, 3, 0),
+    (GX, 5, 0),
+    (GZ, 3, 0),
+    (BX, 5, 0),
+    (BY, 3, 0),
+    (RY, 5, 0),
+    (RZ, 5, 0),
+];
+const BC6H_LAYOUT_2: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 4, 0),
+    (RW, 10, 10),
+    (GY, 3, 0),
+    (GX, 3, 0),
+    (GW, 10, 10),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 3, 0),
+    (BW, 10, 10),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 4, 0),
+    (BZ, 2, 2),
+    (RZ, 4, 0),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_3: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 3, 0),
+    (RW, 10, 10),
+    (GZ, 4, 4),
+    (GY, 3, 0),
+    (GX, 4, 0),
+    (GW, 10, 10),
+    (GZ, 3, 0),
+    (BX, 3, 0),
+    (BW, 10, 10),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 3, 0),
+    (BZ, 0, 0),
+    (BZ, 2, 2),
+    (RZ, 3, 0),
+    (GY, 4, 4),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_4: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 3, 0),
+    (RW, 10, 10),
+    (BY, 4, 4),
+    (GY, 3, 0),
+    (GX, 3, 0),
+    (GW, 10, 10),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 4, 0),
+    (BW, 10, 10),
+    (BY, 3, 0),
+    (RY, 3, 0),
+    (BZ, 1, 1),
+    (BZ, 2, 2),
+    (RZ, 3, 0),
+    (BZ, 4, 4),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_5: &[(usize, u32, u32)] = &[
+    (RW, 8, 0),
+    (BY, 4, 4),
+    (GW, 8, 0),
+    (GY, 4, 4),
+    (BW, 8, 0),
+    (BZ, 4, 4),
+    (RX, 4, 0),
+    (GZ, 4, 4),
+    (GY, 3, 0),
+    (GX, 4, 0),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 4, 0),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 4, 0),
+    (BZ, 2, 2),
+    (RZ, 4, 0),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_6: &[(usize, u32, u32)] = &[
+    (RW, 7, 0),
+    (GZ, 4, 4),
+    (BY, 4, 4),
+    (GW, 7, 0),
+    (BZ, 2, 2),
+    (GY, 4, 4),
+    (BW, 7, 0),
+    (BZ, 3, 3),
+    (BZ, 4, 4),
+    (RX, 5, 0),
+    (GY, 3, 0),
+    (GX, 4, 0),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 4, 0),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 5, 0),
+    (RZ, 5, 0),
+];
+const BC6H_LAYOUT_7: &[(usize, u32, u32)] = &[
+    (RW, 7, 0),
+    (BZ, 0, 0),
+    (BY, 4, 4),
+    (GW, 7, 0),
+    (GY, 5, 5),
+    (GY, 4, 4),
+    (BW, 7, 0),
+    (GZ, 5, 5),
+    (BZ, 4, 4),
+    (RX, 4, 0),
+    (GZ, 4, 4),
+    (GY, 3, 0),
+    (GX, 5, 0),
+    (GZ, 3, 0),
+    (BX, 4, 0),
+    (BZ, 1, 1),
+    (BY, 3, 0),
+    (RY, 4, 0),
+    (BZ, 2, 2),
+    (RZ, 4, 0),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_8: &[(usize, u32, u32)] = &[
+    (RW, 7, 0),
+    (BZ, 1, 1),
+    (BY, 4, 4),
+    (GW, 7, 0),
+    (BY, 5, 5),
+    (GY, 4, 4),
+    (BW, 7, 0),
+    (BZ, 5, 5),
+    (BZ, 4, 4),
+    (RX, 4, 0),
+    (GZ, 4, 4),
+    (GY, 3, 0),
+    (GX, 4, 0),
+    (BZ, 0, 0),
+    (GZ, 3, 0),
+    (BX, 5, 0),
+    (BY, 3, 0),
+    (RY, 4, 0),
+    (BZ, 2, 2),
+    (RZ, 4, 0),
+    (BZ, 3, 3),
+];
+const BC6H_LAYOUT_9: &[(usize, u32, u32)] = &[
+    (RW, 5, 0),
+    (GZ, 4, 4),
+    (BZ, 0, 0),
+    (BZ, 1, 1),
+    (BY, 4, 4),
+    (GW, 5, 0),
+    (GY, 5, 5),
+    (BY, 5, 5),
+    (BZ, 2, 2),
+    (GY, 4, 4),
+    (BW, 5, 0),
+    (GZ, 5, 5),
+    (BZ, 3, 3),
+    (BZ, 5, 5),
+    (BZ, 4, 4),
+    (RX, 5, 0),
+    (GY, 3, 0),
+    (GX, 5, 0),
+    (GZ, 3, 0),
+    (BX, 5, 0),
+    (BY, 3, 0),
+    (RY, 5, 0),
+    (RZ, 5, 0),
+];
+const BC6H_LAYOUT_10: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 9, 0),
+    (GX, 9, 0),
+    (BX, 9, 0),
+];
+const BC6H_LAYOUT_11: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 8, 0),
+    (RW, 10, 10),
+    (GX, 8, 0),
+    (GW, 10, 10),
+    (BX, 8, 0),
+    (BW, 10, 10),
+];
+const BC6H_LAYOUT_12: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 7, 0),
+    (RW, 11, 11),
+    (RW, 10, 10),
+    (GX, 7, 0),
+    (GW, 11, 11),
+    (GW, 10, 10),
+    (BX, 7, 0),
+    (BW, 11, 11),
+    (BW, 10, 10),
+];
+const BC6H_LAYOUT_13: &[(usize, u32, u32)] = &[
+    (RW, 9, 0),
+    (GW, 9, 0),
+    (BW, 9, 0),
+    (RX, 3, 0),
+    (RW, 15, 15),
+    (RW, 14, 14),
+    (RW, 13, 13),
+    (RW, 12, 12),
+    (RW, 11, 11),
+    (RW, 10, 10),
+    (GX, 3, 0),
+    (GW, 15, 15),
+    (GW, 14, 14),
+    (GW, 13, 13),
+    (GW, 12, 12),
+    (GW, 11, 11),
+    (GW, 10, 10),
+    (BX, 3, 0),
+    (BW, 15, 15),
+    (BW, 14, 14),
+    (BW, 13, 13),
+    (BW, 12, 12),
+    (BW, 11, 11),
+    (BW, 10, 10),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The blocks were assembled field by field from the bit layouts in the BC7 and BC6H
+    // format documentation, using partition 0 and random endpoints and indices. The expected
+    // texels come from a separate implementation of the documented decoding.
+
+    fn block(hex: &str) -> [u8; 16] {
+        let mut block = [0u8; 16];
+        for (i, byte) in block.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        block
+    }
+
+    fn check_bc6h(cases: &[(&str, &[(usize, [f32; 3])])], signed: bool) {
+        for (hex, texels) in cases {
+            let mut pixels = [[0f32; 4]; 16];
+            decode_bc6h_block(&block(hex), signed, &mut pixels);
+            for (texel, rgb) in texels.iter() {
+                let expected = [rgb[0], rgb[1], rgb[2], 1.0];
+                assert_eq!(pixels[*texel], expected, "block {} texel {}", hex, texel);
+            }
+        }
+    }
+
+    #[test]
+    fn bc7_every_mode() {
+        let cases: &[(&str, &[(usize, [u8; 4])])] = &[
+            // Mode 0.
+            (
+                "6178c5abe96edff6bca2798262509099",
+                &[
+                    (0, [116, 156, 138, 255]),
+                    (3, [189, 123, 123, 255]),
+                    (5, [97, 178, 128, 255]),
+                    (10, [231, 181, 82, 255]),
+                    (15, [189, 200, 63, 255]),
+                ],
+            ),
+            // Mode 1.
+            (
+                "02d90253f43b67c6f06360858ec3ca10",
+                &[
+                    (0, [100, 209, 24, 255]),
+                    (3, [177, 190, 231, 255]),
+                    (5, [52, 192, 14, 255]),
+                    (10, [112, 130, 140, 255]),
+                    (15, [193, 205, 253, 255]),
+                ],
+            ),
+            // Mode 2.
+            (
+                "045c42d1a3b2c7734207a466cf61d866",
+                &[
+                    (0, [102, 100, 146, 255]),
+                    (3, [90, 56, 71, 255]),
+                    (5, [115, 41, 214, 255]),
+                    (10, [125, 48, 212, 255]),
+                    (15, [247, 115, 156, 255]),
+                ],
+            ),
+            // Mode 3.
+            (
+                "0870717639fd6ca842f273cb305a1acf",
+                &[
+                    (0, [185, 233, 33, 255]),
+                    (3, [236, 12, 230, 255]),
+                    (5, [113, 207, 243, 255]),
+                    (10, [236, 12, 230, 255]),
+                    (15, [233, 64, 169, 255]),
+                ],
+            ),
+            // Mode 4, red stored in the alpha slot and the index sets swapped.
+            (
+                "b0dab056c7f48a285c982106f3d07d5f",
+                &[
+                    (0, [77, 99, 173, 214]),
+                    (3, [114, 102, 110, 144]),
+                    (5, [114, 106, 45, 72]),
+                    (10, [153, 107, 24, 49]),
+                    (15, [190, 101, 131, 168]),
+                ],
+            ),
+            // Mode 5, green stored in the alpha slot.
+            (
+                "a07b739ab96de9fb3410c7902deabf82",
+                &[
+                    (0, [233, 250, 152, 192]),
+                    (3, [247, 250, 183, 211]),
+                    (5, [219, 124, 121, 172]),
+                    (10, [219, 62, 121, 172]),
+                    (15, [205, 124, 90, 153]),
+                ],
+            ),
+            // Mode 6.
+            (
+                "40872e3ea04901b5c29080911098220c",
+                &[
+                    (0, [34, 213, 109, 8]),
+                    (3, [81, 96, 140, 63]),
+                    (5, [75, 110, 136, 57]),
+                    (10, [75, 110, 136, 57]),
+                    (15, [29, 227, 105, 1]),
+                ],
+            ),
+            // Mode 7.
+            (
+                "80c09acb0d36b81462b3478e0f2598f7",
+                &[
+                    (0, [112, 49, 105, 106]),
+                    (3, [109, 164, 156, 52]),
+                    (5, [89, 24, 146, 97]),
+                    (10, [93, 28, 20, 36]),
+                    (15, [101, 95, 87, 44]),
+                ],
+            ),
+        ];
+        for (hex, texels) in cases {
+            let mut pixels = [[0u8; 4]; 16];
+            decode_bc7_block(&block(hex), &mut pixels);
+            for (texel, expected) in texels.iter() {
+                assert_eq!(pixels[*texel], *expected, "block {} texel {}", hex, texel);
+            }
+        }
+    }
+
+    #[test]
+    fn bc6h_every_mode_unsigned() {
+        check_bc6h(
+            &[
+                // Mode 0x0.
+                (
+                    "d0f2329687793534b31a307cc19e8537",
+                    &[
+                        (0, [7396.0, 0.0002617836, 23104.0]),
+                        (3, [6032.0, 0.00021600723, 20624.0]),
+                        (5, [7116.0, 0.0002732277, 23664.0]),
+                        (10, [6320.0, 0.00028181076, 24640.0]),
+                        (15, [6528.0, 0.0003504753, 27568.0]),
+                    ],
+                ),
+                // Mode 0x1.
+                (
+                    "a570ee1a5ea0c06a8f0950f9a5ad9bf8",
+                    &[
+                        (0, [8.1300735e-5, 179.5, 0.00030994415]),
+                        (3, [0.0014886856, 253.125, 7.480383e-5]),
+                        (5, [0.0003042221, 325.75, 0.0037593842]),
+                        (10, [0.0018882751, 686.0, 6.651878e-5]),
+                        (15, [0.0006117821, 13.9140625, 0.000100672245]),
+                    ],
+                ),
+                // Mode 0x2.
+                (
+                    "228096a083300b300f12581c728d833d",
+                    &[
+                        (0, [0.013069153, 33.0625, 0.0040245056]),
+                        (3, [5.066395e-6, 34.5, 0.0037879944]),
+                        (5, [0.013069153, 33.0625, 0.0040245056]),
+                        (10, [5.066395e-6, 34.5, 0.0037879944]),
+                        (15, [7.8082085e-6, 31.078125, 0.0037593842]),
+                    ],
+                ),
+                // Mode 0x6.
+                (
+                    "46c991a8d198e0334d1b94b9254e4b76",
+                    &[
+                        (0, [698.5, 0.0006933212, 0.00030064583]),
+                        (3, [751.5, 0.00076150894, 0.00028705597]),
+                        (5, [671.5, 0.00071048737, 0.00031542778]),
+                        (10, [751.5, 0.00076150894, 0.00028705597]),
+                        (15, [751.5, 0.0006766319, 0.00027370453]),
+                    ],
+                ),
+                // Mode 0xa.
+                (
+                    "2ac7e4873ecc479e911bac7303bda926",
+                    &[
+                        (0, [0.013008118, 38080.0, 9184.0]),
+                        (3, [0.0127334595, 37440.0, 9520.0]),
+                        (5, [0.013008118, 38080.0, 9184.0]),
+                        (10, [0.012458801, 38528.0, 9888.0]),
+                        (15, [0.011711121, 41472.0, 10880.0]),
+                    ],
+                ),
+                // Mode 0xe.
+                (
+                    "4e28cc83a9bfb12db61cac8f8836da2e",
+                    &[
+                        (0, [19.921875, 1091.0, 0.099243164]),
+                        (3, [18.75, 738.5, 0.12219238]),
+                        (5, [24.421875, 887.5, 0.10723877]),
+                        (10, [18.453125, 695.0, 0.12756348]),
+                        (15, [19.578125, 856.5, 0.11102295]),
+                    ],
+                ),
+                // Mode 0x12.
+                (
+                    "3208079c37ffa45dbc0270449bbbf724",
+                    &[
+                        (0, [0.007545471, 0.00010716915, 1030.0]),
+                        (3, [0.07116699, 0.00033807755, 1155.0]),
+                        (5, [0.003129959, 0.00013589859, 896.0]),
+                        (10, [0.012008667, 0.0001513958, 717.0]),
+                        (15, [0.09777832, 0.0003838539, 1278.0]),
+                    ],
+                ),
+                // Mode 0x16.
+                (
+                    "7652f13d1d8e266a800e503ae605ec0e",
+                    &[
+                        (0, [7.4453125, 5848.0, 19.09375]),
+                        (3, [6.1953125, 6924.0, 5.2265625]),
+                        (5, [9.3828125, 2414.0, 7.7773438]),
+                        (10, [7.4453125, 2740.0, 6.4765625]),
+                        (15, [7.4453125, 2740.0, 6.4765625]),
+                    ],
+                ),
+                // Mode 0x1a.
+                (
+                    "9a875168725e3b1ff80270bc335ba7a1",
+                    &[
+                        (0, [0.005180359, 28.78125, 0.0026512146]),
+                        (3, [0.007545471, 52.6875, 0.00024008751]),
+                        (5, [0.008132935, 23.875, 0.002450943]),
+                        (10, [0.0069465637, 57.03125, 0.00035238266]),
+                        (15, [0.0044822693, 85.9375, 0.00137043]),
+                    ],
+                ),
+                // Mode 0x1e.
+                (
+                    "9ee4b85f06805f1d6e13b0bbb36666bf",
+                    &[
+                        (0, [6.71875, 506.0, 258.0]),
+                        (3, [28.890625, 0.008514404, 463.25]),
+                        (5, [0.038146973, 2506.0, 1285.0]),
+                        (10, [143.625, 0.28271484, 13.0625]),
+                        (15, [802.0, 11.609375, 0.29223633]),
+                    ],
+                ),
+                // Mode 0x3.
+                (
+                    "c39ce28aa433e665a429cac5be02eb8a",
+                    &[
+                        (0, [0.013061523, 1.2783203, 1.9941406]),
+                        (3, [0.013061523, 1.2783203, 1.9941406]),
+                        (5, [3.1582031, 193.25, 0.01159668]),
+                        (10, [0.013061523, 1.2783203, 1.9941406]),
+                        (15, [0.34472656, 25.328125, 0.09527588]),
+                    ],
+                ),
+                // Mode 0x7.
+                (
+                    "c7e78f603d2b155d13df9920a8cb6f20",
+                    &[
+                        (0, [0.17822266, 0.0007376671, 2246.0]),
+                        (3, [0.04937744, 0.0030269623, 10984.0]),
+                        (5, [0.07470703, 0.0018262863, 6524.0]),
+                        (10, [0.058441162, 0.0024032593, 8144.0]),
+                        (15, [0.15563965, 0.0008354187, 2698.0]),
+                    ],
+                ),
+                // Mode 0xb.
+                (
+                    "6b7ef1bcba94235c917611a5ec30c9c5",
+                    &[
+                        (0, [1.4052734, 18.421875, 0.043823242]),
+                        (3, [1.0322266, 20.015625, 0.035827637]),
+                        (5, [1.3554688, 18.625, 0.042755127]),
+                        (10, [1.4052734, 18.421875, 0.043823242]),
+                        (15, [0.8857422, 21.125, 0.03074646]),
+                    ],
+                ),
+                // Mode 0xf.
+                (
+                    "8f6d5fea15b25112bc3109cec30947d3",
+                    &[
+                        (0, [0.0008659363, 0.00018441677, 0.008659363]),
+                        (3, [0.0008659363, 0.00018441677, 0.008659363]),
+                        (5, [0.0008659363, 0.00018453598, 0.008651733]),
+                        (10, [0.0008659363, 0.00018441677, 0.008659363]),
+                        (15, [0.0008664131, 0.00018429756, 0.008666992]),
+                    ],
+                ),
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    fn bc6h_signed() {
+        check_bc6h(
+            &[
+                // Mode 0x3.
+                (
+                    "6377fe46d4e38d651b71044311b00e1c",
+                    &[
+                        (0, [-2.4020672e-5, 243.25, -1611.0]),
+                        (3, [7.57575e-5, 24.328125, -585.0]),
+                        (5, [-0.00058984756, 58592.0, -15656.0]),
+                        (10, [-0.00058984756, 58592.0, -15656.0]),
+                        (15, [-0.00036168098, 21056.0, -10448.0]),
+                    ],
+                ),
+                // Mode 0x0.
+                (
+                    "64a03f558966eec4f91568b39ebd56ca",
+                    &[
+                        (0, [1.4560547, -399.75, 0.04611206]),
+                        (3, [1.9941406, -311.75, 0.07098389]),
+                        (5, [0.96533203, -520.5, 0.05596924]),
+                        (10, [2.2441406, -255.5, 0.07043457]),
+                        (15, [1.8525391, -374.5, 0.0715332]),
+                    ],
+                ),
+            ],
+            true,
+        );
+    }
+}
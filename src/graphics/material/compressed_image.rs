@@ -0,0 +1,167 @@
+use super::bcn::{self, BlockFormat};
+use std::{fs, io};
+
+/// Block compressed pixels read from a KTX2 or DDS file along with the file's mip chain.
+///
+/// The wgpu version we build against has no block compressed texture formats, so `decode`
+/// turns every level into plain pixels before upload. Once it does the levels can be copied
+/// to the gpu as they are.
+pub(crate) struct CompressedImage {
+    pub(crate) format: BlockFormat,
+    pub(crate) srgb: bool,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    pub(crate) fn load(path: &str) -> Result<Self, String> {
+        if path.ends_with(".ktx2") {
+            Self::load_ktx2(path)
+        } else {
+            Self::load_dds(path)
+        }
+    }
+
+    fn load_ktx2(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|error| error.to_string())?;
+        let reader = ktx2::Reader::new(bytes).map_err(|error| error.to_string())?;
+        let header = reader.header();
+
+        if header.supercompression_scheme.is_some() {
+            return Err("supercompressed files aren't supported".to_string());
+        }
+        if header.face_count > 1 || header.layer_count > 1 || header.pixel_depth > 1 {
+            return Err("only single 2d textures are supported".to_string());
+        }
+
+        let (format, srgb) = match header.format {
+            Some(ktx2::Format::BC1_RGB_UNORM_BLOCK) | Some(ktx2::Format::BC1_RGBA_UNORM_BLOCK) => {
+                (BlockFormat::Bc1, false)
+            }
+            Some(ktx2::Format::BC1_RGB_SRGB_BLOCK) | Some(ktx2::Format::BC1_RGBA_SRGB_BLOCK) => {
+                (BlockFormat::Bc1, true)
+            }
+            Some(ktx2::Format::BC2_UNORM_BLOCK) => (BlockFormat::Bc2, false),
+            Some(ktx2::Format::BC2_SRGB_BLOCK) => (BlockFormat::Bc2, true),
+            Some(ktx2::Format::BC3_UNORM_BLOCK) => (BlockFormat::Bc3, false),
+            Some(ktx2::Format::BC3_SRGB_BLOCK) => (BlockFormat::Bc3, true),
+            Some(ktx2::Format::BC4_UNORM_BLOCK) => (BlockFormat::Bc4 { signed: false }, false),
+            Some(ktx2::Format::BC4_SNORM_BLOCK) => (BlockFormat::Bc4 { signed: true }, false),
+            Some(ktx2::Format::BC5_UNORM_BLOCK) => (BlockFormat::Bc5 { signed: false }, false),
+            Some(ktx2::Format::BC5_SNORM_BLOCK) => (BlockFormat::Bc5 { signed: true }, false),
+            Some(ktx2::Format::BC6H_UFLOAT_BLOCK) => (BlockFormat::Bc6h { signed: false }, false),
+            Some(ktx2::Format::BC6H_SFLOAT_BLOCK) => (BlockFormat::Bc6h { signed: true }, false),
+            Some(ktx2::Format::BC7_UNORM_BLOCK) => (BlockFormat::Bc7, false),
+            Some(ktx2::Format::BC7_SRGB_BLOCK) => (BlockFormat::Bc7, true),
+            format => return Err(format!("unsupported format {:?}", format)),
+        };
+
+        let width = header.pixel_width;
+        let height = header.pixel_height.max(1);
+        let mut levels = Vec::new();
+        for (mip_level, level) in reader.levels().enumerate() {
+            let size = format.level_size((width >> mip_level).max(1), (height >> mip_level).max(1));
+            if level.len() < size {
+                return Err(format!("mip level {} is too small", mip_level));
+            }
+            levels.push(level[..size].to_vec());
+        }
+
+        Ok(Self {
+            format,
+            srgb,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    fn load_dds(path: &str) -> Result<Self, String> {
+        let file = fs::File::open(path).map_err(|error| error.to_string())?;
+        let dds =
+            ddsfile::Dds::read(&mut io::BufReader::new(file)).map_err(|error| error.to_string())?;
+
+        let (format, srgb) = match dds.get_dxgi_format() {
+            Some(ddsfile::DxgiFormat::BC1_UNorm) | Some(ddsfile::DxgiFormat::BC1_Typeless) => {
+                (BlockFormat::Bc1, false)
+            }
+            Some(ddsfile::DxgiFormat::BC1_UNorm_sRGB) => (BlockFormat::Bc1, true),
+            Some(ddsfile::DxgiFormat::BC2_UNorm) | Some(ddsfile::DxgiFormat::BC2_Typeless) => {
+                (BlockFormat::Bc2, false)
+            }
+            Some(ddsfile::DxgiFormat::BC2_UNorm_sRGB) => (BlockFormat::Bc2, true),
+            Some(ddsfile::DxgiFormat::BC3_UNorm) | Some(ddsfile::DxgiFormat::BC3_Typeless) => {
+                (BlockFormat::Bc3, false)
+            }
+            Some(ddsfile::DxgiFormat::BC3_UNorm_sRGB) => (BlockFormat::Bc3, true),
+            Some(ddsfile::DxgiFormat::BC4_UNorm) | Some(ddsfile::DxgiFormat::BC4_Typeless) => {
+                (BlockFormat::Bc4 { signed: false }, false)
+            }
+            Some(ddsfile::DxgiFormat::BC4_SNorm) => (BlockFormat::Bc4 { signed: true }, false),
+            Some(ddsfile::DxgiFormat::BC5_UNorm) | Some(ddsfile::DxgiFormat::BC5_Typeless) => {
+                (BlockFormat::Bc5 { signed: false }, false)
+            }
+            Some(ddsfile::DxgiFormat::BC5_SNorm) => (BlockFormat::Bc5 { signed: true }, false),
+            Some(ddsfile::DxgiFormat::BC6H_UF16) | Some(ddsfile::DxgiFormat::BC6H_Typeless) => {
+                (BlockFormat::Bc6h { signed: false }, false)
+            }
+            Some(ddsfile::DxgiFormat::BC6H_SF16) => (BlockFormat::Bc6h { signed: true }, false),
+            Some(ddsfile::DxgiFormat::BC7_UNorm) | Some(ddsfile::DxgiFormat::BC7_Typeless) => {
+                (BlockFormat::Bc7, false)
+            }
+            Some(ddsfile::DxgiFormat::BC7_UNorm_sRGB) => (BlockFormat::Bc7, true),
+            // Older writers use these four character codes without a DX10 header.
+            _ => match dds.header.spf.fourcc.as_ref().map(|fourcc| fourcc.0) {
+                Some(ddsfile::FourCC::BC4_UNORM) => (BlockFormat::Bc4 { signed: false }, false),
+                Some(ddsfile::FourCC::BC4_SNORM) => (BlockFormat::Bc4 { signed: true }, false),
+                Some(ddsfile::FourCC::BC5_SNORM) => (BlockFormat::Bc5 { signed: true }, false),
+                _ => return Err("unsupported format".to_string()),
+            },
+        };
+
+        // Levels of the first layer are stored one after another from the largest down.
+        let width = dds.get_width();
+        let height = dds.get_height();
+        let mut levels = Vec::new();
+        let mut offset = 0;
+        for mip_level in 0..dds.get_num_mipmap_levels().max(1) {
+            let size = format.level_size((width >> mip_level).max(1), (height >> mip_level).max(1));
+            let level = dds
+                .data
+                .get(offset..offset + size)
+                .ok_or_else(|| format!("mip level {} is too small", mip_level))?;
+            levels.push(level.to_vec());
+            offset += size;
+        }
+
+        Ok(Self {
+            format,
+            srgb,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    pub(crate) fn texture_format(&self) -> wgpu::TextureFormat {
+        self.format.decoded_format(self.srgb)
+    }
+
+    /// Decodes every level into tightly packed rows in the format from `texture_format`.
+    pub(crate) fn decode(&self) -> Vec<(Vec<u8>, u32, u32)> {
+        self.levels
+            .iter()
+            .enumerate()
+            .map(|(mip_level, level)| {
+                let width = (self.width >> mip_level).max(1);
+                let height = (self.height >> mip_level).max(1);
+                (
+                    bcn::decode(self.format, level, width, height),
+                    width,
+                    height,
+                )
+            })
+            .collect()
+    }
+}
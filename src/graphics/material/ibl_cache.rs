@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    hash::Hasher,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+// Bump this when the precompute shaders or the file layout change so old files are ignored.
+const CACHE_VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"HIBL";
+
+// Every cached map is Rgba32Float.
+const BYTES_PER_PIXEL: u32 = 16;
+
+/// FNV-1a, unlike `DefaultHasher` its output is the same between builds and runs.
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Size and mip count of a cube map stored in the cache.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CubeLayout {
+    pub(crate) size: u32,
+    pub(crate) mip_levels: u32,
+}
+
+impl CubeLayout {
+    fn mip_size(&self, mip_level: u32) -> u32 {
+        (self.size >> mip_level).max(1)
+    }
+
+    fn byte_len(&self) -> usize {
+        (0..self.mip_levels)
+            .map(|mip_level| {
+                let size = self.mip_size(mip_level) as usize;
+                size * size * 6 * BYTES_PER_PIXEL as usize
+            })
+            .sum()
+    }
+
+    // Rows have to be 256 byte aligned for copies between buffers and textures.
+    fn padded_bytes_per_row(&self, mip_level: u32) -> u32 {
+        let bytes_per_row = self.mip_size(mip_level) * BYTES_PER_PIXEL;
+        (bytes_per_row + 255) / 256 * 256
+    }
+
+    /// Reads every layer of every mip level back from the gpu, mip levels first.
+    pub(crate) fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &mut wgpu::Queue,
+        texture: &wgpu::Texture,
+    ) -> Vec<u8> {
+        let mut offsets = Vec::new();
+        let mut buffer_size = 0;
+        for mip_level in 0..self.mip_levels {
+            for _ in 0..6 {
+                offsets.push(buffer_size);
+                buffer_size +=
+                    (self.padded_bytes_per_row(mip_level) * self.mip_size(mip_level)) as u64;
+            }
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: buffer_size,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            label: None,
+        });
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let mut offset = offsets.iter();
+        for mip_level in 0..self.mip_levels {
+            let size = self.mip_size(mip_level);
+            for layer in 0..6 {
+                encoder.copy_texture_to_buffer(
+                    wgpu::TextureCopyView {
+                        texture,
+                        mip_level,
+                        array_layer: layer,
+                        origin: wgpu::Origin3d::ZERO,
+                    },
+                    wgpu::BufferCopyView {
+                        buffer: &buffer,
+                        offset: *offset.next().unwrap(),
+                        bytes_per_row: self.padded_bytes_per_row(mip_level),
+                        rows_per_image: 0,
+                    },
+                    wgpu::Extent3d {
+                        width: size,
+                        height: size,
+                        depth: 1,
+                    },
+                );
+            }
+        }
+        queue.submit(&[encoder.finish()]);
+
+        let buffer_future = buffer.map_read(0, buffer_size);
+        device.poll(wgpu::Maintain::Wait);
+        let mapping = futures::executor::block_on(buffer_future)
+            .expect("IBL cache: Unable to read the maps back from the gpu");
+        let padded = mapping.as_slice();
+
+        let mut bytes = Vec::with_capacity(self.byte_len());
+        let mut offset = offsets.iter();
+        for mip_level in 0..self.mip_levels {
+            let size = self.mip_size(mip_level);
+            let bytes_per_row = (size * BYTES_PER_PIXEL) as usize;
+            let padded_bytes_per_row = self.padded_bytes_per_row(mip_level) as usize;
+            for _ in 0..6 {
+                let start = *offset.next().unwrap() as usize;
+                for row in 0..size as usize {
+                    let row_start = start + row * padded_bytes_per_row;
+                    bytes.extend_from_slice(&padded[row_start..row_start + bytes_per_row]);
+                }
+            }
+        }
+
+        bytes
+    }
+
+    /// Creates a cube texture from bytes laid out like the ones returned by `read`.
+    pub(crate) fn upload(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        bytes: &[u8],
+    ) -> wgpu::Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: self.size,
+                height: self.size,
+                depth: 6,
+            },
+            mip_level_count: self.mip_levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            label: None,
+        });
+
+        let mut offset = 0;
+        for mip_level in 0..self.mip_levels {
+            let size = self.mip_size(mip_level);
+            let bytes_per_row = (size * BYTES_PER_PIXEL) as usize;
+            let padded_bytes_per_row = self.padded_bytes_per_row(mip_level) as usize;
+            for layer in 0..6 {
+                let mut padded = vec![0u8; padded_bytes_per_row * size as usize];
+                for row in 0..size as usize {
+                    let source = offset + row * bytes_per_row;
+                    padded[row * padded_bytes_per_row..row * padded_bytes_per_row + bytes_per_row]
+                        .copy_from_slice(&bytes[source..source + bytes_per_row]);
+                }
+                offset += bytes_per_row * size as usize;
+
+                let buffer = device.create_buffer_with_data(&padded, wgpu::BufferUsage::COPY_SRC);
+                encoder.copy_buffer_to_texture(
+                    wgpu::BufferCopyView {
+                        buffer: &buffer,
+                        offset: 0,
+                        bytes_per_row: padded_bytes_per_row as u32,
+                        rows_per_image: 0,
+                    },
+                    wgpu::TextureCopyView {
+                        texture: &texture,
+                        mip_level,
+                        array_layer: layer,
+                        origin: wgpu::Origin3d::ZERO,
+                    },
+                    wgpu::Extent3d {
+                        width: size,
+                        height: size,
+                        depth: 1,
+                    },
+                );
+            }
+        }
+
+        texture
+    }
+}
+
+/// Precomputed image based lighting maps of one skybox, stored in a single file.
+///
+/// Files are named after a hash of the skybox source and every setting that changes the output,
+/// so a changed source simply misses the cache. Stale files are never cleaned up.
+pub(crate) struct IblCache {
+    path: PathBuf,
+    layouts: Vec<CubeLayout>,
+}
+
+impl IblCache {
+    /// `layouts` lists the maps stored in the file in order.
+    pub(crate) fn new(directory: &Path, source_hash: u64, layouts: Vec<CubeLayout>) -> Self {
+        let mut hasher = StableHasher::default();
+        hasher.write_u64(source_hash);
+        hasher.write_u32(CACHE_VERSION);
+        for layout in layouts.iter() {
+            hasher.write_u32(layout.size);
+            hasher.write_u32(layout.mip_levels);
+        }
+
+        Self {
+            path: directory.join(format!("{:016x}.ibl", hasher.finish())),
+            layouts,
+        }
+    }
+
+    /// Returns one byte buffer per layout, or `None` when there is no valid cache file.
+    pub(crate) fn load(&self) -> Option<Vec<Vec<u8>>> {
+        let mut file = fs::File::open(&self.path).ok()?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).ok()?;
+        if &header[0..4] != MAGIC || header[4..8] != CACHE_VERSION.to_le_bytes() {
+            log::warn!("Ignoring outdated IBL cache file: {}", self.path.display());
+            return None;
+        }
+
+        let mut maps = Vec::new();
+        for layout in self.layouts.iter() {
+            let mut bytes = vec![0u8; layout.byte_len()];
+            if file.read_exact(&mut bytes).is_err() {
+                log::warn!("Ignoring truncated IBL cache file: {}", self.path.display());
+                return None;
+            }
+            maps.push(bytes);
+        }
+        Some(maps)
+    }
+
+    pub(crate) fn save(&self, maps: &[Vec<u8>]) {
+        if let Err(error) = self.write(maps) {
+            log::warn!(
+                "Unable to write the IBL cache file {}: {}",
+                self.path.display(),
+                error
+            );
+        }
+    }
+
+    fn write(&self, maps: &[Vec<u8>]) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(&self.path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&CACHE_VERSION.to_le_bytes())?;
+        for map in maps {
+            file.write_all(map)?;
+        }
+        file.flush()
+    }
+}
@@ -1,5 +1,101 @@
+use super::compressed_image::CompressedImage;
+use image::{ImageBuffer, Pixel};
+use serde::{Deserialize, Serialize};
 use std::{fs, io};
 
+/// How texels are blended when an image is sampled.
+///
+/// Anisotropic filtering isn't available in the wgpu version we build against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageFilter {
+    Nearest,
+    Linear,
+}
+
+impl From<ImageFilter> for wgpu::FilterMode {
+    fn from(filter: ImageFilter) -> Self {
+        match filter {
+            ImageFilter::Nearest => wgpu::FilterMode::Nearest,
+            ImageFilter::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// What happens to uvs outside of 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageWrap {
+    Repeat,
+    MirrorRepeat,
+    ClampToEdge,
+}
+
+impl From<ImageWrap> for wgpu::AddressMode {
+    fn from(wrap: ImageWrap) -> Self {
+        match wrap {
+            ImageWrap::Repeat => wgpu::AddressMode::Repeat,
+            ImageWrap::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+            ImageWrap::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// Per image options used when an image is loaded from disk.
+///
+/// `AssetManager` reads these from an optional `<image>.meta` RON file next to the image,
+/// for example `grass.png.meta`. Fields that are left out keep their defaults:
+///
+/// ```ron
+/// (
+///     mag_filter: Nearest,
+///     min_filter: Nearest,
+///     mip_filter: Nearest,
+///     wrap: ClampToEdge,
+///     generate_mips: false,
+/// )
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSettings {
+    /// Builds the full mip chain on load. Turn this off for UI and pixel art that is always
+    /// drawn at its original size.
+    pub generate_mips: bool,
+    /// Treats the pixels as sRGB colors. Turn this off for data like normal, metallic-roughness
+    /// and occlusion maps. Ignored for `.hdr`, `.ktx2` and `.dds` files which store their own
+    /// color space.
+    pub srgb: bool,
+    /// Multiplies the color by alpha on load, for materials that blend premultiplied colors.
+    /// Ignored for `.ktx2` and `.dds` files.
+    pub premultiply_alpha: bool,
+    pub wrap: ImageWrap,
+    pub mag_filter: ImageFilter,
+    pub min_filter: ImageFilter,
+    pub mip_filter: ImageFilter,
+}
+
+impl ImageSettings {
+    /// Default settings for images holding data instead of colors.
+    pub fn linear() -> Self {
+        Self {
+            srgb: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            generate_mips: true,
+            srgb: true,
+            premultiply_alpha: false,
+            wrap: ImageWrap::Repeat,
+            mag_filter: ImageFilter::Nearest,
+            min_filter: ImageFilter::Linear,
+            mip_filter: ImageFilter::Linear,
+        }
+    }
+}
+
 pub struct Image {
     pub(crate) name: String,
     pub(crate) texture: wgpu::Texture,
@@ -16,20 +112,112 @@ impl Image {
         path: T,
         file_name: T,
     ) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new_with_settings(device, encoder, path, file_name, ImageSettings::default())
+    }
+
+    /// Loads an image from disk. `.hdr` files become `Rgba32Float` textures and everything
+    /// else `Rgba8UnormSrgb`, or `Rgba8Unorm` when `settings.srgb` is off. `.ktx2` and `.dds`
+    /// files keep the mip chain they were saved with.
+    pub fn new_with_settings<T>(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        path: T,
+        file_name: T,
+        settings: ImageSettings,
+    ) -> Self
     where
         T: Into<String>,
     {
         let path = path.into();
 
-        let (image_bytes, texture_extent, format) = if path.ends_with(".hdr") {
-            Self::create_hdr_image(path)
+        let (mips, texture_extent, format) = if path.ends_with(".hdr") {
+            Self::create_hdr_image(path, &settings)
+        } else if path.ends_with(".ktx2") || path.ends_with(".dds") {
+            Self::create_compressed_image(path)
         } else {
-            Self::create_normal_image(path)
+            Self::create_normal_image(path, &settings)
         };
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             size: texture_extent,
-            mip_level_count: 1,
+            mip_level_count: mips.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC
+                | wgpu::TextureUsage::COPY_DST,
+            label: None,
+        });
+
+        let bytes_per_pixel = if format == wgpu::TextureFormat::Rgba32Float {
+            4 * 4
+        } else {
+            4
+        };
+        for (mip_level, (bytes, width, height)) in mips.iter().enumerate() {
+            Self::copy_to_texture(
+                device,
+                encoder,
+                &texture,
+                mip_level as u32,
+                bytes,
+                (*width, *height),
+                bytes_per_pixel,
+            );
+        }
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: settings.wrap.into(),
+            address_mode_v: settings.wrap.into(),
+            address_mode_w: settings.wrap.into(),
+            mag_filter: settings.mag_filter.into(),
+            min_filter: settings.min_filter.into(),
+            mipmap_filter: settings.mip_filter.into(),
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare: wgpu::CompareFunction::Undefined,
+        });
+
+        let view = texture.create_default_view();
+
+        Self {
+            name: file_name.into().clone(),
+            texture,
+            extent: texture_extent,
+            sampler,
+            view,
+            format,
+        }
+    }
+
+    /// Creates an image from pixels already in memory.
+    /// With `generate_mips` the full mip chain is built on the cpu and uploaded as well.
+    pub fn from_rgba<T>(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        name: T,
+        image: &image::RgbaImage,
+        generate_mips: bool,
+    ) -> Self
+    where
+        T: Into<String>,
+    {
+        let (width, height) = image.dimensions();
+        let texture_extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let mips = Self::build_mips(image.clone(), generate_mips);
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: texture_extent,
+            mip_level_count: mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
@@ -37,33 +225,69 @@ impl Image {
             label: None,
         });
 
-        let temp_buf = device.create_buffer_with_data(&image_bytes, wgpu::BufferUsage::COPY_SRC);
+        for (mip_level, mip) in mips.iter().enumerate() {
+            Self::copy_to_texture(
+                device,
+                encoder,
+                &texture,
+                mip_level as u32,
+                mip.as_raw(),
+                mip.dimensions(),
+                4,
+            );
+        }
 
-        encoder.copy_buffer_to_texture(
-            wgpu::BufferCopyView {
-                buffer: &temp_buf,
-                offset: 0,
-                bytes_per_row: if format == wgpu::TextureFormat::Rgba8UnormSrgb {
-                    4 * texture_extent.width
-                } else {
-                    (4 * 4) * texture_extent.width
-                },
-                rows_per_image: 0,
-            },
-            wgpu::TextureCopyView {
-                texture: &texture,
-                mip_level: 0,
-                array_layer: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            texture_extent,
-        );
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare: wgpu::CompareFunction::Undefined,
+        });
+
+        let view = texture.create_default_view();
+
+        Self {
+            name: name.into(),
+            texture,
+            extent: texture_extent,
+            sampler,
+            view,
+            format,
+        }
+    }
+
+    /// Creates an empty image cameras can draw to and materials can sample from.
+    pub fn new_render_texture<T>(device: &wgpu::Device, name: T, width: u32, height: u32) -> Self
+    where
+        T: Into<String>,
+    {
+        let texture_extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let format = crate::graphics::renderer::FRAME_FORMAT;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            label: None,
+        });
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Nearest,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
             mipmap_filter: wgpu::FilterMode::Nearest,
             lod_min_clamp: -100.0,
@@ -74,7 +298,7 @@ impl Image {
         let view = texture.create_default_view();
 
         Self {
-            name: file_name.into().clone(),
+            name: name.into(),
             texture,
             extent: texture_extent,
             sampler,
@@ -83,10 +307,101 @@ impl Image {
         }
     }
 
-    fn create_normal_image(path: String) -> (Vec<u8>, wgpu::Extent3d, wgpu::TextureFormat) {
-        let img = image::open(&path)
+    /// Number of mip levels needed to go from the full size down to a single pixel.
+    pub(crate) fn mip_level_count(width: u32, height: u32) -> u32 {
+        32 - width.max(height).leading_zeros()
+    }
+
+    // Each level is resized from the one before it, the first level is the image itself.
+    fn build_mips<P>(
+        image: ImageBuffer<P, Vec<P::Subpixel>>,
+        generate_mips: bool,
+    ) -> Vec<ImageBuffer<P, Vec<P::Subpixel>>>
+    where
+        P: Pixel + 'static,
+        P::Subpixel: 'static,
+    {
+        let (width, height) = image.dimensions();
+        let mip_level_count = if generate_mips {
+            Self::mip_level_count(width, height)
+        } else {
+            1
+        };
+
+        let mut mips = vec![image];
+        for mip_level in 1..mip_level_count {
+            let mip = image::imageops::resize(
+                mips.last().unwrap(),
+                (width >> mip_level).max(1),
+                (height >> mip_level).max(1),
+                image::imageops::FilterType::Triangle,
+            );
+            mips.push(mip);
+        }
+        mips
+    }
+
+    // Copies have to use rows that are a multiple of 256 bytes so rows are padded first.
+    fn copy_to_texture(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        mip_level: u32,
+        bytes: &[u8],
+        (width, height): (u32, u32),
+        bytes_per_pixel: usize,
+    ) {
+        let row_size = bytes_per_pixel * width as usize;
+        let padded_row_size = (row_size + 255) / 256 * 256;
+        let mut padded = vec![0u8; padded_row_size * height as usize];
+        for (row, padded_row) in bytes
+            .chunks_exact(row_size)
+            .zip(padded.chunks_exact_mut(padded_row_size))
+        {
+            padded_row[..row_size].copy_from_slice(row);
+        }
+
+        let temp_buf = device.create_buffer_with_data(&padded, wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &temp_buf,
+                offset: 0,
+                bytes_per_row: padded_row_size as u32,
+                rows_per_image: 0,
+            },
+            wgpu::TextureCopyView {
+                texture,
+                mip_level,
+                array_layer: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+    }
+
+    fn create_normal_image(
+        path: String,
+        settings: &ImageSettings,
+    ) -> (
+        Vec<(Vec<u8>, u32, u32)>,
+        wgpu::Extent3d,
+        wgpu::TextureFormat,
+    ) {
+        let mut img = image::open(&path)
             .unwrap_or_else(|_| panic!("Image: Unable to open the file: {}", path))
             .to_rgba();
+        if settings.premultiply_alpha {
+            for pixel in img.pixels_mut() {
+                let alpha = pixel[3] as u32;
+                for channel in 0..3 {
+                    pixel[channel] = ((pixel[channel] as u32 * alpha + 127) / 255) as u8;
+                }
+            }
+        }
         let (width, height) = img.dimensions();
         let texture_extent = wgpu::Extent3d {
             width,
@@ -94,16 +409,48 @@ impl Image {
             depth: 1,
         };
 
-        let image_bytes: Vec<u8> = img.into_raw();
+        let mips = Self::build_mips(img, settings.generate_mips)
+            .into_iter()
+            .map(|mip| {
+                let (width, height) = mip.dimensions();
+                (mip.into_raw(), width, height)
+            })
+            .collect();
+
+        let format = if settings.srgb {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        (mips, texture_extent, format)
+    }
+
+    fn create_compressed_image(
+        path: String,
+    ) -> (
+        Vec<(Vec<u8>, u32, u32)>,
+        wgpu::Extent3d,
+        wgpu::TextureFormat,
+    ) {
+        let image = CompressedImage::load(&path)
+            .unwrap_or_else(|error| panic!("Image: Unable to load {}: {}", path, error));
+        let texture_extent = wgpu::Extent3d {
+            width: image.width,
+            height: image.height,
+            depth: 1,
+        };
 
-        (
-            image_bytes,
-            texture_extent,
-            wgpu::TextureFormat::Rgba8UnormSrgb,
-        )
+        (image.decode(), texture_extent, image.texture_format())
     }
 
-    fn create_hdr_image(path: String) -> (Vec<u8>, wgpu::Extent3d, wgpu::TextureFormat) {
+    fn create_hdr_image(
+        path: String,
+        settings: &ImageSettings,
+    ) -> (
+        Vec<(Vec<u8>, u32, u32)>,
+        wgpu::Extent3d,
+        wgpu::TextureFormat,
+    ) {
         // Load the image
         let decoder =
             image::hdr::HdrDecoder::new(io::BufReader::new(fs::File::open(&path).unwrap()))
@@ -123,16 +470,16 @@ impl Image {
             .iter()
             .flat_map(|pixel| vec![pixel[0], pixel[1], pixel[2], 1.0])
             .collect::<Vec<_>>();
+        let img = ImageBuffer::<image::Rgba<f32>, Vec<f32>>::from_raw(w, h, image_data).unwrap();
 
-        let image_bytes = unsafe {
-            std::slice::from_raw_parts(image_data.as_ptr() as *const u8, image_data.len() * 4)
-        }
-        .to_vec();
+        let mips = Self::build_mips(img, settings.generate_mips)
+            .into_iter()
+            .map(|mip| {
+                let (width, height) = mip.dimensions();
+                (bytemuck::cast_slice(mip.as_raw()).to_vec(), width, height)
+            })
+            .collect();
 
-        (
-            image_bytes,
-            texture_extent,
-            wgpu::TextureFormat::Rgba32Float,
-        )
+        (mips, texture_extent, wgpu::TextureFormat::Rgba32Float)
     }
 }
@@ -0,0 +1,163 @@
+use super::{AlphaMode, Image};
+use crate::graphics::pipeline::BindGroupWithData;
+use std::collections::HashMap;
+
+/// A kind of material, implement it to add materials like toon shading, water or holograms.
+///
+/// Materials are bound at set 2 of the pipeline named by `pipeline_name`. The default
+/// `create_bind_group` expects the layout from `material_bind_group_layout`: the bytes from
+/// `uniform` at binding 0 followed by a texture and a sampler for each of the `textures`.
+/// `UnlitPipelineDesc::custom` creates a pipeline for a shader of your own, its layouts are
+/// read from the shader so set 2 has to be declared that way.
+///
+/// Add materials with `AssetManager::add_material` and reference the returned index from the
+/// `Material` component.
+pub trait MaterialType: std::fmt::Debug + Send + Sync + 'static {
+    /// Name of the render graph node that draws this material.
+    fn pipeline_name(&self) -> &str;
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Opaque
+    }
+
+    /// Contents of the uniform buffer at binding 0, usually a `#[repr(C)]` struct passed
+    /// through `bytemuck::bytes_of`.
+    fn uniform(&self) -> Vec<u8>;
+
+    /// Names of the images in the texture slots. Images that can't be found use `white.png`.
+    fn textures(&self) -> Vec<String>;
+
+    fn bind_group_data(&self) -> Option<&BindGroupWithData>;
+
+    fn bind_group_data_mut(&mut self) -> &mut Option<BindGroupWithData>;
+
+    // Note: material_layout needs to be passed in from the pipeline.
+    // Be careful here to make sure the layout of the pipeline matches our layout here.
+    fn create_bind_group(
+        &mut self,
+        images: &HashMap<String, Image>,
+        device: &wgpu::Device,
+        material_layout: &wgpu::BindGroupLayout,
+    ) {
+        let uniform = self.uniform();
+        let textures = self.textures();
+        let uniform_buf = device.create_buffer_with_data(
+            &uniform,
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        // Asset manager will panic if image doesn't exist, but we don't want that.
+        let texture_images: Vec<&Image> = textures
+            .iter()
+            .map(|texture| {
+                images.get(texture).unwrap_or_else(|| {
+                    images.get("white.png")
+                        .unwrap_or_else(|| panic!("Material Error: Couldn't find default white texture. Please make sure it exists in the asset folder or make sure your material's image can be found."))
+                })
+            })
+            .collect();
+
+        let mut bindings = vec![wgpu::Binding {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer {
+                buffer: &uniform_buf,
+                range: 0..uniform.len() as wgpu::BufferAddress,
+            },
+        }];
+        for (slot, image) in texture_images.iter().enumerate() {
+            bindings.push(wgpu::Binding {
+                binding: 1 + slot as u32 * 2,
+                resource: wgpu::BindingResource::TextureView(&image.view),
+            });
+            bindings.push(wgpu::Binding {
+                binding: 2 + slot as u32 * 2,
+                resource: wgpu::BindingResource::Sampler(&image.sampler),
+            });
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: material_layout,
+            bindings: &bindings,
+            label: None,
+        });
+
+        *self.bind_group_data_mut() = Some(BindGroupWithData {
+            uniform_buf,
+            bind_group,
+            uniform,
+            textures,
+        });
+    }
+
+    /// Called before the material's pipeline draws. Uploads the uniform when it changed and
+    /// rebuilds the bind group when the textures changed, so material properties can be edited
+    /// at any time.
+    fn update_bind_group(
+        &mut self,
+        images: &HashMap<String, Image>,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        material_layout: &wgpu::BindGroupLayout,
+    ) {
+        let uniform = self.uniform();
+        let rebuild = match self.bind_group_data() {
+            Some(bind_group_data) => {
+                bind_group_data.uniform.len() != uniform.len()
+                    || bind_group_data.textures != self.textures()
+            }
+            None => true,
+        };
+        if rebuild {
+            self.create_bind_group(images, device, material_layout);
+            return;
+        }
+
+        let bind_group_data = self.bind_group_data_mut().as_mut().unwrap();
+        if bind_group_data.uniform != uniform {
+            let staging_buffer =
+                device.create_buffer_with_data(&uniform, wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(
+                &staging_buffer,
+                0,
+                &bind_group_data.uniform_buf,
+                0,
+                uniform.len() as wgpu::BufferAddress,
+            );
+            bind_group_data.uniform = uniform;
+        }
+    }
+}
+
+/// The material layout `MaterialType::create_bind_group` expects: a uniform buffer at binding 0
+/// and a texture and sampler pair for each texture slot after it.
+pub fn material_bind_group_layout(
+    device: &wgpu::Device,
+    texture_count: u32,
+) -> wgpu::BindGroupLayout {
+    let mut bindings = vec![wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+    }];
+    for slot in 0..texture_count {
+        bindings.push(wgpu::BindGroupLayoutEntry {
+            binding: 1 + slot * 2,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            ty: wgpu::BindingType::SampledTexture {
+                multisampled: false,
+                component_type: wgpu::TextureComponentType::Float,
+                dimension: wgpu::TextureViewDimension::D2,
+            },
+        });
+        bindings.push(wgpu::BindGroupLayoutEntry {
+            binding: 2 + slot * 2,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            ty: wgpu::BindingType::Sampler { comparison: false },
+        });
+    }
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        bindings: &bindings,
+        label: None,
+    })
+}
@@ -1,11 +1,20 @@
 pub(crate) mod shader;
-pub use shader::Shader;
+pub use shader::{Shader, ShaderManifest, ShaderStage, ShaderStageDesc, ShaderVariant};
+pub(crate) mod shader_reflection;
+pub use shader_reflection::ShaderReflection;
 
+pub(crate) mod bcn;
+pub(crate) mod compressed_image;
 pub(crate) mod image;
-pub use self::image::Image;
+pub use self::image::{Image, ImageFilter, ImageSettings, ImageWrap};
 
+pub(crate) mod ibl_cache;
+pub(crate) mod material_type;
+#[cfg(feature = "shader_compiler")]
+pub(crate) mod spirv_cache;
+pub use self::material_type::{material_bind_group_layout, MaterialType};
 pub(crate) mod skybox;
-pub use self::skybox::Skybox;
+pub use self::skybox::{SkyGradient, Skybox};
 
 pub(crate) mod unlit_material;
 pub use self::unlit_material::*;
@@ -13,8 +22,74 @@ pub use self::unlit_material::*;
 pub(crate) mod pbr_material;
 pub use self::pbr_material::*;
 
+/// How a material's alpha is used when rendering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    /// Alpha is ignored and the material is rendered fully opaque.
+    Opaque,
+    /// Pixels with alpha below the cutoff are discarded, everything else is opaque.
+    Mask(f32),
+    /// Alpha blended. Drawn after opaque objects from back to front without writing depth.
+    Blend,
+}
+
+impl Default for AlphaMode {
+    fn default() -> Self {
+        AlphaMode::Opaque
+    }
+}
+
+impl AlphaMode {
+    pub fn is_transparent(&self) -> bool {
+        *self == AlphaMode::Blend
+    }
+
+    /// The value the shaders compare alpha against. Zero means nothing is discarded.
+    pub(crate) fn cutoff(&self) -> f32 {
+        match self {
+            AlphaMode::Mask(cutoff) => *cutoff,
+            _ => 0.0,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Material {
     Unlit(UnlitMaterial),
     PBR(PBRMaterial),
+    /// A material type defined outside of the engine.
+    Custom(Box<dyn MaterialType>),
+}
+
+impl Material {
+    pub fn material_type(&self) -> &dyn MaterialType {
+        match self {
+            Material::Unlit(material) => material,
+            Material::PBR(material) => material,
+            Material::Custom(material) => material.as_ref(),
+        }
+    }
+
+    pub fn material_type_mut(&mut self) -> &mut dyn MaterialType {
+        match self {
+            Material::Unlit(material) => material,
+            Material::PBR(material) => material,
+            Material::Custom(material) => material.as_mut(),
+        }
+    }
+
+    /// Name of the render graph node that draws this material.
+    pub fn pipeline_name(&self) -> &str {
+        self.material_type().pipeline_name()
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.material_type().alpha_mode()
+    }
+
+    pub(crate) fn bind_group(&self) -> Option<&wgpu::BindGroup> {
+        self.material_type()
+            .bind_group_data()
+            .map(|bind_group_data| &bind_group_data.bind_group)
+    }
 }
@@ -1,23 +1,88 @@
-use super::Image;
+use super::{AlphaMode, MaterialType};
 use crate::graphics::pipeline::BindGroupWithData;
 use bytemuck::{Pod, Zeroable};
-use nalgebra_glm::Vec4;
-use std::{collections::HashMap, mem};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct PBRMaterialUniform {
     pub color: Vec4,
+    // xyz: emissive color.
+    pub emissive: Vec4,
+    // x: metallic, y: roughness, z: normal scale, w: occlusion strength.
+    pub factors: Vec4,
+    // x: alpha cutoff, zero when the material doesn't discard anything.
+    // y: one when the material has a normal texture.
+    pub flags: Vec4,
+    // Two rows of a 2x3 uv matrix for each texture slot, in the order of `textures`.
+    pub texture_transforms: [Vec4; 10],
 }
 
 unsafe impl Zeroable for PBRMaterialUniform {}
 unsafe impl Pod for PBRMaterialUniform {}
 
+/// Moves, rotates and scales the uvs a texture is sampled with, like glTF's
+/// `KHR_texture_transform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureTransform {
+    pub offset: Vec2,
+    /// Radians counter-clockwise around the uv origin.
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::zeros(),
+            rotation: 0.0,
+            scale: Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+impl TextureTransform {
+    // Rows of translation * rotation * scale as the spec defines it.
+    fn rows(&self) -> [Vec4; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [
+            Vec4::new(cos * self.scale.x, sin * self.scale.y, self.offset.x, 0.0),
+            Vec4::new(-sin * self.scale.x, cos * self.scale.y, self.offset.y, 0.0),
+        ]
+    }
+}
+
+/// A texture transform for each of the texture slots of a `PBRMaterial`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PBRTextureTransforms {
+    pub main: TextureTransform,
+    pub metallic_roughness: TextureTransform,
+    pub normal: TextureTransform,
+    pub occlusion: TextureTransform,
+    pub emissive: TextureTransform,
+}
+
+/// A metallic-roughness material with every property of a core glTF 2.0 material.
 #[derive(Debug)]
 pub struct PBRMaterial {
     pub index: i32,
     pub main_texture: String,
     pub color: Vec4,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Roughness is read from the green channel and metallic from the blue channel.
+    pub metallic_roughness_texture: Option<String>,
+    pub normal_texture: Option<String>,
+    pub normal_scale: f32,
+    /// Occlusion is read from the red channel and only darkens the skybox's light.
+    pub occlusion_texture: Option<String>,
+    pub occlusion_strength: f32,
+    pub emissive: Vec3,
+    pub emissive_texture: Option<String>,
+    pub texture_transforms: PBRTextureTransforms,
+    pub alpha_mode: AlphaMode,
+    /// Back faces are drawn too, lit as if they faced the camera.
+    pub double_sided: bool,
     pub(crate) bind_group_data: Option<BindGroupWithData>,
 }
 
@@ -31,59 +96,105 @@ impl PBRMaterial {
             index: material_index,
             main_texture: main_texture.clone(),
             color,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: Vec3::zeros(),
+            emissive_texture: None,
+            texture_transforms: PBRTextureTransforms::default(),
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
             bind_group_data: None,
         }
     }
+}
 
-    // Note: local_bind_group_layout needs to be passed in from the pipeline.
-    // Be careful here to make sure the layout of the pipeline matches our layout here.
-    pub(crate) fn create_bind_group(
-        &mut self,
-        images: &HashMap<String, Image>,
-        device: &wgpu::Device,
-        local_bind_group_layout: &wgpu::BindGroupLayout,
-    ) {
-        let material_uniform_size = mem::size_of::<PBRMaterialUniform>() as wgpu::BufferAddress;
-        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
-            size: material_uniform_size,
-            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-            label: None,
-        });
-
-        // Asset manager will panic if image doesn't exist, but we don't want that.
-        // So use get_image_option instead.
-        let image = images
-            .get(&self.main_texture)
-            .unwrap_or(
-                images.get("white.png")
-                    .unwrap_or_else(|| panic!("PBRMaterial Error: Couldn't find default white texture. Please make sure it exists in the asset folder or make sure your material's image can be found."))
-            );
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &local_bind_group_layout,
-            bindings: &[
-                wgpu::Binding {
-                    binding: 0, // We'll use 1 for our local bindings.
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &uniform_buf,
-                        range: 0..material_uniform_size,
-                    },
-                },
-                wgpu::Binding {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&image.view),
-                },
-                wgpu::Binding {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&image.sampler),
+/// Name of the render graph node that draws PBR materials with these settings.
+pub(crate) fn pbr_pipeline_name(transparent: bool, double_sided: bool) -> &'static str {
+    match (transparent, double_sided) {
+        (false, false) => "pbr",
+        (false, true) => "pbr_double_sided",
+        (true, false) => "pbr_transparent",
+        (true, true) => "pbr_transparent_double_sided",
+    }
+}
+
+impl MaterialType for PBRMaterial {
+    fn pipeline_name(&self) -> &str {
+        pbr_pipeline_name(self.alpha_mode.is_transparent(), self.double_sided)
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn uniform(&self) -> Vec<u8> {
+        let transforms = &self.texture_transforms;
+        let mut texture_transforms = [Vec4::zeros(); 10];
+        for (slot, transform) in [
+            transforms.main,
+            transforms.metallic_roughness,
+            transforms.normal,
+            transforms.occlusion,
+            transforms.emissive,
+        ]
+        .iter()
+        .enumerate()
+        {
+            let rows = transform.rows();
+            texture_transforms[slot * 2] = rows[0];
+            texture_transforms[slot * 2 + 1] = rows[1];
+        }
+
+        let uniform = PBRMaterialUniform {
+            color: self.color,
+            emissive: Vec4::new(self.emissive.x, self.emissive.y, self.emissive.z, 0.0),
+            factors: Vec4::new(
+                self.metallic,
+                self.roughness,
+                self.normal_scale,
+                self.occlusion_strength,
+            ),
+            flags: Vec4::new(
+                self.alpha_mode.cutoff(),
+                if self.normal_texture.is_some() {
+                    1.0
+                } else {
+                    0.0
                 },
-            ],
-            label: None,
-        });
-
-        self.bind_group_data = Some(BindGroupWithData {
-            uniform_buf,
-            bind_group,
-        });
+                0.0,
+                0.0,
+            ),
+            texture_transforms,
+        };
+        bytemuck::bytes_of(&uniform).to_vec()
+    }
+
+    // Missing textures fall back to white, which leaves the factors as they are.
+    fn textures(&self) -> Vec<String> {
+        let white = "white.png".to_string();
+        vec![
+            self.main_texture.clone(),
+            self.metallic_roughness_texture
+                .clone()
+                .unwrap_or_else(|| white.clone()),
+            self.normal_texture.clone().unwrap_or_else(|| white.clone()),
+            self.occlusion_texture
+                .clone()
+                .unwrap_or_else(|| white.clone()),
+            self.emissive_texture.clone().unwrap_or(white),
+        ]
+    }
+
+    fn bind_group_data(&self) -> Option<&BindGroupWithData> {
+        self.bind_group_data.as_ref()
+    }
+
+    fn bind_group_data_mut(&mut self) -> &mut Option<BindGroupWithData> {
+        &mut self.bind_group_data
     }
 }
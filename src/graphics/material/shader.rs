@@ -1,17 +1,333 @@
-use shaderc;
+use super::shader_reflection::ShaderReflection;
+use serde::Deserialize;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+#[cfg(feature = "shader_compiler")]
+use {
+    super::{ibl_cache::StableHasher, spirv_cache::SpirvCache},
+    std::{collections::HashSet, hash::Hasher, path::PathBuf},
+};
 
-pub struct Shader {
-    pub fragment: wgpu::ShaderModule,
+// First word of every SPIR-V module.
+#[cfg(not(feature = "shader_compiler"))]
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+#[cfg(feature = "shader_compiler")]
+impl From<ShaderStage> for shaderc::ShaderKind {
+    fn from(stage: ShaderStage) -> Self {
+        match stage {
+            ShaderStage::Vertex => shaderc::ShaderKind::Vertex,
+            ShaderStage::Fragment => shaderc::ShaderKind::Fragment,
+        }
+    }
+}
+
+fn default_entry_point() -> String {
+    "main".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShaderStageDesc {
+    pub stage: ShaderStage,
+    /// Relative to the manifest.
+    pub path: String,
+    #[serde(default = "default_entry_point")]
+    pub entry_point: String,
+}
+
+/// Contents of a `.shader` file, written in RON:
+///
+/// ```ron
+/// (
+///     stages: [
+///         (stage: Vertex, path: "pbr_vertex.glsl"),
+///         (stage: Fragment, path: "pbr_fragment.glsl", entry_point: "main"),
+///     ],
+///     include_paths: ["library"],
+///     defines: { "MAX_LIGHTS": "16" },
+///     features: ["ALPHA_TEST", "NORMAL_MAP"],
+/// )
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ShaderManifest {
+    pub stages: Vec<ShaderStageDesc>,
+    /// Directories searched for `#include`s after the manifest's own directory, relative to
+    /// the manifest.
+    pub include_paths: Vec<String>,
+    /// Defined in every variant.
+    pub defines: HashMap<String, String>,
+    /// Defines that can be turned on per variant, see `Shader::variant`.
+    pub features: Vec<String>,
+}
+
+impl ShaderManifest {
+    // Deprecated: older manifests list one file per line and the one with "frag" in its name
+    // is the fragment shader.
+    fn from_lines(contents: &str) -> Self {
+        let stages = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| ShaderStageDesc {
+                stage: if line.contains("frag") {
+                    ShaderStage::Fragment
+                } else {
+                    ShaderStage::Vertex
+                },
+                path: line.to_string(),
+                entry_point: default_entry_point(),
+            })
+            .collect();
+
+        Self {
+            stages,
+            ..Self::default()
+        }
+    }
+}
+
+/// A shader compiled with one set of features.
+pub struct ShaderVariant {
     pub vertex: wgpu::ShaderModule,
+    pub fragment: wgpu::ShaderModule,
+    pub reflection: ShaderReflection,
+}
+
+pub struct Shader {
+    name: String,
+    directory: String,
+    manifest: ShaderManifest,
+    #[cfg(feature = "shader_compiler")]
+    cache_directory: Option<PathBuf>,
+    // Keyed by the sorted list of features.
+    variants: Mutex<HashMap<Vec<String>, Arc<ShaderVariant>>>,
 }
 
 impl Shader {
-    pub fn new(device: &wgpu::Device, path: String, file_name: String) -> Self {
+    /// Compiled stages are cached in `cache_directory`. Without the `shader_compiler` feature
+    /// nothing is compiled or cached, every variant is loaded from plain SPIR-V files next to
+    /// the manifest instead, e.g. `pbr+ALPHA_TEST.frag.spv` for `pbr.shader`.
+    pub fn new(
+        device: &wgpu::Device,
+        path: String,
+        file_name: String,
+        #[cfg_attr(not(feature = "shader_compiler"), allow(unused_variables))]
+        cache_directory: Option<&Path>,
+    ) -> Self {
+        let shader_path = format!("{}{}", path, file_name);
+        let contents = fs::read_to_string(&shader_path)
+            .unwrap_or_else(|_| panic!("Shader: Unable to read the file: {}", shader_path));
+        let manifest = if contents.trim_start().starts_with('(') {
+            ron::de::from_str(&contents).unwrap_or_else(|error| {
+                panic!("Shader: Unable to parse {}: {}", shader_path, error)
+            })
+        } else {
+            log::warn!(
+                "Shader: {} lists one file per line, which is deprecated. Describe the stages in a RON manifest instead.",
+                shader_path
+            );
+            ShaderManifest::from_lines(&contents)
+        };
+
+        let shader = Self {
+            name: file_name,
+            directory: path,
+            manifest,
+            #[cfg(feature = "shader_compiler")]
+            cache_directory: cache_directory.map(|directory| directory.to_path_buf()),
+            variants: Mutex::new(HashMap::new()),
+        };
+
+        // Compiled up front so broken shaders show up while loading.
+        shader.variant::<&str>(device, &[]);
+
+        shader
+    }
+
+    /// File name of the manifest.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn manifest(&self) -> &ShaderManifest {
+        &self.manifest
+    }
+
+    pub fn entry_point(&self, stage: ShaderStage) -> &str {
+        &self.stage(stage).entry_point
+    }
+
+    /// Returns the shader compiled with `features` defined. Each combination of features is
+    /// compiled the first time it's asked for and reused after that.
+    pub fn variant<T: AsRef<str>>(
+        &self,
+        device: &wgpu::Device,
+        features: &[T],
+    ) -> Arc<ShaderVariant> {
+        let mut key: Vec<String> = features
+            .iter()
+            .map(|feature| feature.as_ref().to_string())
+            .collect();
+        key.sort();
+        key.dedup();
+        for feature in key.iter() {
+            if !self.manifest.features.contains(feature) {
+                panic!("Shader: {} doesn't list the feature {}", self.name, feature);
+            }
+        }
+
+        let mut variants = self.variants.lock().unwrap();
+        if let Some(variant) = variants.get(&key) {
+            return variant.clone();
+        }
+        let variant = Arc::new(self.compile(device, &key));
+        variants.insert(key, variant.clone());
+        variant
+    }
+
+    fn stage(&self, stage: ShaderStage) -> &ShaderStageDesc {
+        self.manifest
+            .stages
+            .iter()
+            .find(|desc| desc.stage == stage)
+            .unwrap_or_else(|| panic!("Shader: {} has no {:?} stage", self.name, stage))
+    }
+
+    fn compile(&self, device: &wgpu::Device, features: &[String]) -> ShaderVariant {
+        let vertex = self.load_stage(ShaderStage::Vertex, features);
+        let fragment = self.load_stage(ShaderStage::Fragment, features);
+
+        ShaderVariant {
+            reflection: ShaderReflection::new(&self.name, &vertex, &fragment),
+            vertex: device.create_shader_module(&vertex),
+            fragment: device.create_shader_module(&fragment),
+        }
+    }
+
+    // Named after the shader and features rather than a hash so variants can be found by
+    // name, e.g. `pbr+ALPHA_TEST.frag` followed by `extension`.
+    fn variant_file_name(
+        &self,
+        stage: ShaderStage,
+        features: &[String],
+        extension: &str,
+    ) -> String {
+        let mut file_name = self.name.trim_end_matches(".shader").to_string();
+        for feature in features {
+            file_name.push('+');
+            file_name.push_str(feature);
+        }
+        file_name.push_str(match stage {
+            ShaderStage::Vertex => ".vert.",
+            ShaderStage::Fragment => ".frag.",
+        });
+        file_name.push_str(extension);
+        file_name
+    }
+
+    #[cfg(not(feature = "shader_compiler"))]
+    fn load_stage(&self, stage: ShaderStage, features: &[String]) -> Vec<u32> {
+        let spirv_path = format!(
+            "{}{}",
+            self.directory,
+            self.variant_file_name(stage, features, "spv")
+        );
+        let bytes = fs::read(&spirv_path).unwrap_or_else(|_| {
+            panic!(
+                "Shader: No precompiled {:?} stage of {} with features {:?} at {}. Build with the shader_compiler feature to compile it.",
+                stage, self.name, features, spirv_path
+            )
+        });
+        let spirv: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        if bytes.len() % 4 != 0 || spirv.first() != Some(&SPIRV_MAGIC) {
+            panic!("Shader: {} isn't a SPIR-V file", spirv_path);
+        }
+        spirv
+    }
+
+    // Not `.spv` since the header makes the files unreadable to SPIR-V tools.
+    #[cfg(feature = "shader_compiler")]
+    fn spirv_cache(&self, stage: ShaderStage, features: &[String]) -> Option<SpirvCache> {
+        let cache_directory = self.cache_directory.as_ref()?;
+        Some(SpirvCache::new(
+            cache_directory,
+            self.variant_file_name(stage, features, "spvc"),
+        ))
+    }
+
+    #[cfg(feature = "shader_compiler")]
+    fn load_stage(&self, stage: ShaderStage, features: &[String]) -> Vec<u32> {
+        let cache = self.spirv_cache(stage, features);
+        let source_hash = self.source_hash(stage, features);
+        if let Some(spirv) = cache.as_ref().and_then(|cache| cache.load(source_hash)) {
+            return spirv;
+        }
+
+        let spirv = self.compile_stage(stage, features);
+        if let Some(cache) = cache {
+            cache.save(source_hash, &spirv);
+        }
+        spirv
+    }
+
+    #[cfg(feature = "shader_compiler")]
+    fn include_directories(&self) -> Vec<String> {
+        let mut include_directories = vec![self.directory.clone()];
+        for include_path in self.manifest.include_paths.iter() {
+            include_directories.push(format!(
+                "{}{}/",
+                self.directory,
+                include_path.trim_end_matches('/')
+            ));
+        }
+        include_directories
+    }
+
+    // Covers everything that changes the output: the source and its includes, the defines
+    // and the optimization level.
+    #[cfg(feature = "shader_compiler")]
+    fn source_hash(&self, stage: ShaderStage, features: &[String]) -> u64 {
+        let desc = self.stage(stage);
+        let mut hasher = StableHasher::default();
+        hasher.write(desc.entry_point.as_bytes());
+        hasher.write_u8(cfg!(debug_assertions) as u8);
+
+        let mut defines: Vec<_> = self.manifest.defines.iter().collect();
+        defines.sort();
+        for (name, value) in defines {
+            hasher.write(name.as_bytes());
+            hasher.write(value.as_bytes());
+        }
+        for feature in features {
+            hasher.write(feature.as_bytes());
+        }
+
+        hash_source(
+            &mut hasher,
+            &format!("{}{}", self.directory, desc.path),
+            &self.include_directories(),
+            &mut HashSet::new(),
+        );
+        hasher.finish()
+    }
+
+    #[cfg(feature = "shader_compiler")]
+    fn compile_stage(&self, stage: ShaderStage, features: &[String]) -> Vec<u32> {
         // Compiler
         let mut compiler = shaderc::Compiler::new().unwrap();
         let mut options = shaderc::CompileOptions::new().unwrap();
@@ -25,78 +341,78 @@ impl Shader {
             options.set_optimization_level(shaderc::OptimizationLevel::Zero);
         }
 
-        options.add_macro_definition("EP", Some("main"));
-        options.set_include_callback(|file_path, _include_type, _, _| {
-            let shader_path = format!("{}{}", path, file_path);
-            let mut file = File::open(&shader_path).expect("Shader: Unable to open the file");
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)
-                .unwrap_or_else(|_| panic!("Unable to read the file: {}", shader_path));
-            Result::Ok(shaderc::ResolvedInclude {
-                resolved_name: file_path.to_string(),
-                content: contents,
-            })
-        });
-
-        let shader_path = format!("{}{}", path, file_name);
-        let file = File::open(&shader_path).expect("Shader: Unable to open the file");
-
-        let shader_file = BufReader::new(&file);
-        let mut vert_file_name = String::new();
-        let mut frag_file_name = String::new();
-        for (_num, line) in shader_file.lines().enumerate() {
-            let current_line = line.unwrap();
-            if current_line.contains("frag") {
-                frag_file_name = current_line;
-            } else {
-                vert_file_name = current_line;
-            }
+        for (name, value) in self.manifest.defines.iter() {
+            options.add_macro_definition(name, Some(value));
+        }
+        for feature in features {
+            options.add_macro_definition(feature, None);
         }
 
-        // Pixel
-        let shader_path = format!("{}{}", path, frag_file_name);
-        let mut file = File::open(&shader_path)
-            .unwrap_or_else(|_| panic!("Unable to open the file: {}", shader_path));
-        let mut frag_contents = String::new();
-        file.read_to_string(&mut frag_contents)
-            .unwrap_or_else(|_| panic!("Unable to read the file: {}", shader_path));
+        let include_directories = self.include_directories();
+        options.set_include_callback(move |file_path, _include_type, _, _| {
+            for directory in include_directories.iter() {
+                let shader_path = format!("{}{}", directory, file_path);
+                if let Ok(content) = fs::read_to_string(&shader_path) {
+                    return Ok(shaderc::ResolvedInclude {
+                        resolved_name: file_path.to_string(),
+                        content,
+                    });
+                }
+            }
+            Err(format!("Shader: Unable to find the include {}", file_path))
+        });
 
-        // Vertex
-        let shader_path = format!("{}/{}", path, vert_file_name);
-        file = File::open(&shader_path)
-            .unwrap_or_else(|_| panic!("Unable to read the file: {}", shader_path));
-        let mut vert_contents = String::new();
-        file.read_to_string(&mut vert_contents)
+        let desc = self.stage(stage);
+        let shader_path = format!("{}{}", self.directory, desc.path);
+        let contents = fs::read_to_string(&shader_path)
             .unwrap_or_else(|_| panic!("Unable to read the file: {}", shader_path));
-
-        options.add_macro_definition("EP", Some("main"));
-
-        let vertex = {
-            let spirv = compiler
-                .compile_into_spirv(
-                    &vert_contents,
-                    shaderc::ShaderKind::Vertex,
-                    "vertex.glsl",
-                    "main",
-                    Some(&options),
+        let spirv = compiler
+            .compile_into_spirv(
+                &contents,
+                stage.into(),
+                &desc.path,
+                &desc.entry_point,
+                Some(&options),
+            )
+            .unwrap_or_else(|error| {
+                panic!(
+                    "Shader: Unable to compile {} with features {:?}: {}",
+                    shader_path, features, error
                 )
-                .unwrap();
-            device.create_shader_module(&spirv.as_binary())
-        };
+            });
+        spirv.as_binary().to_vec()
+    }
+}
 
-        let fragment = {
-            let spirv = compiler
-                .compile_into_spirv(
-                    &frag_contents,
-                    shaderc::ShaderKind::Fragment,
-                    "pixel.glsl",
-                    "main",
-                    Some(&options),
-                )
-                .unwrap();
-            device.create_shader_module(spirv.as_binary())
-        };
+// Hashes a file and every file it includes, so editing an include misses the cache too.
+#[cfg(feature = "shader_compiler")]
+fn hash_source(
+    hasher: &mut StableHasher,
+    path: &str,
+    include_directories: &[String],
+    visited: &mut HashSet<String>,
+) {
+    if !visited.insert(path.to_string()) {
+        return;
+    }
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    hasher.write(contents.as_bytes());
 
-        Shader { fragment, vertex }
+    for line in contents.lines() {
+        let line = line.trim();
+        if !line.starts_with("#include") {
+            continue;
+        }
+        let include = line
+            .trim_start_matches("#include")
+            .trim()
+            .trim_matches(|c| c == '"' || c == '<' || c == '>');
+        let include_path = include_directories
+            .iter()
+            .map(|directory| format!("{}{}", directory, include))
+            .find(|include_path| Path::new(include_path).exists());
+        if let Some(include_path) = include_path {
+            hash_source(hasher, &include_path, include_directories, visited);
+        }
     }
 }
@@ -0,0 +1,288 @@
+use spirv_reflect::types::{
+    ReflectBlockVariable, ReflectDecorationFlags, ReflectDescriptorBinding, ReflectDescriptorType,
+    ReflectDimension, ReflectFormat,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BindingKind {
+    UniformBuffer,
+    StorageBuffer {
+        readonly: bool,
+    },
+    SampledTexture {
+        multisampled: bool,
+        dimension: wgpu::TextureViewDimension,
+    },
+    Sampler,
+}
+
+#[derive(Debug, Clone)]
+struct ReflectedBinding {
+    set: u32,
+    binding: u32,
+    visibility: wgpu::ShaderStage,
+    kind: BindingKind,
+    // Declared size of buffer blocks.
+    size: u64,
+}
+
+/// What a shader variant declares, read from its SPIR-V.
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    // Sorted by set and binding.
+    bindings: Vec<ReflectedBinding>,
+    // Sorted by location.
+    vertex_inputs: Vec<(u32, wgpu::VertexFormat)>,
+}
+
+impl ShaderReflection {
+    pub(crate) fn new(name: &str, vertex: &[u32], fragment: &[u32]) -> Self {
+        let mut reflection = Self {
+            bindings: Vec::new(),
+            vertex_inputs: Vec::new(),
+        };
+
+        for (spirv, stage) in [
+            (vertex, wgpu::ShaderStage::VERTEX),
+            (fragment, wgpu::ShaderStage::FRAGMENT),
+        ]
+        .iter()
+        {
+            let module = spirv_reflect::ShaderModule::load_u32_data(spirv)
+                .unwrap_or_else(|error| panic!("Shader: Unable to reflect {}: {}", name, error));
+            let bindings = module
+                .enumerate_descriptor_bindings(None)
+                .unwrap_or_else(|error| panic!("Shader: Unable to reflect {}: {}", name, error));
+            for binding in bindings.iter() {
+                reflection.add_binding(name, binding, *stage);
+            }
+
+            if *stage == wgpu::ShaderStage::VERTEX {
+                let inputs = module
+                    .enumerate_input_variables(None)
+                    .unwrap_or_else(|error| {
+                        panic!("Shader: Unable to reflect {}: {}", name, error)
+                    });
+                for input in inputs.iter() {
+                    if input
+                        .decoration_flags
+                        .contains(ReflectDecorationFlags::BUILT_IN)
+                    {
+                        continue;
+                    }
+                    reflection
+                        .vertex_inputs
+                        .push((input.location, vertex_format(name, input.format)));
+                }
+            }
+        }
+
+        reflection
+            .bindings
+            .sort_by_key(|binding| (binding.set, binding.binding));
+        reflection
+            .vertex_inputs
+            .sort_by_key(|(location, _)| *location);
+        reflection
+    }
+
+    fn add_binding(
+        &mut self,
+        name: &str,
+        binding: &ReflectDescriptorBinding,
+        stage: wgpu::ShaderStage,
+    ) {
+        let kind = match binding.descriptor_type {
+            ReflectDescriptorType::UniformBuffer => BindingKind::UniformBuffer,
+            ReflectDescriptorType::StorageBuffer => BindingKind::StorageBuffer {
+                readonly: is_readonly(&binding.block),
+            },
+            ReflectDescriptorType::SampledImage => BindingKind::SampledTexture {
+                multisampled: binding.image.ms != 0,
+                dimension: match (binding.image.dim, binding.image.arrayed != 0) {
+                    (ReflectDimension::Type1d, _) => wgpu::TextureViewDimension::D1,
+                    (ReflectDimension::Type2d, false) => wgpu::TextureViewDimension::D2,
+                    (ReflectDimension::Type2d, true) => wgpu::TextureViewDimension::D2Array,
+                    (ReflectDimension::Type3d, _) => wgpu::TextureViewDimension::D3,
+                    (ReflectDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                    (ReflectDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+                    (dimension, _) => panic!(
+                        "Shader: {} uses a texture of dimension {:?} at set {} binding {} which isn't supported",
+                        name, dimension, binding.set, binding.binding
+                    ),
+                },
+            },
+            ReflectDescriptorType::Sampler => BindingKind::Sampler,
+            ReflectDescriptorType::CombinedImageSampler => panic!(
+                "Shader: {} uses a combined image sampler at set {} binding {}, declare the texture and sampler separately",
+                name, binding.set, binding.binding
+            ),
+            descriptor_type => panic!(
+                "Shader: {} uses a {:?} at set {} binding {} which isn't supported",
+                name, descriptor_type, binding.set, binding.binding
+            ),
+        };
+
+        // Both stages can declare the same binding.
+        if let Some(existing) = self
+            .bindings
+            .iter_mut()
+            .find(|existing| existing.set == binding.set && existing.binding == binding.binding)
+        {
+            if existing.kind != kind {
+                panic!(
+                    "Shader: {} declares set {} binding {} differently in its stages",
+                    name, binding.set, binding.binding
+                );
+            }
+            existing.visibility |= stage;
+            return;
+        }
+
+        self.bindings.push(ReflectedBinding {
+            set: binding.set,
+            binding: binding.binding,
+            visibility: stage,
+            kind,
+            size: binding.block.size as u64,
+        });
+    }
+
+    /// One more than the highest set the shader uses.
+    pub fn set_count(&self) -> u32 {
+        self.bindings
+            .iter()
+            .map(|binding| binding.set + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The layout entries of every binding the shader declares in `set`.
+    pub fn bind_group_layout_entries(&self, set: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.set == set)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding: binding.binding,
+                visibility: binding.visibility,
+                ty: match binding.kind {
+                    BindingKind::UniformBuffer => {
+                        wgpu::BindingType::UniformBuffer { dynamic: false }
+                    }
+                    BindingKind::StorageBuffer { readonly } => wgpu::BindingType::StorageBuffer {
+                        dynamic: false,
+                        readonly,
+                    },
+                    BindingKind::SampledTexture {
+                        multisampled,
+                        dimension,
+                    } => wgpu::BindingType::SampledTexture {
+                        multisampled,
+                        component_type: wgpu::TextureComponentType::Float,
+                        dimension,
+                    },
+                    BindingKind::Sampler => wgpu::BindingType::Sampler { comparison: false },
+                },
+            })
+            .collect()
+    }
+
+    /// Declared size of the uniform or storage block at `set` and `binding`.
+    pub fn buffer_size(&self, set: u32, binding: u32) -> Option<u64> {
+        self.bindings
+            .iter()
+            .find(|reflected| reflected.set == set && reflected.binding == binding)
+            .filter(|reflected| match reflected.kind {
+                BindingKind::UniformBuffer | BindingKind::StorageBuffer { .. } => true,
+                _ => false,
+            })
+            .map(|reflected| reflected.size)
+    }
+
+    /// Checks that a rust struct of `size` bytes matches the uniform or storage block at `set`
+    /// and `binding`. Uniform blocks are padded to a multiple of 16 bytes, so the struct needs
+    /// the same trailing padding.
+    pub fn check_buffer_size(&self, shader_name: &str, set: u32, binding: u32, size: u64) {
+        let block_size = self.buffer_size(set, binding).unwrap_or_else(|| {
+            panic!(
+                "Shader: {} has no buffer at set {} binding {}",
+                shader_name, set, binding
+            )
+        });
+        if size != block_size {
+            panic!(
+                "Shader: The buffer at set {} binding {} of {} is {} bytes but the rust struct is {} bytes",
+                set, binding, shader_name, block_size, size
+            );
+        }
+    }
+
+    /// Locations and formats of the vertex shader's inputs.
+    pub fn vertex_inputs(&self) -> &[(u32, wgpu::VertexFormat)] {
+        &self.vertex_inputs
+    }
+
+    /// Attributes for a single vertex buffer holding every input tightly packed in location
+    /// order, along with the buffer's stride.
+    pub fn vertex_attributes(&self) -> (wgpu::BufferAddress, Vec<wgpu::VertexAttributeDescriptor>) {
+        let mut offset = 0;
+        let attributes = self
+            .vertex_inputs
+            .iter()
+            .map(|(location, format)| {
+                let attribute = wgpu::VertexAttributeDescriptor {
+                    offset,
+                    format: *format,
+                    shader_location: *location,
+                };
+                offset += vertex_format_size(*format);
+                attribute
+            })
+            .collect();
+        (offset, attributes)
+    }
+}
+
+// glslang marks every member of a `readonly buffer` as non writable.
+fn is_readonly(block: &ReflectBlockVariable) -> bool {
+    block
+        .decoration_flags
+        .contains(ReflectDecorationFlags::NON_WRITABLE)
+        || (!block.members.is_empty()
+            && block.members.iter().all(|member| {
+                member
+                    .decoration_flags
+                    .contains(ReflectDecorationFlags::NON_WRITABLE)
+            }))
+}
+
+fn vertex_format(name: &str, format: ReflectFormat) -> wgpu::VertexFormat {
+    match format {
+        ReflectFormat::R32_UINT => wgpu::VertexFormat::Uint,
+        ReflectFormat::R32_SINT => wgpu::VertexFormat::Int,
+        ReflectFormat::R32_SFLOAT => wgpu::VertexFormat::Float,
+        ReflectFormat::R32G32_UINT => wgpu::VertexFormat::Uint2,
+        ReflectFormat::R32G32_SINT => wgpu::VertexFormat::Int2,
+        ReflectFormat::R32G32_SFLOAT => wgpu::VertexFormat::Float2,
+        ReflectFormat::R32G32B32_UINT => wgpu::VertexFormat::Uint3,
+        ReflectFormat::R32G32B32_SINT => wgpu::VertexFormat::Int3,
+        ReflectFormat::R32G32B32_SFLOAT => wgpu::VertexFormat::Float3,
+        ReflectFormat::R32G32B32A32_UINT => wgpu::VertexFormat::Uint4,
+        ReflectFormat::R32G32B32A32_SINT => wgpu::VertexFormat::Int4,
+        ReflectFormat::R32G32B32A32_SFLOAT => wgpu::VertexFormat::Float4,
+        ReflectFormat::Undefined => panic!("Shader: {} has a vertex input of unknown type", name),
+    }
+}
+
+fn vertex_format_size(format: wgpu::VertexFormat) -> wgpu::BufferAddress {
+    match format {
+        wgpu::VertexFormat::Uint | wgpu::VertexFormat::Int | wgpu::VertexFormat::Float => 4,
+        wgpu::VertexFormat::Uint2 | wgpu::VertexFormat::Int2 | wgpu::VertexFormat::Float2 => 8,
+        wgpu::VertexFormat::Uint3 | wgpu::VertexFormat::Int3 | wgpu::VertexFormat::Float3 => 12,
+        wgpu::VertexFormat::Uint4 | wgpu::VertexFormat::Int4 | wgpu::VertexFormat::Float4 => 16,
+        format => panic!(
+            "Shader: Vertex format {:?} isn't a shader input type",
+            format
+        ),
+    }
+}
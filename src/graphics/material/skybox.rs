@@ -1,10 +1,40 @@
+use super::ibl_cache::{CubeLayout, IblCache, StableHasher};
 use crate::{
-    graphics::{resources::RenderTarget, RenderGraph},
+    graphics::{resources::RenderTarget, RenderGraph, SimplePipelineDesc},
     Application,
 };
-use std::fs::File;
+use nalgebra_glm::Vec3;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::Hasher,
+};
 
 pub const SPEC_CUBEMAP_MIP_LEVELS: u32 = 6;
+const IRRADIANCE_SIZE: u32 = 64;
+const SPECULAR_SIZE: u32 = 64;
+const BRDF_SIZE: u32 = 128;
+
+/// Colors for a procedural sky, in linear space.
+#[derive(Debug, Clone, Copy)]
+pub struct SkyGradient {
+    pub top: Vec3,
+    pub horizon: Vec3,
+    pub bottom: Vec3,
+    /// How quickly the horizon color fades out, higher values make a thinner horizon band.
+    pub exponent: f32,
+}
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self {
+            top: Vec3::new(0.15, 0.35, 0.8),
+            horizon: Vec3::new(0.75, 0.85, 0.95),
+            bottom: Vec3::new(0.2, 0.2, 0.2),
+            exponent: 0.5,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Skybox {
@@ -19,16 +49,181 @@ pub struct Skybox {
     pub(crate) brdf_view: wgpu::TextureView,
     pub(crate) cubemap_sampler: wgpu::Sampler,
     pub(crate) cubemap_bind_group: Option<wgpu::BindGroup>,
-    pub(crate) pbr_bind_group: Option<wgpu::BindGroup>,
+    /// One bind group per pbr pipeline, keyed by the pipeline's name in the render graph.
+    pub(crate) pbr_bind_groups: HashMap<String, wgpu::BindGroup>,
 }
 
 impl Skybox {
+    /// Creates a skybox from an equirectangular image, usually a `.hdr` file.
     pub fn new<T>(app: &mut Application, texture: T, size: f32) -> Self
     where
         T: Into<String>,
+    {
+        let texture = texture.into();
+        let source_hash = Self::hash_images(app, "equirectangular", &[texture.as_str()]);
+        let cube_projection_pipeline_desc =
+            crate::graphics::pipelines::equirectangular::CubeProjectionPipelineDesc::new(
+                texture, size,
+            );
+        Self::from_cube_source(app, cube_projection_pipeline_desc, size, source_hash)
+    }
+
+    /// Creates a skybox from six face images in +X, -X, +Y, -Y, +Z, -Z order, often named
+    /// px, nx, py, ny, pz and nz. The images are looked up by file name in the asset manager.
+    pub fn from_faces(app: &mut Application, faces: [&str; 6], size: f32) -> Self {
+        let source_hash = Self::hash_images(app, "faces", &faces);
+        let mut face_names: [String; 6] = Default::default();
+        for (face_name, face) in face_names.iter_mut().zip(faces.iter()) {
+            *face_name = face.to_string();
+        }
+        let cube_faces_pipeline_desc =
+            crate::graphics::pipelines::cube_faces::CubeFacesPipelineDesc::new(face_names, size);
+        Self::from_cube_source(app, cube_faces_pipeline_desc, size, source_hash)
+    }
+
+    /// Creates a skybox from a top, horizon and bottom color gradient without any images.
+    pub fn from_gradient(app: &mut Application, gradient: SkyGradient, size: f32) -> Self {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"gradient");
+        for value in gradient
+            .top
+            .iter()
+            .chain(gradient.horizon.iter())
+            .chain(gradient.bottom.iter())
+            .chain(std::iter::once(&gradient.exponent))
+        {
+            hasher.write(&value.to_le_bytes());
+        }
+        let gradient_pipeline_desc =
+            crate::graphics::pipelines::gradient_sky::GradientSkyPipelineDesc::new(gradient, size);
+        Self::from_cube_source(app, gradient_pipeline_desc, size, Some(hasher.finish()))
+    }
+
+    // Hashes the files the images were loaded from. Images that didn't come from a file, like
+    // render textures, can't be cached.
+    fn hash_images(app: &Application, kind: &str, names: &[&str]) -> Option<u64> {
+        let mut hasher = StableHasher::default();
+        hasher.write(kind.as_bytes());
+        for name in names {
+            let path = app.asset_manager.get_image_path(*name)?;
+            let bytes = fs::read(path).ok()?;
+            hasher.write(&bytes);
+        }
+        Some(hasher.finish())
+    }
+
+    // `cube_source` renders the six faces on top of each other and returns them as a cube map,
+    // the lighting maps are then precomputed from it. With a `source_hash` the maps are loaded
+    // from the asset manager's cache folder when they were computed before.
+    fn from_cube_source<T>(
+        app: &mut Application,
+        cube_source: T,
+        size: f32,
+        source_hash: Option<u64>,
+    ) -> Self
+    where
+        T: SimplePipelineDesc + 'static,
+    {
+        let layouts = vec![
+            CubeLayout {
+                size: size as u32,
+                mip_levels: 1,
+            },
+            CubeLayout {
+                size: IRRADIANCE_SIZE,
+                mip_levels: 1,
+            },
+            CubeLayout {
+                size: SPECULAR_SIZE,
+                mip_levels: SPEC_CUBEMAP_MIP_LEVELS,
+            },
+        ];
+        let cache = match (source_hash, app.asset_manager.get_cache_path()) {
+            (Some(source_hash), Some(directory)) => {
+                Some(IblCache::new(directory, source_hash, layouts.clone()))
+            }
+            _ => None,
+        };
+
+        let (color_texture, irradiance_texture, specular_texture) =
+            match cache.as_ref().and_then(|cache| cache.load()) {
+                Some(maps) => {
+                    let device = &app.renderer.device;
+                    let mut encoder = device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                    let textures = (
+                        layouts[0].upload(device, &mut encoder, &maps[0]),
+                        layouts[1].upload(device, &mut encoder, &maps[1]),
+                        layouts[2].upload(device, &mut encoder, &maps[2]),
+                    );
+                    app.renderer.queue.submit(&[encoder.finish()]);
+                    textures
+                }
+                None => {
+                    let textures = Self::render_maps(app, cube_source, size);
+                    if let Some(cache) = cache {
+                        let device = &app.renderer.device;
+                        let queue = &mut app.renderer.queue;
+                        cache.save(&[
+                            layouts[0].read(device, queue, &textures.0),
+                            layouts[1].read(device, queue, &textures.1),
+                            layouts[2].read(device, queue, &textures.2),
+                        ]);
+                    }
+                    textures
+                }
+            };
+
+        let brdf_texture = Self::create_brdf(app);
+
+        let color_view = Self::create_cube_view(&color_texture, 1);
+        let irradiance_view = Self::create_cube_view(&irradiance_texture, 1);
+        // Every mip so the shader can pick the one prefiltered for a material's roughness.
+        let specular_view = Self::create_cube_view(&specular_texture, SPEC_CUBEMAP_MIP_LEVELS);
+        let brdf_view = brdf_texture.create_default_view();
+
+        let cubemap_sampler = app
+            .renderer
+            .device
+            .create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                lod_min_clamp: -100.0,
+                lod_max_clamp: 100.0,
+                compare: wgpu::CompareFunction::Undefined,
+            });
+
+        Self {
+            size,
+            color_texture,
+            color_view,
+            irradiance_texture,
+            irradiance_view,
+            specular_texture,
+            specular_view,
+            brdf_texture,
+            brdf_view,
+            cubemap_sampler,
+            cubemap_bind_group: None,
+            pbr_bind_groups: HashMap::new(),
+        }
+    }
+
+    // Returns the color, irradiance and specular cube maps.
+    fn render_maps<T>(
+        app: &mut Application,
+        cube_source: T,
+        size: f32,
+    ) -> (wgpu::Texture, wgpu::Texture, wgpu::Texture)
+    where
+        T: SimplePipelineDesc + 'static,
     {
         // Create a new render graph for this process..
-        let mut graph = RenderGraph::new(&app.renderer.device);
+        let mut graph = RenderGraph::new();
 
         let cube_map_target = RenderTarget::new(
             &app.renderer.device,
@@ -40,23 +235,18 @@ impl Skybox {
             wgpu::TextureUsage::COPY_SRC | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
         );
 
-        let cube_projection_pipeline_desc =
-            crate::graphics::pipelines::equirectangular::CubeProjectionPipelineDesc::new(
-                texture.into(),
-                size,
-            );
         graph.add(
             &app.asset_manager,
             &mut app.renderer,
             "cube_projection",
-            cube_projection_pipeline_desc,
+            cube_source,
             vec![],
             false,
             Some(cube_map_target),
             false,
         );
 
-        let irradiance_size = 64.0;
+        let irradiance_size = IRRADIANCE_SIZE as f32;
         let irradiance_target = RenderTarget::new(
             &app.renderer.device,
             irradiance_size,
@@ -79,7 +269,7 @@ impl Skybox {
             true,
         );
 
-        let specular_size = 64;
+        let specular_size = SPECULAR_SIZE;
         // Add in a pass per mip level.
         for i in 0..SPEC_CUBEMAP_MIP_LEVELS {
             let res = (specular_size / 2u32.pow(i)) as f32;
@@ -106,44 +296,9 @@ impl Skybox {
             );
         }
 
-        // Specular BRDF
-        let specular_brdf_size = 128.0;
-        let spec_brdf_texture = RenderTarget::new(
-            &app.renderer.device,
-            specular_brdf_size,
-            specular_brdf_size,
-            1,
-            1,
-            wgpu::TextureFormat::Rgba8UnormSrgb,
-            wgpu::TextureUsage::SAMPLED
-                | wgpu::TextureUsage::COPY_SRC
-                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-        );
-        let spec_brdf_pipeline_desc =
-            crate::graphics::pipelines::specular_brdf::SpecularBRDFPipelineDesc::new(
-                specular_brdf_size,
-            );
-        graph.add(
-            &app.asset_manager,
-            &mut app.renderer,
-            "spec_brdf",
-            spec_brdf_pipeline_desc,
-            vec![],
-            false,
-            Some(spec_brdf_texture),
-            false,
-        );
-
-        // let output_buffer = app.renderer.device.create_buffer(&wgpu::BufferDescriptor {
-        //     size: (specular_brdf_size * specular_brdf_size) as u64 * std::mem::size_of::<u32>() as u64,
-        //     usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
-        //     label: None,
-        // });
-
         // We need to convert our regular texture map to a cube texture map with 6 faces.
         // Should be straight forward enough if we use equirectangular projection.
         // First we need a custom pipeline that will run in here to do the conversion.
-        //let output = app.renderer.swap_chain.get_next_texture().unwrap();
         let command_buffer = graph.render(
             &mut app.renderer,
             &mut app.asset_manager,
@@ -158,7 +313,9 @@ impl Skybox {
             6,
             SPEC_CUBEMAP_MIP_LEVELS,
             wgpu::TextureFormat::Rgba32Float,
-            wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC
+                | wgpu::TextureUsage::COPY_DST,
         );
 
         let mut encoder = app
@@ -197,104 +354,113 @@ impl Skybox {
             }
         }
 
-        // let output = graph.pull_render_target("spec_brdf");
-        // encoder.copy_texture_to_buffer(
-        //     wgpu::TextureCopyView {
-        //         texture: &output.texture,
-        //         mip_level: 0,
-        //         array_layer: 0,
-        //         origin: wgpu::Origin3d::ZERO,
-        //     },
-        //     wgpu::BufferCopyView {
-        //         buffer: &output_buffer,
-        //         offset: 0,
-        //         bytes_per_row: std::mem::size_of::<u32>() as u32 * specular_brdf_size as u32,
-        //         rows_per_image: 0,
-        //     },
-        //     wgpu::Extent3d {
-        //         width: specular_brdf_size as u32,
-        //         height: specular_brdf_size as u32,
-        //         depth: 1,
-        //     },
-        // );
-
         // Push to all command buffers to the queue
         app.renderer
             .queue
             .submit(&vec![command_buffer, encoder.finish()]);
 
-        // Note that we're not calling `.await` here.
-        // let buffer_future = output_buffer.map_read(0, (specular_brdf_size * specular_brdf_size) as u64 * std::mem::size_of::<u32>() as u64);
-
         app.renderer.device.poll(wgpu::Maintain::Wait);
 
-        // futures::executor::block_on(Self::save(buffer_future));
-
         let color = graph.pull_render_target("cube_projection");
         let irradiance = graph.pull_render_target("irradiance");
-        let brdf = graph.pull_render_target("spec_brdf");
 
-        let color_view = color.texture.create_view(&wgpu::TextureViewDescriptor {
-            format: wgpu::TextureFormat::Rgba32Float,
-            dimension: wgpu::TextureViewDimension::Cube,
-            aspect: wgpu::TextureAspect::default(),
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            array_layer_count: 6,
-        });
+        (color.texture, irradiance.texture, specular.texture)
+    }
 
-        let irradiance_view = irradiance
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor {
-                format: wgpu::TextureFormat::Rgba32Float,
-                dimension: wgpu::TextureViewDimension::Cube,
-                aspect: wgpu::TextureAspect::default(),
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                array_layer_count: 6,
-            });
+    // The BRDF lookup table doesn't depend on the skybox. `brdf_texture.png` in the core assets
+    // is a saved copy of it, it's only rendered when that image isn't loaded.
+    fn create_brdf(app: &mut Application) -> wgpu::Texture {
+        let brdf_extent = wgpu::Extent3d {
+            width: BRDF_SIZE,
+            height: BRDF_SIZE,
+            depth: 1,
+        };
 
-        let specular_view = specular.texture.create_view(&wgpu::TextureViewDescriptor {
+        if let Some(image) = app.asset_manager.get_image_option("brdf_texture.png") {
+            if image.extent.width == BRDF_SIZE
+                && image.extent.height == BRDF_SIZE
+                && image.format == wgpu::TextureFormat::Rgba8UnormSrgb
+            {
+                let device = &app.renderer.device;
+                let brdf_texture = device.create_texture(&wgpu::TextureDescriptor {
+                    size: brdf_extent,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+                    label: None,
+                });
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                encoder.copy_texture_to_texture(
+                    wgpu::TextureCopyView {
+                        texture: &image.texture,
+                        mip_level: 0,
+                        array_layer: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                    },
+                    wgpu::TextureCopyView {
+                        texture: &brdf_texture,
+                        mip_level: 0,
+                        array_layer: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                    },
+                    brdf_extent,
+                );
+                app.renderer.queue.submit(&[encoder.finish()]);
+                return brdf_texture;
+            }
+        }
+
+        let mut graph = RenderGraph::new();
+        let specular_brdf_size = BRDF_SIZE as f32;
+        let spec_brdf_texture = RenderTarget::new(
+            &app.renderer.device,
+            specular_brdf_size,
+            specular_brdf_size,
+            1,
+            1,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC
+                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        );
+        let spec_brdf_pipeline_desc =
+            crate::graphics::pipelines::specular_brdf::SpecularBRDFPipelineDesc::new(
+                specular_brdf_size,
+            );
+        graph.add(
+            &app.asset_manager,
+            &mut app.renderer,
+            "spec_brdf",
+            spec_brdf_pipeline_desc,
+            vec![],
+            false,
+            Some(spec_brdf_texture),
+            false,
+        );
+        let command_buffer = graph.render(
+            &mut app.renderer,
+            &mut app.asset_manager,
+            &mut app.current_scene.world,
+            None,
+        );
+        app.renderer.queue.submit(&[command_buffer]);
+
+        graph.pull_render_target("spec_brdf").texture
+    }
+
+    fn create_cube_view(texture: &wgpu::Texture, mip_levels: u32) -> wgpu::TextureView {
+        texture.create_view(&wgpu::TextureViewDescriptor {
             format: wgpu::TextureFormat::Rgba32Float,
             dimension: wgpu::TextureViewDimension::Cube,
             aspect: wgpu::TextureAspect::default(),
             base_mip_level: 0,
-            level_count: 1,
+            level_count: mip_levels,
             base_array_layer: 0,
             array_layer_count: 6,
-        });
-
-        let cubemap_sampler = app
-            .renderer
-            .device
-            .create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Linear,
-                min_filter: wgpu::FilterMode::Linear,
-                mipmap_filter: wgpu::FilterMode::Linear,
-                lod_min_clamp: -100.0,
-                lod_max_clamp: 100.0,
-                compare: wgpu::CompareFunction::Undefined,
-            });
-
-        Self {
-            size,
-            color_texture: color.texture,
-            color_view: color_view,
-            irradiance_texture: irradiance.texture,
-            irradiance_view,
-            specular_texture: specular.texture,
-            specular_view,
-            brdf_texture: brdf.texture,
-            brdf_view: brdf.texture_view,
-            cubemap_sampler,
-            cubemap_bind_group: None,
-            pbr_bind_group: None,
-        }
+        })
     }
 
     pub(crate) fn create_bind_group(
@@ -319,17 +485,20 @@ impl Skybox {
         self.cubemap_bind_group = Some(bind_group);
     }
 
-    pub(crate) fn create_pbr_bind_group(
+    pub(crate) fn create_pbr_bind_group<T>(
         &mut self,
         device: &wgpu::Device,
+        pipeline_name: T,
         material_layout: &wgpu::BindGroupLayout,
-    ) {
+    ) where
+        T: Into<String>,
+    {
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &material_layout,
             bindings: &[
                 wgpu::Binding {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&self.irradiance_view),
+                    resource: wgpu::BindingResource::TextureView(&self.specular_view),
                 },
                 wgpu::Binding {
                     binding: 1,
@@ -337,7 +506,7 @@ impl Skybox {
                 },
                 wgpu::Binding {
                     binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&self.specular_view),
+                    resource: wgpu::BindingResource::TextureView(&self.irradiance_view),
                 },
                 wgpu::Binding {
                     binding: 3,
@@ -355,7 +524,8 @@ impl Skybox {
             label: None,
         });
 
-        self.pbr_bind_group = Some(bind_group);
+        self.pbr_bind_groups
+            .insert(pipeline_name.into(), bind_group);
     }
 
     async fn save(
@@ -0,0 +1,78 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+// Bump this when the file layout changes so old files are ignored.
+const CACHE_VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"HSPV";
+const HEADER_SIZE: usize = 16;
+
+/// One compiled shader stage stored on disk.
+///
+/// Files start with a header holding a hash of the source, its includes and defines, followed
+/// by the SPIR-V words, so they aren't valid SPIR-V files on their own.
+pub(crate) struct SpirvCache {
+    path: PathBuf,
+}
+
+impl SpirvCache {
+    pub(crate) fn new(directory: &Path, file_name: String) -> Self {
+        Self {
+            path: directory.join(file_name),
+        }
+    }
+
+    /// Returns `None` when there is no valid file or when `source_hash` doesn't match the one
+    /// the file was compiled from.
+    pub(crate) fn load(&self, source_hash: u64) -> Option<Vec<u32>> {
+        let bytes = fs::read(&self.path).ok()?;
+        if bytes.len() < HEADER_SIZE
+            || &bytes[0..4] != MAGIC
+            || bytes[4..8] != CACHE_VERSION.to_le_bytes()
+            || (bytes.len() - HEADER_SIZE) % 4 != 0
+        {
+            log::warn!(
+                "Ignoring invalid SPIR-V cache file: {}",
+                self.path.display()
+            );
+            return None;
+        }
+
+        if bytes[8..16] != source_hash.to_le_bytes() {
+            return None;
+        }
+
+        Some(
+            bytes[HEADER_SIZE..]
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn save(&self, source_hash: u64, spirv: &[u32]) {
+        if let Err(error) = self.write(source_hash, spirv) {
+            log::warn!(
+                "Unable to write the SPIR-V cache file {}: {}",
+                self.path.display(),
+                error
+            );
+        }
+    }
+
+    fn write(&self, source_hash: u64, spirv: &[u32]) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(&self.path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&CACHE_VERSION.to_le_bytes())?;
+        file.write_all(&source_hash.to_le_bytes())?;
+        for word in spirv {
+            file.write_all(&word.to_le_bytes())?;
+        }
+        file.flush()
+    }
+}
@@ -1,13 +1,14 @@
-use super::Image;
+use super::{AlphaMode, MaterialType};
 use crate::graphics::pipeline::BindGroupWithData;
 use bytemuck::{Pod, Zeroable};
 use nalgebra_glm::Vec4;
-use std::{collections::HashMap, mem};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct UnlitUniform {
     pub color: Vec4,
+    // x: alpha cutoff, zero when the material doesn't discard anything.
+    pub alpha_cutoff: Vec4,
 }
 
 unsafe impl Zeroable for UnlitUniform {}
@@ -18,6 +19,7 @@ pub struct UnlitMaterial {
     pub index: i32,
     pub main_texture: String,
     pub color: Vec4,
+    pub alpha_mode: AlphaMode,
     pub(crate) bind_group_data: Option<BindGroupWithData>,
 }
 
@@ -31,59 +33,42 @@ impl UnlitMaterial {
             index: material_index,
             main_texture: main_texture.clone(),
             color,
+            alpha_mode: AlphaMode::Opaque,
             bind_group_data: None,
         }
     }
+}
 
-    // Note: local_bind_group_layout needs to be passed in from the pipeline.
-    // Be careful here to make sure the layout of the pipeline matches our layout here.
-    pub(crate) fn create_bind_group(
-        &mut self,
-        images: &HashMap<String, Image>,
-        device: &wgpu::Device,
-        local_bind_group_layout: &wgpu::BindGroupLayout,
-    ) {
-        let material_uniform_size = mem::size_of::<UnlitUniform>() as wgpu::BufferAddress;
-        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
-            size: material_uniform_size,
-            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-            label: None,
-        });
+impl MaterialType for UnlitMaterial {
+    fn pipeline_name(&self) -> &str {
+        if self.alpha_mode.is_transparent() {
+            "unlit_transparent"
+        } else {
+            "unlit"
+        }
+    }
 
-        // Asset manager will panic if image doesn't exist, but we don't want that.
-        // So use get_image_option instead.
-        let image = images
-            .get(&self.main_texture)
-            .unwrap_or(
-                images.get("white.png")
-                    .unwrap_or_else(|| panic!("UnlitMaterial Error: Couldn't find default white texture. Please make sure it exists in the asset folder or make sure your material's image can be found."))
-            );
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &local_bind_group_layout,
-            bindings: &[
-                wgpu::Binding {
-                    binding: 0, // We'll use 1 for our local bindings.
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &uniform_buf,
-                        range: 0..material_uniform_size,
-                    },
-                },
-                wgpu::Binding {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&image.view),
-                },
-                wgpu::Binding {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&image.sampler),
-                },
-            ],
-            label: None,
-        });
+    fn uniform(&self) -> Vec<u8> {
+        let uniform = UnlitUniform {
+            color: self.color,
+            alpha_cutoff: Vec4::new(self.alpha_mode.cutoff(), 0.0, 0.0, 0.0),
+        };
+        bytemuck::bytes_of(&uniform).to_vec()
+    }
+
+    fn textures(&self) -> Vec<String> {
+        vec![self.main_texture.clone()]
+    }
+
+    fn bind_group_data(&self) -> Option<&BindGroupWithData> {
+        self.bind_group_data.as_ref()
+    }
 
-        self.bind_group_data = Some(BindGroupWithData {
-            uniform_buf,
-            bind_group,
-        });
+    fn bind_group_data_mut(&mut self) -> &mut Option<BindGroupWithData> {
+        &mut self.bind_group_data
     }
 }
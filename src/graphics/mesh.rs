@@ -1,7 +1,12 @@
-use super::material::PBRMaterial;
-use crate::graphics::material::Material;
+use super::material::{PBRMaterial, TextureTransform};
+use crate::{
+    assets::GltfScene,
+    core::BoundingBox,
+    graphics::material::{AlphaMode, Material},
+};
 use bytemuck::{Pod, Zeroable};
 use nalgebra_glm::{Vec2, Vec3, Vec4};
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::Path;
 
@@ -38,157 +43,409 @@ pub struct SubMesh {
     material_id: Option<usize>,
     pub(crate) vertex_buffer: wgpu::Buffer,
     pub(crate) index_buffer: wgpu::Buffer,
+    pub(crate) bounds: BoundingBox,
 
     // Material index is stored here.
     pub(crate) material_index: i32,
 }
 
+impl SubMesh {
+    pub fn vertices(&self) -> &[MeshVertexData] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn topology(&self) -> wgpu::PrimitiveTopology {
+        self.mode
+    }
+
+    /// Bounds of the vertices in model space.
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+}
+
 pub struct Mesh {
     pub sub_meshes: Vec<SubMesh>,
+    bounds: BoundingBox,
+}
+
+/// Everything imported from a glTF file.
+pub(crate) struct GltfImport {
+    pub(crate) meshes: Vec<Mesh>,
+    pub(crate) materials: Vec<Material>,
+    /// File names of the normal, metallic-roughness and occlusion textures the materials use,
+    /// those have to be loaded as linear images.
+    pub(crate) linear_textures: Vec<String>,
+    pub(crate) scene: GltfScene,
 }
 
 impl Mesh {
-    /// Imports glTF 2.0
-    pub fn new<T>(
+    /// Name of a mesh imported from a glTF file. The first mesh is named after the file,
+    /// the others get their index appended like `level.gltf#2`.
+    pub fn gltf_mesh_name(file_name: &str, mesh_index: usize) -> String {
+        if mesh_index == 0 {
+            file_name.to_string()
+        } else {
+            format!("{}#{}", file_name, mesh_index)
+        }
+    }
+
+    /// Imports every mesh, material and node of a glTF 2.0 file. Materials are numbered from
+    /// `material_start_index` in the order of the file, followed by a default material for
+    /// primitives that don't have one.
+    pub(crate) fn load_gltf(
         device: &wgpu::Device,
-        path: T,
+        path: String,
+        file_name: &str,
         material_start_index: i32,
-    ) -> (Mesh, Vec<Material>)
-    where
-        T: Into<String>,
-    {
-        let mut materials = Vec::new();
-        let cloned_path = path.into().clone();
-        let (document, data, _) =
-            gltf::import(cloned_path).expect("Loaded the gltf file successfully!");
+    ) -> GltfImport {
+        let (document, data, _) = gltf::import(&path)
+            .unwrap_or_else(|error| panic!("Mesh: Unable to load {}: {}", path, error));
         let get_buffer_data = |buffer: gltf::Buffer<'_>| data.get(buffer.index()).map(|x| &*x.0);
+        let images: Vec<gltf::Image<'_>> = document.images().collect();
 
-        // let mut meshes = Vec::new();
-        let meshes = document.meshes().collect::<Vec<gltf::Mesh<'_>>>();
-        if meshes.len() > 1 {
-            log::warn!("Currently we only support 1 mesh per gltf object. If you have more than one it will not be rendered.");
+        let mut linear_textures = Vec::new();
+        let mut materials: Vec<Material> = document
+            .materials()
+            .map(|gltf_material| {
+                let material_index = material_start_index + gltf_material.index().unwrap() as i32;
+                Self::create_material(
+                    &gltf_material,
+                    &images,
+                    material_index,
+                    &mut linear_textures,
+                )
+            })
+            .collect();
+        let default_material_index = material_start_index + materials.len() as i32;
+        let mut uses_default_material = false;
+
+        let meshes = document
+            .meshes()
+            .map(|gltf_mesh| {
+                let mut sub_meshes = Vec::new();
+                for primitive in gltf_mesh.primitives() {
+                    let material_index = match primitive.material().index() {
+                        Some(index) => material_start_index + index as i32,
+                        None => {
+                            uses_default_material = true;
+                            default_material_index
+                        }
+                    };
+                    sub_meshes.push(Self::create_sub_mesh(
+                        device,
+                        &primitive,
+                        get_buffer_data,
+                        material_index,
+                    ));
+                }
+
+                let bounds = sub_meshes
+                    .iter()
+                    .fold(BoundingBox::empty(), |bounds, sub_mesh| {
+                        bounds.union(&sub_mesh.bounds)
+                    });
+                Mesh { sub_meshes, bounds }
+            })
+            .collect();
+
+        if uses_default_material {
+            materials.push(Material::PBR(PBRMaterial::new(
+                "white.png",
+                Vec4::new(1.0, 1.0, 1.0, 1.0),
+                default_material_index,
+            )));
+        }
+
+        GltfImport {
+            meshes,
+            materials,
+            linear_textures,
+            scene: GltfScene::new(&document, file_name),
         }
-        // For now we only support 1 mesh.
-        let gltf_mesh: &gltf::Mesh<'_> = meshes.first().unwrap();
+    }
 
-        let mut sub_meshes = Vec::new();
-        let primitives = gltf_mesh.primitives();
+    fn create_material(
+        gltf_material: &gltf::Material<'_>,
+        images: &[gltf::Image<'_>],
+        material_index: i32,
+        linear_textures: &mut Vec<String>,
+    ) -> Material {
+        let pbr = gltf_material.pbr_metallic_roughness();
 
-        let images: Vec<gltf::Image<'_>> = document.images().collect();
+        let color_factor = pbr.base_color_factor();
+        let color = Vec4::new(
+            color_factor[0],
+            color_factor[1],
+            color_factor[2],
+            color_factor[3],
+        );
 
-        for primitive in primitives {
-            let reader = primitive.reader(get_buffer_data);
-            let positions: Vec<_> = reader
-                .read_positions()
-                .map(|iter| iter.collect())
-                .ok_or(format!("mesh primitive is missing positions"))
-                .unwrap();
-
-            let mut vertices: Vec<MeshVertexData> = positions
-                .iter()
-                .map(|pos| MeshVertexData {
-                    position: Vec3::from(pos.clone()),
-                    ..MeshVertexData::default()
-                })
-                .collect();
-
-            if let Some(normals) = reader.read_normals() {
-                for (i, normal) in normals.enumerate() {
-                    vertices[i].normal = Vec3::from(normal.clone());
+        let main_texture = pbr
+            .base_color_texture()
+            .and_then(|info| Self::texture_file_name(images, &info.texture()));
+
+        // These hold data instead of colors so they can't be sampled as sRGB.
+        let data_textures = [
+            pbr.metallic_roughness_texture().map(|info| info.texture()),
+            gltf_material.normal_texture().map(|info| info.texture()),
+            gltf_material.occlusion_texture().map(|info| info.texture()),
+        ];
+        for texture in data_textures.iter().flatten() {
+            if let Some(file_name) = Self::texture_file_name(images, texture) {
+                if !linear_textures.contains(&file_name) {
+                    linear_textures.push(file_name);
                 }
             }
-            if let Some(uvs) = reader.read_tex_coords(0) {
-                for (i, uv) in uvs.into_f32().enumerate() {
-                    vertices[i].uv = Vec2::from(uv.clone());
-                }
+        }
+
+        // Only the first uv set is loaded.
+        let tex_coords = [
+            pbr.base_color_texture().map(|info| info.tex_coord()),
+            pbr.metallic_roughness_texture()
+                .map(|info| info.tex_coord()),
+            gltf_material.normal_texture().map(|info| info.tex_coord()),
+            gltf_material
+                .occlusion_texture()
+                .map(|info| info.tex_coord()),
+            gltf_material
+                .emissive_texture()
+                .map(|info| info.tex_coord()),
+        ];
+        if tex_coords.iter().flatten().any(|tex_coord| *tex_coord != 0) {
+            log::warn!(
+                "Mesh: Material {} samples a texture with a second uv set which isn't supported, the first one is used instead.",
+                gltf_material.name().unwrap_or_default()
+            );
+        }
+
+        let mut material = PBRMaterial::new(
+            main_texture.unwrap_or("white.png".to_string()),
+            color,
+            material_index,
+        );
+        material.metallic = pbr.metallic_factor();
+        material.roughness = pbr.roughness_factor();
+        material.metallic_roughness_texture = pbr
+            .metallic_roughness_texture()
+            .and_then(|info| Self::texture_file_name(images, &info.texture()));
+        if let Some(normal) = gltf_material.normal_texture() {
+            material.normal_texture = Self::texture_file_name(images, &normal.texture());
+            material.normal_scale = normal.scale();
+        }
+        if let Some(occlusion) = gltf_material.occlusion_texture() {
+            material.occlusion_texture = Self::texture_file_name(images, &occlusion.texture());
+            material.occlusion_strength = occlusion.strength();
+        }
+        material.emissive = Vec3::from(gltf_material.emissive_factor());
+        material.emissive_texture = gltf_material
+            .emissive_texture()
+            .and_then(|info| Self::texture_file_name(images, &info.texture()));
+
+        // The gltf crate only reads KHR_texture_transform from regular texture infos, so normal
+        // and occlusion textures keep the identity transform.
+        let transforms = &mut material.texture_transforms;
+        if let Some(info) = pbr.base_color_texture() {
+            transforms.main = Self::texture_transform(&info);
+        }
+        if let Some(info) = pbr.metallic_roughness_texture() {
+            transforms.metallic_roughness = Self::texture_transform(&info);
+        }
+        if let Some(info) = gltf_material.emissive_texture() {
+            transforms.emissive = Self::texture_transform(&info);
+        }
+
+        material.alpha_mode = match gltf_material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => {
+                AlphaMode::Mask(gltf_material.alpha_cutoff().unwrap_or(0.5))
             }
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        };
+        material.double_sided = gltf_material.double_sided();
+        Material::PBR(material)
+    }
 
-            // Load tangents if we have them.
-            if let Some(tangents) = reader.read_tangents() {
-                for (i, tangent) in tangents.enumerate() {
-                    vertices[i].tangent = Vec4::from(tangent.clone());
-                }
-            } else {
-                // TODO: Calculate tangents if we don't have them.
+    fn texture_transform(info: &gltf::texture::Info<'_>) -> TextureTransform {
+        match info.texture_transform() {
+            Some(transform) => TextureTransform {
+                offset: Vec2::from(transform.offset()),
+                rotation: transform.rotation(),
+                scale: Vec2::from(transform.scale()),
+            },
+            None => TextureTransform::default(),
+        }
+    }
+
+    fn create_sub_mesh<'a, 's, F>(
+        device: &wgpu::Device,
+        primitive: &'a gltf::Primitive<'a>,
+        get_buffer_data: F,
+        material_index: i32,
+    ) -> SubMesh
+    where
+        F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        let reader = primitive.reader(get_buffer_data);
+        let positions: Vec<_> = reader
+            .read_positions()
+            .map(|iter| iter.collect())
+            .ok_or(format!("mesh primitive is missing positions"))
+            .unwrap();
+
+        let mut vertices: Vec<MeshVertexData> = positions
+            .iter()
+            .map(|pos| MeshVertexData {
+                position: Vec3::from(pos.clone()),
+                ..MeshVertexData::default()
+            })
+            .collect();
+
+        let has_normals = if let Some(normals) = reader.read_normals() {
+            for (i, normal) in normals.enumerate() {
+                vertices[i].normal = Vec3::from(normal.clone());
+            }
+            true
+        } else {
+            false
+        };
+        let has_uvs = if let Some(uvs) = reader.read_tex_coords(0) {
+            for (i, uv) in uvs.into_f32().enumerate() {
+                vertices[i].uv = Vec2::from(uv.clone());
             }
+            true
+        } else {
+            false
+        };
 
-            let indices: Vec<u32> = if let Some(index_enum) = reader.read_indices() {
-                index_enum.into_u32().collect()
-            } else {
-                panic!("model doesn't have indices");
-            };
-
-            let gltf_material: gltf::Material<'_> = primitive.material();
-            let pbr = gltf_material.pbr_metallic_roughness();
-
-            let color_factor = pbr.base_color_factor();
-            let mut main_texture = None;
-            let color = Vec4::new(
-                color_factor[0],
-                color_factor[1],
-                color_factor[2],
-                color_factor[3],
-            );
+        // Load tangents if we have them.
+        let has_tangents = if let Some(tangents) = reader.read_tangents() {
+            for (i, tangent) in tangents.enumerate() {
+                vertices[i].tangent = Vec4::from(tangent.clone());
+            }
+            true
+        } else {
+            false
+        };
 
-            let info = pbr.base_color_texture();
-            if info.is_some() {
-                let info = info.unwrap();
-                let tex = info.texture();
-                let image: Option<&gltf::Image<'_>> = images.get(tex.index());
-                if image.is_some() {
-                    let image = image.unwrap();
-                    let source = image.source();
-                    match source {
-                        gltf::image::Source::Uri { uri, .. } => {
-                            let main_texture_file_name = Some(
-                                Path::new(&uri)
-                                    .file_name()
-                                    .and_then(OsStr::to_str)
-                                    .unwrap()
-                                    .to_string(),
-                            );
-                            if main_texture_file_name.is_some() {
-                                main_texture = Some(main_texture_file_name.unwrap());
-                            }
-                        }
-                        _ => (),
-                    }
+        let mut indices: Vec<u32> = if let Some(index_enum) = reader.read_indices() {
+            index_enum.into_u32().collect()
+        } else {
+            panic!("model doesn't have indices");
+        };
+
+        let primitive_topology = Self::get_primitive_mode(primitive.mode());
+
+        // Normals and tangents are only generated for triangles.
+        if primitive_topology == wgpu::PrimitiveTopology::TriangleList {
+            if !has_normals {
+                Self::generate_flat_normals(&mut vertices, &mut indices);
+            }
+            if !has_tangents {
+                if has_uvs {
+                    Self::generate_tangents(&mut vertices, &mut indices);
+                } else {
+                    log::warn!(
+                        "Mesh: A primitive has no tangents or uvs to generate them from, normal maps won't work on it."
+                    );
                 }
             }
+        }
 
-            let material_index = material_start_index + materials.len() as i32;
-            let material = PBRMaterial::new(
-                main_texture.unwrap_or("white.png".to_string()),
-                color,
-                material_index,
-            );
-            materials.push(Material::PBR(material));
+        let vertex_buffer = device
+            .create_buffer_with_data(&bytemuck::cast_slice(&vertices), wgpu::BufferUsage::VERTEX);
+        let index_buffer = device
+            .create_buffer_with_data(&bytemuck::cast_slice(&indices), wgpu::BufferUsage::INDEX);
+        let index_count = indices.len();
+        let bounds = BoundingBox::from_points(vertices.iter().map(|vertex| &vertex.position));
 
-            // mesh.calculate_tangents();
+        SubMesh {
+            vertices,
+            indices,
+            index_count,
+            mode: primitive_topology,
+            material_id: primitive.material().index(),
+            vertex_buffer,
+            index_buffer,
+            bounds,
+            material_index,
+        }
+    }
 
-            let primitive_topology = Self::get_primitive_mode(primitive.mode());
+    // The glTF spec asks for flat normals when a primitive has none. Vertices can't be shared
+    // between faces then, so every triangle gets its own three.
+    fn generate_flat_normals(vertices: &mut Vec<MeshVertexData>, indices: &mut Vec<u32>) {
+        let mut flat_vertices = Vec::with_capacity(indices.len());
+        for triangle in indices.chunks_exact(3) {
+            let mut corners = [
+                vertices[triangle[0] as usize],
+                vertices[triangle[1] as usize],
+                vertices[triangle[2] as usize],
+            ];
+            let normal = (corners[1].position - corners[0].position)
+                .cross(&(corners[2].position - corners[0].position))
+                // Degenerate triangles have no normal of their own.
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(|| Vec3::new(0.0, 1.0, 0.0));
+            for corner in corners.iter_mut() {
+                corner.normal = normal;
+            }
+            flat_vertices.extend_from_slice(&corners);
+        }
+        *indices = (0..flat_vertices.len() as u32).collect();
+        *vertices = flat_vertices;
+    }
 
-            let vertex_buffer = device.create_buffer_with_data(
-                &bytemuck::cast_slice(&vertices),
-                wgpu::BufferUsage::VERTEX,
-            );
-            let index_buffer = device
-                .create_buffer_with_data(&bytemuck::cast_slice(&indices), wgpu::BufferUsage::INDEX);
-            let index_count = indices.len();
-
-            sub_meshes.push(SubMesh {
-                vertices,
-                indices,
-                index_count,
-                mode: primitive_topology,
-                material_id: primitive.material().index(),
-                vertex_buffer,
-                index_buffer,
-                material_index,
+    // Tangents are generated with MikkTSpace, the same algorithm the glTF spec and most
+    // baking tools use, so normal maps baked elsewhere line up. It can give a shared vertex a
+    // different tangent on each face, so it runs on unshared vertices which are merged again
+    // afterwards wherever the tangents agree.
+    fn generate_tangents(vertices: &mut Vec<MeshVertexData>, indices: &mut Vec<u32>) {
+        let mut corners: Vec<MeshVertexData> = indices
+            .iter()
+            .map(|index| vertices[*index as usize])
+            .collect();
+        let mut geometry = TangentGeometry {
+            corners: &mut corners,
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            log::warn!("Mesh: Unable to generate tangents for a primitive.");
+            return;
+        }
+
+        // Compared bit for bit, the same way the corners were copied.
+        let mut welded: HashMap<[u32; 12], u32> = HashMap::new();
+        vertices.clear();
+        for (corner, index) in corners.iter().zip(indices.iter_mut()) {
+            *index = *welded.entry(bytemuck::cast(*corner)).or_insert_with(|| {
+                vertices.push(*corner);
+                vertices.len() as u32 - 1
             });
         }
+    }
+
+    /// Bounds of every sub mesh in model space.
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
 
-        (Mesh { sub_meshes }, materials)
+    // Textures are looked up by file name in the asset manager, embedded images aren't supported.
+    fn texture_file_name(
+        images: &[gltf::Image<'_>],
+        texture: &gltf::Texture<'_>,
+    ) -> Option<String> {
+        let image = images.get(texture.source().index())?;
+        match image.source() {
+            gltf::image::Source::Uri { uri, .. } => Path::new(uri)
+                .file_name()
+                .and_then(OsStr::to_str)
+                .map(|file_name| file_name.to_string()),
+            _ => None,
+        }
     }
 
     fn get_primitive_mode(mode: gltf::mesh::Mode) -> wgpu::PrimitiveTopology {
@@ -202,3 +459,101 @@ impl Mesh {
         }
     }
 }
+
+// Triangle list geometry handed to MikkTSpace, three corners per face.
+struct TangentGeometry<'a> {
+    corners: &'a mut [MeshVertexData],
+}
+
+impl<'a> bevy_mikktspace::Geometry for TangentGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.corners.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.corners[face * 3 + vert].position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.corners[face * 3 + vert].normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.corners[face * 3 + vert].uv.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corners[face * 3 + vert].tangent = Vec4::from(tangent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32) -> MeshVertexData {
+        MeshVertexData {
+            position: Vec3::new(x, y, 0.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            uv: Vec2::new(x, y),
+            ..MeshVertexData::default()
+        }
+    }
+
+    #[test]
+    fn flat_normals_unshare_vertices() {
+        // The second triangle is a line, (2, 0) lies on the edge from (0, 0) to (1, 0).
+        let mut vertices = vec![
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(2.0, 0.0),
+        ];
+        let mut indices = vec![0, 1, 2, 0, 1, 3];
+        SubMesh::generate_flat_normals(&mut vertices, &mut indices);
+
+        assert_eq!(indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(vertices[5].position, Vec3::new(2.0, 0.0, 0.0));
+        for vertex in &vertices[0..3] {
+            assert_eq!(vertex.normal, Vec3::new(0.0, 0.0, 1.0));
+        }
+        for vertex in &vertices[3..6] {
+            assert_eq!(vertex.normal, Vec3::new(0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn tangents_follow_the_uvs_of_a_quad() {
+        let mut vertices = vec![
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+            vertex(0.0, 1.0),
+        ];
+        let mut indices = vec![0, 1, 2, 0, 2, 3];
+        SubMesh::generate_tangents(&mut vertices, &mut indices);
+
+        // Both triangles agree on the shared corners, so they're merged back together.
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices.len(), 6);
+        let corners = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+        ];
+        for (index, (x, y)) in indices.iter().zip(corners.iter()) {
+            assert_eq!(vertices[*index as usize].position, Vec3::new(*x, *y, 0.0));
+        }
+        for vertex in vertices.iter() {
+            assert_eq!(vertex.tangent, Vec4::new(1.0, 0.0, 0.0, 1.0));
+        }
+    }
+}
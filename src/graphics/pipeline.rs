@@ -1,16 +1,33 @@
-use super::{material::Shader, resources::RenderTarget};
-use crate::AssetManager;
+use super::{
+    material::{Shader, ShaderStage},
+    resources::RenderTarget,
+};
+use crate::{scene::components::transform::LocalUniform, AssetManager};
 
 #[derive(Debug)]
 pub struct Pipeline {
     pub pipeline: wgpu::RenderPipeline,
     pub bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    // Set of the first layout, one when the local bindings are included at set 0.
+    first_set: u32,
+}
+
+impl Pipeline {
+    /// The layout of `layout(set = ...)` in the pipeline's shaders.
+    pub fn bind_group_layout(&self, set: u32) -> &wgpu::BindGroupLayout {
+        set.checked_sub(self.first_set)
+            .and_then(|index| self.bind_group_layouts.get(index as usize))
+            .unwrap_or_else(|| panic!("Pipeline: There's no bind group layout for set {}", set))
+    }
 }
 
 #[derive(Debug)]
 pub struct BindGroupWithData {
     pub(crate) uniform_buf: wgpu::Buffer,
     pub(crate) bind_group: wgpu::BindGroup,
+    // What was last uploaded, so changes can be picked up before drawing.
+    pub(crate) uniform: Vec<u8>,
+    pub(crate) textures: Vec<String>,
 }
 
 pub trait SimplePipeline: std::fmt::Debug + Send + Sync + 'static {
@@ -29,7 +46,7 @@ pub trait SimplePipeline: std::fmt::Debug + Send + Sync + 'static {
         depth: Option<&wgpu::TextureView>,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
         input: Option<&RenderTarget>,
         output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -44,20 +61,39 @@ pub trait SimplePipelineDesc: std::fmt::Debug {
         &mut self,
         asset_manager: &'a AssetManager,
         renderer: &'a mut crate::graphics::Renderer,
-        local_bind_group_layout: Option<&'a wgpu::BindGroupLayout>,
+        include_local_bindings: bool,
     ) -> Pipeline {
         let mut_device = &mut renderer.device;
         let shader = self.load_shader(asset_manager);
+        let shader_variant = shader.variant(mut_device, &self.shader_features());
+        let reflection = &shader_variant.reflection;
+
+        let first_set = if include_local_bindings { 1 } else { 0 };
+        if include_local_bindings && reflection.buffer_size(0, 0).is_some() {
+            reflection.check_buffer_size(
+                shader.name(),
+                0,
+                0,
+                std::mem::size_of::<LocalUniform>() as u64,
+            );
+        }
+        for (set, binding, size) in self.uniform_sizes() {
+            reflection.check_buffer_size(shader.name(), set, binding, size);
+        }
         let vertex_stage = wgpu::ProgrammableStageDescriptor {
-            module: &shader.vertex,
-            entry_point: "main",
+            module: &shader_variant.vertex,
+            entry_point: shader.entry_point(ShaderStage::Vertex),
         };
         let fragment_stage = Some(wgpu::ProgrammableStageDescriptor {
-            module: &shader.fragment,
-            entry_point: "main",
+            module: &shader_variant.fragment,
+            entry_point: shader.entry_point(ShaderStage::Fragment),
         });
 
-        let bind_group_layouts = self.create_layout(mut_device);
+        let reflected_entries: Vec<Vec<wgpu::BindGroupLayoutEntry>> = (first_set
+            ..reflection.set_count())
+            .map(|set| reflection.bind_group_layout_entries(set))
+            .collect();
+        let bind_group_layouts = self.create_layout(mut_device, &reflected_entries);
         let rasterization_state = self.rasterization_state_desc();
         let primitive_topology = self.primitive_topology();
         let color_states = self.color_states_desc(&renderer.sc_desc);
@@ -71,8 +107,8 @@ pub trait SimplePipelineDesc: std::fmt::Debug {
             .iter()
             .map(|bind_group_layout| bind_group_layout)
             .collect::<Vec<&wgpu::BindGroupLayout>>();
-        if local_bind_group_layout.is_some() {
-            total_bind_group_layouts.insert(0, local_bind_group_layout.as_ref().unwrap());
+        if include_local_bindings {
+            total_bind_group_layouts.insert(0, &renderer.local_bind_group_layout);
         }
 
         // Once we create the layout we don't need the bind group layout.
@@ -90,6 +126,28 @@ pub trait SimplePipelineDesc: std::fmt::Debug {
             })
             .collect();
 
+        for (location, format) in reflection.vertex_inputs() {
+            let attribute = vertex_buffers
+                .iter()
+                .flat_map(|buffer| buffer.attributes.iter())
+                .find(|attribute| attribute.shader_location == *location);
+            match attribute {
+                Some(attribute) if attribute.format == *format => {}
+                Some(attribute) => panic!(
+                    "Pipeline: {} reads vertex input {} as {:?} but the vertex buffer has {:?}",
+                    shader.name(),
+                    location,
+                    format,
+                    attribute.format
+                ),
+                None => panic!(
+                    "Pipeline: {} reads vertex input {} which no vertex buffer has",
+                    shader.name(),
+                    location
+                ),
+            }
+        }
+
         let vertex_state = wgpu::VertexStateDescriptor {
             index_format: vertex_state_builder.index_format,
             vertex_buffers: &vertex_buffers,
@@ -111,13 +169,40 @@ pub trait SimplePipelineDesc: std::fmt::Debug {
         Pipeline {
             pipeline,
             bind_group_layouts,
+            first_set,
         }
     }
 
     // TODO: Support other types of shaders like compute.
     // Also support having only a vertex shader.
     fn load_shader<'a>(&self, asset_manager: &'a AssetManager) -> &'a Shader;
-    fn create_layout(&self, _device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout>;
+    /// Features from the shader's manifest to compile the pipeline's variant with.
+    fn shader_features(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Bind group layouts for every set after the local bindings. `reflected_entries` holds
+    /// what the shader declares in each of those sets, override this only for bindings
+    /// reflection can't describe like dynamic buffers.
+    fn create_layout(
+        &self,
+        device: &mut wgpu::Device,
+        reflected_entries: &[Vec<wgpu::BindGroupLayoutEntry>],
+    ) -> Vec<wgpu::BindGroupLayout> {
+        reflected_entries
+            .iter()
+            .map(|entries| {
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    bindings: entries,
+                    label: None,
+                })
+            })
+            .collect()
+    }
+    /// Sizes of the rust structs uploaded to the shader's buffers as `(set, binding, size)`.
+    /// They're checked against the shader when the pipeline is built.
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        Vec::new()
+    }
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor;
     fn primitive_topology(&self) -> wgpu::PrimitiveTopology;
     fn color_states_desc(
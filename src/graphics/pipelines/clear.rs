@@ -0,0 +1,217 @@
+use specs::WorldExt;
+use std::mem;
+
+use crate::{
+    graphics::{
+        pipeline::VertexStateBuilder, renderer::DEPTH_FORMAT, resources::RenderTarget, Pipeline,
+        SimplePipeline, SimplePipelineDesc,
+    },
+    scene::{
+        components::{CameraClear, CameraData},
+        resources::CurrentCamera,
+    },
+    AssetManager,
+};
+
+#[derive(Debug)]
+pub struct ClearPipeline {
+    color_buffer: wgpu::Buffer,
+    global_bind_group: wgpu::BindGroup,
+    clear: Option<CameraClear>,
+}
+
+impl SimplePipeline for ClearPipeline {
+    fn prepare(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        device: &mut wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        _pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) {
+        self.clear = None;
+        let current_camera = world.try_fetch::<CurrentCamera>();
+        if current_camera.is_none() {
+            return;
+        }
+        let camera_data = world.read_component::<CameraData>();
+        let camera_data = camera_data.get(current_camera.unwrap().entity);
+        if camera_data.is_none() {
+            return;
+        }
+        let clear = camera_data.unwrap().clear;
+        self.clear = Some(clear);
+
+        if let CameraClear::Color(color) = clear {
+            let color = [color.x, color.y, color.z, color.w];
+            let color_buffer = device
+                .create_buffer_with_data(bytemuck::bytes_of(&color), wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(
+                &color_buffer,
+                0,
+                &self.color_buffer,
+                0,
+                std::mem::size_of::<[f32; 4]>() as u64,
+            );
+        }
+    }
+
+    fn render(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        depth: Option<&wgpu::TextureView>,
+        _device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: Option<&wgpu::TextureView>,
+        _input: Option<&RenderTarget>,
+        _output: Option<&RenderTarget>,
+        pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) -> Option<RenderTarget> {
+        if self.clear.is_none() {
+            return None;
+        }
+        let current_camera = world.fetch::<CurrentCamera>();
+
+        // Load ops clear the whole attachment, so a camera that only covers part of the
+        // target draws its clear color instead.
+        let (load_op, clear_color, draw_color) = match self.clear.unwrap() {
+            CameraClear::Color(color) => {
+                let clear_color = wgpu::Color {
+                    r: color.x as f64,
+                    g: color.y as f64,
+                    b: color.z as f64,
+                    a: color.w as f64,
+                };
+                if current_camera.is_fullscreen() {
+                    (wgpu::LoadOp::Clear, clear_color, false)
+                } else {
+                    (wgpu::LoadOp::Load, clear_color, true)
+                }
+            }
+            CameraClear::DepthOnly => (wgpu::LoadOp::Load, wgpu::Color::BLACK, false),
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: frame.unwrap(),
+                resolve_target: None,
+                load_op,
+                store_op: wgpu::StoreOp::Store,
+                clear_color,
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: depth.unwrap(),
+                depth_load_op: wgpu::LoadOp::Clear,
+                depth_store_op: wgpu::StoreOp::Store,
+                stencil_load_op: wgpu::LoadOp::Clear,
+                stencil_store_op: wgpu::StoreOp::Store,
+                clear_depth: 1.0,
+                clear_stencil: 0,
+            }),
+        });
+
+        if draw_color {
+            current_camera.set_viewport(&mut render_pass);
+            render_pass.set_pipeline(&pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.global_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        None
+    }
+}
+
+/// Clears the current camera's viewport and depth buffer. Runs before every other scene node.
+#[derive(Debug, Default)]
+pub struct ClearPipelineDesc;
+
+impl SimplePipelineDesc for ClearPipelineDesc {
+    type Pipeline = ClearPipeline;
+
+    fn load_shader<'a>(
+        &self,
+        asset_manager: &'a crate::AssetManager,
+    ) -> &'a crate::graphics::material::Shader {
+        asset_manager.get_shader("clear.shader")
+    }
+
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<[f32; 4]>() as u64)]
+    }
+
+    fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
+        wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }
+    }
+
+    fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
+        wgpu::PrimitiveTopology::TriangleList
+    }
+
+    fn color_states_desc(
+        &self,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> Vec<wgpu::ColorStateDescriptor> {
+        vec![wgpu::ColorStateDescriptor {
+            format: sc_desc.format,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }]
+    }
+
+    fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        // Depth is cleared by the load op, the pipeline only has to match the attachment.
+        Some(wgpu::DepthStencilStateDescriptor {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_read_mask: 0,
+            stencil_write_mask: 0,
+        })
+    }
+
+    fn vertex_state_desc(&self) -> VertexStateBuilder {
+        let mut vertex_state_builder = VertexStateBuilder::new();
+        vertex_state_builder.set_index_format(wgpu::IndexFormat::Uint16);
+
+        vertex_state_builder
+    }
+
+    fn build(
+        self,
+        device: &wgpu::Device,
+        bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+    ) -> ClearPipeline {
+        let color_buffer = device.create_buffer_with_data(
+            bytemuck::bytes_of(&[0.0f32, 0.0, 0.0, 1.0]),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layouts[0],
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &color_buffer,
+                    range: 0..std::mem::size_of::<[f32; 4]>() as u64,
+                },
+            }],
+            label: None,
+        });
+
+        ClearPipeline {
+            color_buffer,
+            global_bind_group,
+            clear: None,
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use super::{PointLight, SpotLight};
+use nalgebra_glm::{Mat4, Vec2, Vec4};
+
+pub const CLUSTER_COUNT_X: usize = 16;
+pub const CLUSTER_COUNT_Y: usize = 9;
+pub const CLUSTER_COUNT_Z: usize = 24;
+pub const CLUSTER_COUNT: usize = CLUSTER_COUNT_X * CLUSTER_COUNT_Y * CLUSTER_COUNT_Z;
+
+/// Splits the view frustum into a grid of clusters and works out which point and spot lights
+/// touch each one. Clusters are evenly spaced in screen space and exponentially spaced in depth.
+///
+/// The result is laid out for the shaders: `clusters` has one entry per cluster and
+/// `indices` holds every cluster's point light indices followed by its spot light indices.
+/// It's a world resource, `UpdateLightClusters` builds it once for each camera and uploads it
+/// to the `LightBuffers` every PBR pipeline binds.
+#[derive(Debug, Default)]
+pub struct LightClusters {
+    pub(crate) point_lights: Vec<PointLight>,
+    pub(crate) spot_lights: Vec<SpotLight>,
+    // x: offset into `indices`, y: point light count, z: spot light count.
+    pub(crate) clusters: Vec<[u32; 4]>,
+    pub(crate) indices: Vec<u32>,
+    point_lists: Vec<Vec<u32>>,
+    spot_lists: Vec<Vec<u32>>,
+}
+
+/// How view space depth is split into slices, the same values go to the shaders as
+/// `cluster_depth`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClusterDepth {
+    pub(crate) z_near: f32,
+    pub(crate) z_far: f32,
+    /// Orthographic cameras get linear slices, their near plane can be at or behind the eye.
+    pub(crate) orthographic: bool,
+}
+
+impl ClusterDepth {
+    // Has to match `get_cluster` in lighting.glsl.
+    fn slice(&self, view_z: f32) -> usize {
+        let slice = if self.orthographic {
+            (view_z - self.z_near) / (self.z_far - self.z_near)
+        } else {
+            let view_z = view_z.max(self.z_near);
+            (view_z / self.z_near).ln() / (self.z_far / self.z_near).ln()
+        };
+        let slice = (slice * CLUSTER_COUNT_Z as f32).floor();
+        slice.max(0.0).min(CLUSTER_COUNT_Z as f32 - 1.0) as usize
+    }
+}
+
+impl LightClusters {
+    pub(crate) fn build(
+        &mut self,
+        view: &Mat4,
+        projection: &Mat4,
+        depth: ClusterDepth,
+        point_lights: Vec<PointLight>,
+        spot_lights: Vec<SpotLight>,
+    ) {
+        self.point_lights = point_lights;
+        self.spot_lights = spot_lights;
+        self.point_lists.resize_with(CLUSTER_COUNT, Vec::new);
+        self.spot_lists.resize_with(CLUSTER_COUNT, Vec::new);
+        self.point_lists.iter_mut().for_each(|list| list.clear());
+        self.spot_lists.iter_mut().for_each(|list| list.clear());
+
+        for (index, light) in self.point_lights.iter().enumerate() {
+            Self::assign(
+                &mut self.point_lists,
+                index as u32,
+                light.position,
+                view,
+                projection,
+                depth,
+            );
+        }
+        for (index, light) in self.spot_lights.iter().enumerate() {
+            Self::assign(
+                &mut self.spot_lists,
+                index as u32,
+                light.position,
+                view,
+                projection,
+                depth,
+            );
+        }
+
+        self.clusters.clear();
+        self.indices.clear();
+        for (point_list, spot_list) in self.point_lists.iter().zip(self.spot_lists.iter()) {
+            self.clusters.push([
+                self.indices.len() as u32,
+                point_list.len() as u32,
+                spot_list.len() as u32,
+                0,
+            ]);
+            self.indices.extend_from_slice(point_list);
+            self.indices.extend_from_slice(spot_list);
+        }
+    }
+
+    /// Adds the light to every cluster its bounding sphere overlaps.
+    /// `position` is in world space with the light's range in w.
+    fn assign(
+        lists: &mut Vec<Vec<u32>>,
+        index: u32,
+        position: Vec4,
+        view: &Mat4,
+        projection: &Mat4,
+        depth: ClusterDepth,
+    ) {
+        let range = position.w;
+        let full_range = (0, CLUSTER_COUNT_X - 1, 0, CLUSTER_COUNT_Y - 1);
+
+        let ((x0, x1, y0, y1), (z0, z1)) = if range <= 0.0 {
+            // Lights without a range reach everything.
+            (full_range, (0, CLUSTER_COUNT_Z - 1))
+        } else {
+            let center = view * Vec4::new(position.x, position.y, position.z, 1.0);
+            let min_z = center.z - range;
+            let max_z = center.z + range;
+            if max_z < depth.z_near || min_z > depth.z_far {
+                return;
+            }
+            let slices = (depth.slice(min_z), depth.slice(max_z));
+
+            // Once the sphere crosses a perspective camera's near plane its projection isn't
+            // bounded anymore.
+            if !depth.orthographic && min_z <= depth.z_near {
+                (full_range, slices)
+            } else {
+                let mut min = Vec2::new(std::f32::MAX, std::f32::MAX);
+                let mut max = Vec2::new(std::f32::MIN, std::f32::MIN);
+                for corner in 0..8 {
+                    let offset = Vec4::new(
+                        if corner & 1 == 0 { -range } else { range },
+                        if corner & 2 == 0 { -range } else { range },
+                        if corner & 4 == 0 { -range } else { range },
+                        0.0,
+                    );
+                    let clip = projection * (center + offset);
+                    let ndc = Vec2::new(clip.x / clip.w, clip.y / clip.w);
+                    min = nalgebra_glm::min2(&min, &ndc);
+                    max = nalgebra_glm::max2(&max, &ndc);
+                }
+                if max.x < -1.0 || min.x > 1.0 || max.y < -1.0 || min.y > 1.0 {
+                    return;
+                }
+                (
+                    (
+                        Self::tile(min.x, CLUSTER_COUNT_X),
+                        Self::tile(max.x, CLUSTER_COUNT_X),
+                        Self::tile(min.y, CLUSTER_COUNT_Y),
+                        Self::tile(max.y, CLUSTER_COUNT_Y),
+                    ),
+                    slices,
+                )
+            }
+        };
+
+        for z in z0..=z1 {
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let cluster = x + y * CLUSTER_COUNT_X + z * CLUSTER_COUNT_X * CLUSTER_COUNT_Y;
+                    lists[cluster].push(index);
+                }
+            }
+        }
+    }
+
+    fn tile(ndc: f32, count: usize) -> usize {
+        let tile = ((ndc * 0.5 + 0.5) * count as f32).floor();
+        tile.max(0.0).min(count as f32 - 1.0) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERSPECTIVE: ClusterDepth = ClusterDepth {
+        z_near: 0.1,
+        z_far: 100.0,
+        orthographic: false,
+    };
+    const ORTHOGRAPHIC: ClusterDepth = ClusterDepth {
+        z_near: -10.0,
+        z_far: 10.0,
+        orthographic: true,
+    };
+
+    fn cluster(x: usize, y: usize, z: usize) -> usize {
+        x + y * CLUSTER_COUNT_X + z * CLUSTER_COUNT_X * CLUSTER_COUNT_Y
+    }
+
+    fn point_light(x: f32, y: f32, z: f32, range: f32) -> PointLight {
+        PointLight {
+            position: Vec4::new(x, y, z, range),
+            ..PointLight::default()
+        }
+    }
+
+    fn build(depth: ClusterDepth, point_lights: Vec<PointLight>) -> LightClusters {
+        let projection = if depth.orthographic {
+            nalgebra_glm::ortho_lh_no(-8.0, 8.0, -4.5, 4.5, depth.z_near, depth.z_far)
+        } else {
+            nalgebra_glm::perspective_fov_lh_no(
+                std::f32::consts::FRAC_PI_2,
+                16.0,
+                9.0,
+                depth.z_near,
+                depth.z_far,
+            )
+        };
+        let mut light_clusters = LightClusters::default();
+        light_clusters.build(
+            &Mat4::identity(),
+            &projection,
+            depth,
+            point_lights,
+            Vec::new(),
+        );
+        light_clusters
+    }
+
+    #[test]
+    fn perspective_slices_are_exponential() {
+        assert_eq!(PERSPECTIVE.slice(0.1), 0);
+        assert_eq!(PERSPECTIVE.slice(0.01), 0);
+        assert_eq!(PERSPECTIVE.slice(99.0), CLUSTER_COUNT_Z - 1);
+        assert_eq!(PERSPECTIVE.slice(1000.0), CLUSTER_COUNT_Z - 1);
+        // Every slice covers the same ratio of depths, 1000^(1/24) here.
+        let ratio = 1000.0f32.powf(1.0 / CLUSTER_COUNT_Z as f32);
+        for slice in 0..CLUSTER_COUNT_Z {
+            let middle = 0.1 * ratio.powf(slice as f32 + 0.5);
+            assert_eq!(PERSPECTIVE.slice(middle), slice);
+        }
+    }
+
+    #[test]
+    fn orthographic_slices_are_linear_with_any_near_plane() {
+        assert_eq!(ORTHOGRAPHIC.slice(-10.0), 0);
+        assert_eq!(ORTHOGRAPHIC.slice(-20.0), 0);
+        assert_eq!(ORTHOGRAPHIC.slice(-5.0), 6);
+        assert_eq!(ORTHOGRAPHIC.slice(0.0), 12);
+        assert_eq!(ORTHOGRAPHIC.slice(9.9), CLUSTER_COUNT_Z - 1);
+        assert_eq!(ORTHOGRAPHIC.slice(20.0), CLUSTER_COUNT_Z - 1);
+
+        let at_the_eye = ClusterDepth {
+            z_near: 0.0,
+            ..ORTHOGRAPHIC
+        };
+        assert_eq!(at_the_eye.slice(0.0), 0);
+        assert_eq!(at_the_eye.slice(5.0), 12);
+    }
+
+    #[test]
+    fn light_only_reaches_the_clusters_around_it() {
+        let light_clusters = build(PERSPECTIVE, vec![point_light(0.0, 0.0, 10.0, 1.0)]);
+        let reached: Vec<usize> = (0..CLUSTER_COUNT)
+            .filter(|cluster| light_clusters.clusters[*cluster][1] == 1)
+            .collect();
+
+        assert!(reached.contains(&cluster(8, 4, PERSPECTIVE.slice(10.0))));
+        assert!(!reached.contains(&cluster(0, 0, PERSPECTIVE.slice(10.0))));
+        assert!(!reached.contains(&cluster(8, 4, 0)));
+        assert!(reached.len() < CLUSTER_COUNT / 10);
+        for cluster in reached {
+            let offset = light_clusters.clusters[cluster][0] as usize;
+            assert_eq!(light_clusters.indices[offset], 0);
+        }
+    }
+
+    #[test]
+    fn lights_outside_the_frustum_are_skipped() {
+        let light_clusters = build(
+            PERSPECTIVE,
+            vec![
+                point_light(0.0, 0.0, -10.0, 1.0),
+                point_light(0.0, 0.0, 200.0, 1.0),
+                point_light(100.0, 0.0, 10.0, 1.0),
+            ],
+        );
+        assert!(light_clusters
+            .clusters
+            .iter()
+            .all(|cluster| cluster[1] == 0));
+        assert!(light_clusters.indices.is_empty());
+    }
+
+    #[test]
+    fn lights_without_a_range_reach_every_cluster() {
+        let light_clusters = build(
+            PERSPECTIVE,
+            vec![
+                point_light(0.0, 0.0, 10.0, 1.0),
+                point_light(0.0, 0.0, -10.0, 0.0),
+            ],
+        );
+        assert_eq!(light_clusters.clusters.len(), CLUSTER_COUNT);
+        for cluster in light_clusters.clusters.iter() {
+            let offset = cluster[0] as usize;
+            let point_lights = &light_clusters.indices[offset..offset + cluster[1] as usize];
+            assert_eq!(point_lights.last(), Some(&1));
+        }
+    }
+
+    #[test]
+    fn sphere_crossing_the_near_plane_covers_the_whole_screen() {
+        let light_clusters = build(PERSPECTIVE, vec![point_light(0.0, 0.0, 0.5, 1.0)]);
+        for x in 0..CLUSTER_COUNT_X {
+            for y in 0..CLUSTER_COUNT_Y {
+                assert_eq!(light_clusters.clusters[cluster(x, y, 0)][1], 1);
+            }
+        }
+    }
+
+    #[test]
+    fn orthographic_lights_behind_the_eye_are_assigned() {
+        let light_clusters = build(ORTHOGRAPHIC, vec![point_light(0.0, 0.0, -5.0, 1.0)]);
+        let reached: Vec<usize> = (0..CLUSTER_COUNT)
+            .filter(|cluster| light_clusters.clusters[*cluster][1] == 1)
+            .collect();
+
+        // 1 unit in every direction is one tile across and 1.2 slices deep.
+        assert!(reached.contains(&cluster(8, 4, 6)));
+        assert!(!reached.contains(&cluster(8, 4, 12)));
+        assert!(!reached.contains(&cluster(0, 0, 6)));
+        assert!(reached.len() < CLUSTER_COUNT / 10);
+    }
+}
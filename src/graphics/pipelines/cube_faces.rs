@@ -0,0 +1,192 @@
+use super::equirectangular::strip_to_cube_map;
+use crate::{
+    graphics::{
+        pipeline::VertexStateBuilder, resources::RenderTarget, Pipeline, SimplePipeline,
+        SimplePipelineDesc,
+    },
+    AssetManager,
+};
+
+/// Builds a cube map from six face images in +X, -X, +Y, -Y, +Z, -Z order.
+#[derive(Debug)]
+pub struct CubeFacesPipeline {
+    faces: [String; 6],
+    size: f32,
+    sampler: wgpu::Sampler,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl SimplePipeline for CubeFacesPipeline {
+    fn prepare(
+        &mut self,
+        asset_manager: &mut AssetManager,
+        device: &mut wgpu::Device,
+        _encoder: &mut wgpu::CommandEncoder,
+        pipeline: &Pipeline,
+        _world: &mut specs::World,
+    ) {
+        let images: Vec<_> = self
+            .faces
+            .iter()
+            .map(|face| asset_manager.get_image(face.clone()))
+            .collect();
+
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.bind_group_layouts[0],
+            bindings: &[
+                wgpu::Binding {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&images[0].view),
+                },
+                wgpu::Binding {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&images[1].view),
+                },
+                wgpu::Binding {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&images[2].view),
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&images[3].view),
+                },
+                wgpu::Binding {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&images[4].view),
+                },
+                wgpu::Binding {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&images[5].view),
+                },
+                wgpu::Binding {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+            label: None,
+        }));
+    }
+
+    fn render(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        _depth: Option<&wgpu::TextureView>,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        _frame: Option<&wgpu::TextureView>,
+        _input: Option<&RenderTarget>,
+        output: Option<&RenderTarget>,
+        pipeline: &Pipeline,
+        _world: &mut specs::World,
+    ) -> Option<RenderTarget> {
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &output.as_ref().unwrap().texture_view,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(&pipeline.pipeline);
+            render_pass.set_bind_group(0, self.bind_group.as_ref().unwrap(), &[]);
+            render_pass.draw(0..6, 0..6);
+        }
+
+        Some(strip_to_cube_map(
+            device,
+            encoder,
+            output.as_ref().unwrap(),
+            self.size,
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CubeFacesPipelineDesc {
+    faces: [String; 6],
+    size: f32,
+}
+
+impl CubeFacesPipelineDesc {
+    pub fn new(faces: [String; 6], size: f32) -> Self {
+        Self { faces, size }
+    }
+}
+
+impl SimplePipelineDesc for CubeFacesPipelineDesc {
+    type Pipeline = CubeFacesPipeline;
+
+    fn load_shader<'a>(
+        &self,
+        asset_manager: &'a crate::AssetManager,
+    ) -> &'a crate::graphics::material::Shader {
+        asset_manager.get_shader("cube_faces_to_cubemap.shader")
+    }
+
+    fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
+        wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }
+    }
+    fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
+        wgpu::PrimitiveTopology::TriangleList
+    }
+    fn color_states_desc(
+        &self,
+        _sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> Vec<wgpu::ColorStateDescriptor> {
+        vec![wgpu::ColorStateDescriptor {
+            format: wgpu::TextureFormat::Rgba32Float,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }]
+    }
+
+    fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        None
+    }
+
+    fn vertex_state_desc(&self) -> VertexStateBuilder {
+        let vertex_state_builder = VertexStateBuilder::new();
+        vertex_state_builder
+    }
+
+    fn build(
+        self,
+        device: &wgpu::Device,
+        _bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+    ) -> CubeFacesPipeline {
+        // Clamped so the faces don't bleed into each other along the edges.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare: wgpu::CompareFunction::Undefined,
+        });
+
+        CubeFacesPipeline {
+            faces: self.faces,
+            size: self.size,
+            sampler,
+            bind_group: None,
+        }
+    }
+}
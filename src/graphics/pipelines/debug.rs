@@ -0,0 +1,295 @@
+use bytemuck::{Pod, Zeroable};
+use specs::WorldExt;
+use std::mem;
+
+use super::{alpha_blend_color_state, GlobalUniforms};
+use crate::{
+    graphics::{
+        pipeline::VertexStateBuilder, renderer::DEPTH_FORMAT, resources::RenderTarget, Pipeline,
+        SimplePipeline, SimplePipelineDesc,
+    },
+    scene::{
+        components::CameraData,
+        resources::{CurrentCamera, DebugDraw},
+    },
+    AssetManager,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+unsafe impl Zeroable for DebugVertex {}
+unsafe impl Pod for DebugVertex {}
+
+#[derive(Debug)]
+pub struct DebugPipeline {
+    depth_test: bool,
+    constants_buffer: wgpu::Buffer,
+    global_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    vertices: Vec<DebugVertex>,
+}
+
+impl DebugPipeline {
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            size: (capacity * mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            label: None,
+        })
+    }
+}
+
+impl SimplePipeline for DebugPipeline {
+    fn prepare(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        device: &mut wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        _pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) {
+        self.vertices.clear();
+
+        let current_camera = world.try_fetch::<CurrentCamera>();
+        let debug_draw = world.try_fetch::<DebugDraw>();
+        if current_camera.is_none() || debug_draw.is_none() {
+            return;
+        }
+        let camera_data = world.read_component::<CameraData>();
+        let camera_data = camera_data.get(current_camera.unwrap().entity);
+        if camera_data.is_none() {
+            return;
+        }
+
+        let depth_test = self.depth_test;
+        for line in debug_draw
+            .unwrap()
+            .lines
+            .iter()
+            .filter(|line| line.depth_test == depth_test)
+        {
+            let color = [line.color.x, line.color.y, line.color.z, line.color.w];
+            self.vertices.push(DebugVertex {
+                position: [line.start.x, line.start.y, line.start.z],
+                color,
+            });
+            self.vertices.push(DebugVertex {
+                position: [line.end.x, line.end.y, line.end.z],
+                color,
+            });
+        }
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let uniforms = GlobalUniforms {
+            view_projection: camera_data.unwrap().get_matrix(),
+            ..GlobalUniforms::default()
+        };
+        let constants_buffer = device
+            .create_buffer_with_data(bytemuck::bytes_of(&uniforms), wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(
+            &constants_buffer,
+            0,
+            &self.constants_buffer,
+            0,
+            mem::size_of::<GlobalUniforms>() as u64,
+        );
+
+        if self.vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = self.vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        let temp_buf = device.create_buffer_with_data(
+            bytemuck::cast_slice(&self.vertices),
+            wgpu::BufferUsage::COPY_SRC,
+        );
+        encoder.copy_buffer_to_buffer(
+            &temp_buf,
+            0,
+            &self.vertex_buffer,
+            0,
+            (self.vertices.len() * mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+        );
+    }
+
+    fn render(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        depth: Option<&wgpu::TextureView>,
+        _device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: Option<&wgpu::TextureView>,
+        _input: Option<&RenderTarget>,
+        _output: Option<&RenderTarget>,
+        pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) -> Option<RenderTarget> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+        let current_camera = world.fetch::<CurrentCamera>();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: frame.unwrap(),
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Load,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::BLACK,
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: depth.unwrap(),
+                depth_load_op: wgpu::LoadOp::Load,
+                depth_store_op: wgpu::StoreOp::Store,
+                stencil_load_op: wgpu::LoadOp::Load,
+                stencil_store_op: wgpu::StoreOp::Store,
+                clear_depth: 1.0,
+                clear_stencil: 0,
+            }),
+        });
+        current_camera.set_viewport(&mut render_pass);
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, &self.global_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, &self.vertex_buffer, 0, 0);
+        render_pass.draw(0..self.vertices.len() as u32, 0..1);
+
+        None
+    }
+}
+
+/// Draws the lines pushed to `DebugDraw`. One node draws the depth tested lines after the
+/// scene and another draws the rest on top of everything.
+#[derive(Debug, Default)]
+pub struct DebugPipelineDesc {
+    depth_test: bool,
+}
+
+impl DebugPipelineDesc {
+    pub fn depth_tested() -> Self {
+        Self { depth_test: true }
+    }
+
+    pub fn on_top() -> Self {
+        Self { depth_test: false }
+    }
+}
+
+impl SimplePipelineDesc for DebugPipelineDesc {
+    type Pipeline = DebugPipeline;
+
+    fn load_shader<'a>(
+        &self,
+        asset_manager: &'a crate::AssetManager,
+    ) -> &'a crate::graphics::material::Shader {
+        asset_manager.get_shader("debug.shader")
+    }
+
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<GlobalUniforms>() as u64)]
+    }
+
+    fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
+        wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }
+    }
+
+    fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
+        wgpu::PrimitiveTopology::LineList
+    }
+
+    fn color_states_desc(
+        &self,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> Vec<wgpu::ColorStateDescriptor> {
+        vec![alpha_blend_color_state(sc_desc.format)]
+    }
+
+    fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        // Lines never write depth so they can't hide each other or the scene.
+        Some(wgpu::DepthStencilStateDescriptor {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: if self.depth_test {
+                wgpu::CompareFunction::Greater
+            } else {
+                wgpu::CompareFunction::Always
+            },
+            stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
+            stencil_read_mask: 0,
+            stencil_write_mask: 0,
+        })
+    }
+
+    fn vertex_state_desc(&self) -> VertexStateBuilder {
+        let mut vertex_state_builder = VertexStateBuilder::new();
+
+        vertex_state_builder
+            .set_index_format(wgpu::IndexFormat::Uint16)
+            .new_buffer_descriptor(
+                mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+                wgpu::InputStepMode::Vertex,
+                vec![
+                    wgpu::VertexAttributeDescriptor {
+                        format: wgpu::VertexFormat::Float3,
+                        offset: 0,
+                        shader_location: 0,
+                    },
+                    wgpu::VertexAttributeDescriptor {
+                        format: wgpu::VertexFormat::Float4,
+                        offset: 4 * 3,
+                        shader_location: 1,
+                    },
+                ],
+            );
+
+        vertex_state_builder
+    }
+
+    fn build(
+        self,
+        device: &wgpu::Device,
+        bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+    ) -> DebugPipeline {
+        let constants_buffer = device.create_buffer_with_data(
+            bytemuck::bytes_of(&GlobalUniforms::default()),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layouts[0],
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &constants_buffer,
+                    range: 0..mem::size_of::<GlobalUniforms>() as u64,
+                },
+            }],
+            label: None,
+        });
+
+        let vertex_capacity = 1024;
+        let vertex_buffer = DebugPipeline::create_vertex_buffer(device, vertex_capacity);
+
+        DebugPipeline {
+            depth_test: self.depth_test,
+            constants_buffer,
+            global_bind_group,
+            vertex_buffer,
+            vertex_capacity,
+            vertices: Vec::new(),
+        }
+    }
+}
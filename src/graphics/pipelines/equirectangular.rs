@@ -6,6 +6,53 @@ use crate::{
     AssetManager,
 };
 
+/// Copies the six faces a cube projection pass rendered on top of each other into the layers of
+/// a cube map.
+pub(crate) fn strip_to_cube_map(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    strip: &RenderTarget,
+    size: f32,
+) -> RenderTarget {
+    let cube_map = RenderTarget::new(
+        device,
+        size,
+        size,
+        6,
+        1,
+        wgpu::TextureFormat::Rgba32Float,
+        wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_SRC | wgpu::TextureUsage::COPY_DST,
+    );
+
+    for i in 0..6 {
+        encoder.copy_texture_to_texture(
+            wgpu::TextureCopyView {
+                texture: &strip.texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: size as u32 * i,
+                    z: 0,
+                },
+            },
+            wgpu::TextureCopyView {
+                texture: &cube_map.texture,
+                mip_level: 0,
+                array_layer: i,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::Extent3d {
+                width: size as u32,
+                height: size as u32,
+                depth: 1,
+            },
+        );
+    }
+
+    cube_map
+}
+
 #[derive(Debug)]
 pub struct CubeProjectionPipeline {
     texture: String,
@@ -46,7 +93,7 @@ impl SimplePipeline for CubeProjectionPipeline {
         _depth: Option<&wgpu::TextureView>,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        _frame: Option<&wgpu::SwapChainOutput>,
+        _frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -73,43 +120,12 @@ impl SimplePipeline for CubeProjectionPipeline {
             render_pass.draw(0..6, 0..6);
         }
 
-        let cube_map = RenderTarget::new(
+        Some(strip_to_cube_map(
             device,
+            encoder,
+            output.as_ref().unwrap(),
             self.size,
-            self.size,
-            6,
-            1,
-            wgpu::TextureFormat::Rgba32Float,
-            wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
-        );
-
-        for i in 0..6 {
-            encoder.copy_texture_to_texture(
-                wgpu::TextureCopyView {
-                    texture: &output.as_ref().unwrap().texture,
-                    mip_level: 0,
-                    array_layer: 0,
-                    origin: wgpu::Origin3d {
-                        x: 0,
-                        y: self.size as u32 * i,
-                        z: 0,
-                    },
-                },
-                wgpu::TextureCopyView {
-                    texture: &cube_map.texture,
-                    mip_level: 0,
-                    array_layer: i,
-                    origin: wgpu::Origin3d::ZERO,
-                },
-                wgpu::Extent3d {
-                    width: self.size as u32,
-                    height: self.size as u32,
-                    depth: 1,
-                },
-            );
-        }
-
-        Some(cube_map)
+        ))
     }
 }
 
@@ -135,31 +151,6 @@ impl SimplePipelineDesc for CubeProjectionPipelineDesc {
         asset_manager.get_shader("hdr_to_cubemap.shader")
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // We can create whatever layout we want here.
-        let global_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-
-        vec![global_bind_group_layout]
-    }
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
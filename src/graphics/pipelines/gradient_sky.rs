@@ -0,0 +1,186 @@
+use super::equirectangular::strip_to_cube_map;
+use crate::{
+    graphics::{
+        material::SkyGradient, pipeline::VertexStateBuilder, resources::RenderTarget, Pipeline,
+        SimplePipeline, SimplePipelineDesc,
+    },
+    AssetManager,
+};
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm::Vec4;
+use std::mem;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Uniforms {
+    // w: how sharply the horizon blends into the top and bottom colors.
+    pub top: Vec4,
+    pub horizon: Vec4,
+    pub bottom: Vec4,
+}
+
+unsafe impl Zeroable for Uniforms {}
+unsafe impl Pod for Uniforms {}
+
+/// Builds a cube map from a vertical top, horizon and bottom color gradient.
+#[derive(Debug)]
+pub struct GradientSkyPipeline {
+    size: f32,
+    constants_buffer: wgpu::Buffer,
+}
+
+impl SimplePipeline for GradientSkyPipeline {
+    fn prepare(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        _device: &mut wgpu::Device,
+        _encoder: &mut wgpu::CommandEncoder,
+        _pipeline: &Pipeline,
+        _world: &mut specs::World,
+    ) {
+    }
+
+    fn render(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        _depth: Option<&wgpu::TextureView>,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        _frame: Option<&wgpu::TextureView>,
+        _input: Option<&RenderTarget>,
+        output: Option<&RenderTarget>,
+        pipeline: &Pipeline,
+        _world: &mut specs::World,
+    ) -> Option<RenderTarget> {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.bind_group_layouts[0],
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &self.constants_buffer,
+                    range: 0..std::mem::size_of::<Uniforms>() as u64,
+                },
+            }],
+            label: None,
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &output.as_ref().unwrap().texture_view,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(&pipeline.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..6, 0..6);
+        }
+
+        Some(strip_to_cube_map(
+            device,
+            encoder,
+            output.as_ref().unwrap(),
+            self.size,
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub struct GradientSkyPipelineDesc {
+    gradient: SkyGradient,
+    size: f32,
+}
+
+impl GradientSkyPipelineDesc {
+    pub fn new(gradient: SkyGradient, size: f32) -> Self {
+        Self { gradient, size }
+    }
+}
+
+impl SimplePipelineDesc for GradientSkyPipelineDesc {
+    type Pipeline = GradientSkyPipeline;
+
+    fn load_shader<'a>(
+        &self,
+        asset_manager: &'a crate::AssetManager,
+    ) -> &'a crate::graphics::material::Shader {
+        asset_manager.get_shader("gradient_to_cubemap.shader")
+    }
+
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<Uniforms>() as u64)]
+    }
+
+    fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
+        wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }
+    }
+    fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
+        wgpu::PrimitiveTopology::TriangleList
+    }
+    fn color_states_desc(
+        &self,
+        _sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> Vec<wgpu::ColorStateDescriptor> {
+        vec![wgpu::ColorStateDescriptor {
+            format: wgpu::TextureFormat::Rgba32Float,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }]
+    }
+
+    fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        None
+    }
+
+    fn vertex_state_desc(&self) -> VertexStateBuilder {
+        let vertex_state_builder = VertexStateBuilder::new();
+        vertex_state_builder
+    }
+
+    fn build(
+        self,
+        device: &wgpu::Device,
+        _bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+    ) -> GradientSkyPipeline {
+        let gradient = self.gradient;
+        let constants_buffer = device.create_buffer_with_data(
+            bytemuck::bytes_of(&Uniforms {
+                top: Vec4::new(
+                    gradient.top.x,
+                    gradient.top.y,
+                    gradient.top.z,
+                    gradient.exponent,
+                ),
+                horizon: Vec4::new(
+                    gradient.horizon.x,
+                    gradient.horizon.y,
+                    gradient.horizon.z,
+                    0.0,
+                ),
+                bottom: Vec4::new(gradient.bottom.x, gradient.bottom.y, gradient.bottom.z, 0.0),
+            }),
+            wgpu::BufferUsage::UNIFORM,
+        );
+
+        GradientSkyPipeline {
+            size: self.size,
+            constants_buffer,
+        }
+    }
+}
@@ -2,15 +2,27 @@ use bytemuck::{Pod, Zeroable};
 use nalgebra_glm::{Mat4, Vec4};
 
 mod unlit;
-pub(crate) use unlit::UnlitPipelineDesc;
+pub use unlit::UnlitPipelineDesc;
 
 mod pbr;
 pub(crate) use pbr::PBRPipelineDesc;
 
+mod clear;
+pub(crate) use clear::ClearPipelineDesc;
+
+mod debug;
+pub(crate) use debug::DebugPipelineDesc;
+
 mod skybox;
 pub(crate) use skybox::SkyboxPipelineDesc;
 
+mod sprite;
+pub(crate) use sprite::{SpriteBatch, SpriteInstance, SpritePipelineDesc};
+
+pub mod clusters;
+pub(crate) mod cube_faces;
 pub(crate) mod equirectangular;
+pub(crate) mod gradient_sky;
 pub(crate) mod irradiance;
 pub(crate) mod specular;
 pub(crate) mod specular_brdf;
@@ -19,12 +31,18 @@ pub(crate) mod specular_brdf;
 #[derive(Debug, Clone, Copy)]
 pub struct GlobalUniforms {
     pub view_projection: Mat4,
+    // x: debug view mode, y: z near, z: z far, w: 1 for orthographic cameras.
+    pub debug_view: Vec4,
+    // xyz: world space position of the camera.
+    pub camera_position: Vec4,
 }
 
 impl Default for GlobalUniforms {
     fn default() -> Self {
         Self {
             view_projection: Mat4::identity(),
+            debug_view: Vec4::zeros(),
+            camera_position: Vec4::zeros(),
         }
     }
 }
@@ -32,7 +50,9 @@ impl Default for GlobalUniforms {
 unsafe impl Zeroable for GlobalUniforms {}
 unsafe impl Pod for GlobalUniforms {}
 
-pub const MAX_LIGHTS: usize = 10;
+/// Directional lights light every pixel so they live in a uniform with a fixed budget.
+/// Point and spot lights are stored in storage buffers and assigned to clusters instead.
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 5;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -53,50 +73,98 @@ impl Default for DirectionalLight {
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct PointLight {
+    // w: range
     pub position: Vec4,
+    // w: intensity
     pub color: Vec4,
-    pub attenuation: Vec4,
 }
 
 impl Default for PointLight {
     fn default() -> Self {
         Self {
-            attenuation: Vec4::zeros(),
             position: Vec4::zeros(),
             color: Vec4::zeros(),
         }
     }
 }
 
+unsafe impl Zeroable for PointLight {}
+unsafe impl Pod for PointLight {}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SpotLight {
+    // w: range
+    pub position: Vec4,
+    pub direction: Vec4,
+    // w: intensity
+    pub color: Vec4,
+    // x: cos(inner cone angle), y: cos(outer cone angle)
+    pub cone: Vec4,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            position: Vec4::zeros(),
+            direction: Vec4::zeros(),
+            color: Vec4::zeros(),
+            cone: Vec4::zeros(),
+        }
+    }
+}
+
+unsafe impl Zeroable for SpotLight {}
+unsafe impl Pod for SpotLight {}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct LightingUniform {
+    // x: directional, y: point, z: spot light counts.
     pub light_num: Vec4,
-    pub directional_lights: [DirectionalLight; MAX_LIGHTS / 2],
-    pub point_lights: [PointLight; MAX_LIGHTS / 2],
+    // xyz: number of clusters on each axis.
+    pub cluster_count: Vec4,
+    // x: z near, y: z far of the camera the clusters were built for, z: 1 if it's orthographic.
+    pub cluster_depth: Vec4,
+    pub directional_lights: [DirectionalLight; MAX_DIRECTIONAL_LIGHTS],
 }
 
 impl Default for LightingUniform {
     fn default() -> Self {
         Self {
             light_num: Vec4::zeros(),
-            directional_lights: [
-                DirectionalLight::default(),
-                DirectionalLight::default(),
-                DirectionalLight::default(),
-                DirectionalLight::default(),
-                DirectionalLight::default(),
-            ],
-            point_lights: [
-                PointLight::default(),
-                PointLight::default(),
-                PointLight::default(),
-                PointLight::default(),
-                PointLight::default(),
-            ],
+            cluster_count: Vec4::new(
+                clusters::CLUSTER_COUNT_X as f32,
+                clusters::CLUSTER_COUNT_Y as f32,
+                clusters::CLUSTER_COUNT_Z as f32,
+                0.0,
+            ),
+            cluster_depth: Vec4::new(0.1, 100.0, 0.0, 0.0),
+            directional_lights: [DirectionalLight::default(); MAX_DIRECTIONAL_LIGHTS],
         }
     }
 }
 
 unsafe impl Zeroable for LightingUniform {}
 unsafe impl Pod for LightingUniform {}
+
+/// Color state of the pipelines that blend over what's already been drawn: the transparent
+/// pipelines, sprites and debug lines. None of them write depth, so they run after the opaque
+/// pipelines. Masked materials aren't blended, the opaque pipelines draw them and discard the
+/// pixels below the cutoff through the `ALPHA_TEST` shader feature.
+pub(crate) fn alpha_blend_color_state(format: wgpu::TextureFormat) -> wgpu::ColorStateDescriptor {
+    wgpu::ColorStateDescriptor {
+        format,
+        color_blend: wgpu::BlendDescriptor {
+            src_factor: wgpu::BlendFactor::SrcAlpha,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha_blend: wgpu::BlendDescriptor {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        },
+        write_mask: wgpu::ColorWrite::ALL,
+    }
+}
@@ -1,13 +1,14 @@
 use specs::RunNow;
 use std::mem;
 
-use super::{GlobalUniforms, LightingUniform};
+use super::{alpha_blend_color_state, GlobalUniforms, LightingUniform};
 use crate::{
     graphics::{
+        material::{pbr_pipeline_name, PBRMaterialUniform},
         mesh::MeshVertexData,
         pipeline::VertexStateBuilder,
         renderer::DEPTH_FORMAT,
-        resources::RenderTarget,
+        resources::{LightBuffers, RenderTarget},
         // renderer::DEPTH_FORMAT,
         Pipeline,
         SimplePipeline,
@@ -19,28 +20,119 @@ use crate::{
 
 #[derive(Debug)]
 pub struct PBRPipeline {
+    /// Name of the draw items in the render queue this pipeline draws.
+    pipeline_name: &'static str,
     constants_buffer: wgpu::Buffer,
     lighting_buffer: wgpu::Buffer,
-    global_bind_group: wgpu::BindGroup,
+    warned_directional_lights: bool,
+    // Made in `prepare` once the shared `LightBuffers` exist, along with their generation.
+    global_bind_group: Option<(wgpu::BindGroup, u32)>,
+}
+
+impl PBRPipeline {
+    fn create_global_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        constants_buffer: &wgpu::Buffer,
+        lighting_buffer: &wgpu::Buffer,
+        light_buffers: &LightBuffers,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: constants_buffer,
+                        range: 0..std::mem::size_of::<GlobalUniforms>() as u64,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: lighting_buffer,
+                        range: 0..std::mem::size_of::<LightingUniform>() as u64,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &light_buffers.point_lights.buffer,
+                        range: 0..light_buffers.point_lights.size(),
+                    },
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &light_buffers.spot_lights.buffer,
+                        range: 0..light_buffers.spot_lights.size(),
+                    },
+                },
+                wgpu::Binding {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &light_buffers.clusters.buffer,
+                        range: 0..light_buffers.clusters.size(),
+                    },
+                },
+                wgpu::Binding {
+                    binding: 5,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &light_buffers.indices.buffer,
+                        range: 0..light_buffers.indices.size(),
+                    },
+                },
+            ],
+            label: None,
+        })
+    }
 }
 
 impl SimplePipeline for PBRPipeline {
     fn prepare(
         &mut self,
-        _asset_manager: &mut AssetManager,
+        asset_manager: &mut AssetManager,
         device: &mut wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        _pipeline: &Pipeline,
+        pipeline: &Pipeline,
         world: &mut specs::World,
     ) {
+        asset_manager.update_materials(
+            self.pipeline_name,
+            device,
+            encoder,
+            pipeline.bind_group_layout(2),
+        );
+
         let mut prepare_pbr = PreparePBR {
             device,
             encoder,
             constants_buffer: &self.constants_buffer,
             lighting_buffer: &self.lighting_buffer,
+            warned_directional_lights: &mut self.warned_directional_lights,
         };
         RunNow::setup(&mut prepare_pbr, world);
         prepare_pbr.run_now(world);
+
+        // Light buffers that grew are new buffers, so the bind group has to point at them.
+        let light_buffers = match world.try_fetch::<LightBuffers>() {
+            Some(light_buffers) => light_buffers,
+            None => return,
+        };
+        let stale = match &self.global_bind_group {
+            Some((_, generation)) => *generation != light_buffers.generation,
+            None => true,
+        };
+        if stale {
+            let global_bind_group = Self::create_global_bind_group(
+                device,
+                &pipeline.bind_group_layouts[0],
+                &self.constants_buffer,
+                &self.lighting_buffer,
+                &light_buffers,
+            );
+            self.global_bind_group = Some((global_bind_group, light_buffers.generation));
+        }
     }
 
     fn render(
@@ -49,21 +141,26 @@ impl SimplePipeline for PBRPipeline {
         depth: Option<&wgpu::TextureView>,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         _output: Option<&RenderTarget>,
         pipeline: &Pipeline,
         world: &mut specs::World,
     ) -> Option<RenderTarget> {
+        let global_bind_group = match &self.global_bind_group {
+            Some((global_bind_group, _)) => global_bind_group,
+            None => return None,
+        };
         let mut render_pbr = RenderPBR {
             device,
+            pipeline_name: self.pipeline_name,
             asset_manager: asset_manager,
             encoder,
-            frame_view: &frame.as_ref().unwrap().view,
+            frame_view: frame.unwrap(),
             pipeline,
             constants_buffer: &self.constants_buffer,
             lighting_buffer: &self.lighting_buffer,
-            global_bind_group: &self.global_bind_group,
+            global_bind_group,
             depth: depth.as_ref().unwrap(),
         };
         RunNow::setup(&mut render_pbr, world);
@@ -74,7 +171,26 @@ impl SimplePipeline for PBRPipeline {
 }
 
 #[derive(Debug, Default)]
-pub struct PBRPipelineDesc;
+pub struct PBRPipelineDesc {
+    transparent: bool,
+    double_sided: bool,
+}
+
+impl PBRPipelineDesc {
+    /// Like `UnlitPipelineDesc::transparent`, for PBR materials.
+    pub fn transparent() -> Self {
+        Self {
+            transparent: true,
+            double_sided: false,
+        }
+    }
+
+    /// Draws back faces too, for materials with `double_sided` set.
+    pub fn double_sided(mut self) -> Self {
+        self.double_sided = true;
+        self
+    }
+}
 
 impl SimplePipelineDesc for PBRPipelineDesc {
     type Pipeline = PBRPipeline;
@@ -86,112 +202,30 @@ impl SimplePipelineDesc for PBRPipelineDesc {
         asset_manager.get_shader("pbr.shader")
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // We can create whatever layout we want here.
-        let global_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        // CAMERA TRANSFORM
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::VERTEX,
-                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        // LIGHTING DATA
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                    },
-                ],
-                label: None,
-            });
-
-        let material_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::VERTEX,
-                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-
-        let pbr_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::Cube,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::Cube,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 3,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 4,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 5,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
         vec![
-            global_bind_group_layout,
-            material_bind_group_layout,
-            pbr_bind_group_layout,
+            (1, 0, mem::size_of::<GlobalUniforms>() as u64),
+            (1, 1, mem::size_of::<LightingUniform>() as u64),
+            (2, 0, mem::size_of::<PBRMaterialUniform>() as u64),
         ]
     }
+
+    fn shader_features(&self) -> Vec<String> {
+        if self.transparent {
+            Vec::new()
+        } else {
+            vec!["ALPHA_TEST".to_string()]
+        }
+    }
+
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
-            cull_mode: wgpu::CullMode::Back,
+            cull_mode: if self.double_sided {
+                wgpu::CullMode::None
+            } else {
+                wgpu::CullMode::Back
+            },
             depth_bias: 0,
             depth_bias_slope_scale: 0.0,
             depth_bias_clamp: 0.0,
@@ -204,6 +238,9 @@ impl SimplePipelineDesc for PBRPipelineDesc {
         &self,
         sc_desc: &wgpu::SwapChainDescriptor,
     ) -> Vec<wgpu::ColorStateDescriptor> {
+        if self.transparent {
+            return vec![alpha_blend_color_state(sc_desc.format)];
+        }
         vec![wgpu::ColorStateDescriptor {
             format: sc_desc.format,
             color_blend: wgpu::BlendDescriptor::REPLACE,
@@ -215,7 +252,7 @@ impl SimplePipelineDesc for PBRPipelineDesc {
     fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
         Some(wgpu::DepthStencilStateDescriptor {
             format: DEPTH_FORMAT,
-            depth_write_enabled: true,
+            depth_write_enabled: !self.transparent,
             depth_compare: wgpu::CompareFunction::Greater,
             stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
             stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
@@ -264,7 +301,7 @@ impl SimplePipelineDesc for PBRPipelineDesc {
     fn build(
         self,
         device: &wgpu::Device,
-        bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+        _bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
     ) -> PBRPipeline {
         // This data needs to be saved and passed onto the pipeline.
         let constants_buffer = device.create_buffer_with_data(
@@ -277,31 +314,12 @@ impl SimplePipelineDesc for PBRPipelineDesc {
             wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
         );
 
-        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layouts[0],
-            bindings: &[
-                wgpu::Binding {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &constants_buffer,
-                        range: 0..std::mem::size_of::<GlobalUniforms>() as u64,
-                    },
-                },
-                wgpu::Binding {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &lighting_buffer,
-                        range: 0..std::mem::size_of::<LightingUniform>() as u64,
-                    },
-                },
-            ],
-            label: None,
-        });
-
         PBRPipeline {
+            pipeline_name: pbr_pipeline_name(self.transparent, self.double_sided),
             constants_buffer,
             lighting_buffer,
-            global_bind_group,
+            warned_directional_lights: false,
+            global_bind_group: None,
         }
     }
 }
@@ -1,13 +1,14 @@
 use bytemuck::{Pod, Zeroable};
 use nalgebra_glm::Mat4;
 use specs::WorldExt;
+use std::mem;
 
 use crate::{
     graphics::{
         pipeline::VertexStateBuilder, renderer::DEPTH_FORMAT, resources::RenderTarget, Pipeline,
         SimplePipeline, SimplePipelineDesc,
     },
-    scene::components::CameraData,
+    scene::{components::CameraData, resources::CurrentCamera},
     AssetManager,
 };
 
@@ -49,14 +50,12 @@ impl SimplePipeline for SkyboxPipeline {
         if skybox.is_none() {
             return;
         }
+        let current_camera = world.try_fetch::<CurrentCamera>();
+        if current_camera.is_none() {
+            return;
+        }
         let camera_data = world.read_component::<CameraData>();
-
-        use specs::Join;
-        let filtered_camera_data: Vec<&CameraData> = camera_data
-            .join()
-            .filter(|data: &&CameraData| data.active)
-            .collect();
-        let camera_data = filtered_camera_data.first();
+        let camera_data = camera_data.get(current_camera.unwrap().entity);
 
         if camera_data.is_none() {
             return;
@@ -87,7 +86,7 @@ impl SimplePipeline for SkyboxPipeline {
         depth: Option<&wgpu::TextureView>,
         _device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         _output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -98,13 +97,19 @@ impl SimplePipeline for SkyboxPipeline {
             if skybox.is_none() {
                 return None;
             }
+            let current_camera = world.try_fetch::<CurrentCamera>();
+            if current_camera.is_none() {
+                return None;
+            }
             let skybox = skybox.unwrap();
+            let current_camera = current_camera.unwrap();
 
+            // The clear node has already cleared this camera's viewport.
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &frame.as_ref().unwrap().view,
+                    attachment: frame.unwrap(),
                     resolve_target: None,
-                    load_op: wgpu::LoadOp::Clear,
+                    load_op: wgpu::LoadOp::Load,
                     store_op: wgpu::StoreOp::Store,
                     clear_color: wgpu::Color {
                         r: 0.0,
@@ -116,14 +121,15 @@ impl SimplePipeline for SkyboxPipeline {
                 //depth_stencil_attachment: None,
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
                     attachment: depth.as_ref().unwrap(),
-                    depth_load_op: wgpu::LoadOp::Clear,
+                    depth_load_op: wgpu::LoadOp::Load,
                     depth_store_op: wgpu::StoreOp::Store,
-                    stencil_load_op: wgpu::LoadOp::Clear,
+                    stencil_load_op: wgpu::LoadOp::Load,
                     stencil_store_op: wgpu::StoreOp::Store,
                     clear_depth: 1.0,
                     clear_stencil: 0,
                 }),
             });
+            current_camera.set_viewport(&mut render_pass);
             render_pass.set_pipeline(&pipeline.pipeline);
             render_pass.set_bind_group(0, &self.global_bind_group, &[]);
 
@@ -148,41 +154,10 @@ impl SimplePipelineDesc for SkyboxPipelineDesc {
         asset_manager.get_shader("skybox.shader")
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // We can create whatever layout we want here.
-        let global_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX,
-                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                }],
-                label: None,
-            });
-
-        let material_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            component_type: wgpu::TextureComponentType::Float,
-                            multisampled: false,
-                            dimension: wgpu::TextureViewDimension::Cube,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-
-        vec![global_bind_group_layout, material_bind_group_layout]
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<SkyboxUniforms>() as u64)]
     }
+
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
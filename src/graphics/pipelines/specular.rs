@@ -6,12 +6,15 @@ use crate::{
     AssetManager,
 };
 use bytemuck::{Pod, Zeroable};
+use std::mem;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct Uniforms {
     pub roughness: f32,
     pub resoultion: f32,
+    // Uniform blocks are padded to 16 bytes.
+    pub _padding: [f32; 2],
 }
 
 impl Default for Uniforms {
@@ -19,6 +22,7 @@ impl Default for Uniforms {
         Self {
             roughness: 1.0,
             resoultion: 1024.0,
+            _padding: [0.0; 2],
         }
     }
 }
@@ -49,7 +53,7 @@ impl SimplePipeline for SpecularPipeline {
         _depth: Option<&wgpu::TextureView>,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        _frame: Option<&wgpu::SwapChainOutput>,
+        _frame: Option<&wgpu::TextureView>,
         input: Option<&RenderTarget>,
         output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -127,36 +131,10 @@ impl SimplePipelineDesc for SpecularPipelineDesc {
         asset_manager.get_shader("specular.shader")
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // We can create whatever layout we want here.
-        let global_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-
-        vec![global_bind_group_layout]
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<Uniforms>() as u64)]
     }
+
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
@@ -200,6 +178,7 @@ impl SimplePipelineDesc for SpecularPipelineDesc {
             bytemuck::bytes_of(&Uniforms {
                 roughness: self.mip_level as f32 / (SPEC_CUBEMAP_MIP_LEVELS - 1) as f32,
                 resoultion: self.resoultion,
+                ..Default::default()
             }),
             wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
         );
@@ -28,7 +28,7 @@ impl SimplePipeline for SpecularBRDFPipeline {
         _depth: Option<&wgpu::TextureView>,
         _device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        _frame: Option<&wgpu::SwapChainOutput>,
+        _frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -77,10 +77,6 @@ impl SimplePipelineDesc for SpecularBRDFPipelineDesc {
         asset_manager.get_shader("specular_brdf.shader")
     }
 
-    fn create_layout(&self, _device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // No bindings? No problem! Just remember that later on!
-        vec![]
-    }
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
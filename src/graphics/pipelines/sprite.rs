@@ -0,0 +1,296 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm::{Mat4, Vec4};
+use specs::RunNow;
+use std::{collections::HashMap, mem, ops::Range};
+
+use super::{alpha_blend_color_state, GlobalUniforms};
+use crate::{
+    graphics::{
+        pipeline::VertexStateBuilder, resources::RenderTarget, Pipeline, SimplePipeline,
+        SimplePipelineDesc,
+    },
+    scene::{resources::CurrentCamera, systems::PrepareSprites},
+    AssetManager,
+};
+
+const QUAD_VERTICES: [[f32; 2]; 4] = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]];
+const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SpriteInstance {
+    /// Transform of the unit quad, includes the sprite's size and pivot.
+    pub world: Mat4,
+    // xy: uv offset, zw: uv scale
+    pub uv_rect: Vec4,
+    pub tint: Vec4,
+}
+
+unsafe impl Zeroable for SpriteInstance {}
+unsafe impl Pod for SpriteInstance {}
+
+/// A run of instances that all use the same image.
+#[derive(Debug, Clone)]
+pub struct SpriteBatch {
+    pub image: String,
+    pub instances: Range<u32>,
+}
+
+#[derive(Debug)]
+pub struct SpritePipeline {
+    constants_buffer: wgpu::Buffer,
+    global_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    instances: Vec<SpriteInstance>,
+    batches: Vec<SpriteBatch>,
+    image_bind_groups: HashMap<String, wgpu::BindGroup>,
+}
+
+impl SpritePipeline {
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            size: (capacity * mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            label: None,
+        })
+    }
+}
+
+impl SimplePipeline for SpritePipeline {
+    fn prepare(
+        &mut self,
+        asset_manager: &mut AssetManager,
+        device: &mut wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) {
+        let mut prepare_sprites = PrepareSprites {
+            device,
+            encoder,
+            asset_manager,
+            constants_buffer: &self.constants_buffer,
+            instances: &mut self.instances,
+            batches: &mut self.batches,
+        };
+        RunNow::setup(&mut prepare_sprites, world);
+        prepare_sprites.run_now(world);
+
+        if self.instances.is_empty() {
+            return;
+        }
+
+        if self.instances.len() > self.instance_capacity {
+            self.instance_capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+
+        let temp_buf = device.create_buffer_with_data(
+            bytemuck::cast_slice(&self.instances),
+            wgpu::BufferUsage::COPY_SRC,
+        );
+        encoder.copy_buffer_to_buffer(
+            &temp_buf,
+            0,
+            &self.instance_buffer,
+            0,
+            (self.instances.len() * mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+        );
+
+        // Images are bound the first time a sprite uses them.
+        for batch in self.batches.iter() {
+            if self.image_bind_groups.contains_key(&batch.image) {
+                continue;
+            }
+            let image = asset_manager.get_image(batch.image.clone());
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &pipeline.bind_group_layouts[1],
+                bindings: &[
+                    wgpu::Binding {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&image.view),
+                    },
+                    wgpu::Binding {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&image.sampler),
+                    },
+                ],
+                label: None,
+            });
+            self.image_bind_groups
+                .insert(batch.image.clone(), bind_group);
+        }
+    }
+
+    fn render(
+        &mut self,
+        _asset_manager: &mut AssetManager,
+        _depth: Option<&wgpu::TextureView>,
+        _device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: Option<&wgpu::TextureView>,
+        _input: Option<&RenderTarget>,
+        _output: Option<&RenderTarget>,
+        pipeline: &Pipeline,
+        world: &mut specs::World,
+    ) -> Option<RenderTarget> {
+        if self.batches.is_empty() {
+            return None;
+        }
+        let current_camera = world.fetch::<CurrentCamera>();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: frame.unwrap(),
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Load,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.0,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        current_camera.set_viewport(&mut render_pass);
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, &self.global_bind_group, &[]);
+        render_pass.set_index_buffer(&self.index_buffer, 0, 0);
+        render_pass.set_vertex_buffer(0, &self.vertex_buffer, 0, 0);
+        render_pass.set_vertex_buffer(1, &self.instance_buffer, 0, 0);
+
+        for batch in self.batches.iter() {
+            render_pass.set_bind_group(1, &self.image_bind_groups[&batch.image], &[]);
+            render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, batch.instances.clone());
+        }
+
+        None
+    }
+}
+
+/// Draws every entity with a `Sprite` and a `Transform` on top of the 3D scene.
+/// Sprites are alpha blended in z order and don't read or write depth.
+#[derive(Debug, Default)]
+pub struct SpritePipelineDesc;
+
+impl SimplePipelineDesc for SpritePipelineDesc {
+    type Pipeline = SpritePipeline;
+
+    fn load_shader<'a>(
+        &self,
+        asset_manager: &'a crate::AssetManager,
+    ) -> &'a crate::graphics::material::Shader {
+        asset_manager.get_shader("sprite.shader")
+    }
+
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        vec![(0, 0, mem::size_of::<GlobalUniforms>() as u64)]
+    }
+
+    fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
+        // Flipped sprites turn around so nothing can be culled.
+        wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }
+    }
+
+    fn primitive_topology(&self) -> wgpu::PrimitiveTopology {
+        wgpu::PrimitiveTopology::TriangleList
+    }
+
+    fn color_states_desc(
+        &self,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> Vec<wgpu::ColorStateDescriptor> {
+        vec![alpha_blend_color_state(sc_desc.format)]
+    }
+
+    fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        None
+    }
+
+    fn vertex_state_desc(&self) -> VertexStateBuilder {
+        let mut vertex_state_builder = VertexStateBuilder::new();
+
+        vertex_state_builder
+            .set_index_format(wgpu::IndexFormat::Uint16)
+            .new_buffer_descriptor(
+                mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                wgpu::InputStepMode::Vertex,
+                vec![wgpu::VertexAttributeDescriptor {
+                    format: wgpu::VertexFormat::Float2,
+                    offset: 0,
+                    shader_location: 0,
+                }],
+            )
+            .new_buffer_descriptor(
+                mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+                wgpu::InputStepMode::Instance,
+                (0..6)
+                    .map(|i| wgpu::VertexAttributeDescriptor {
+                        format: wgpu::VertexFormat::Float4,
+                        offset: 4 * 4 * i as wgpu::BufferAddress,
+                        shader_location: 1 + i as u32,
+                    })
+                    .collect(),
+            );
+
+        vertex_state_builder
+    }
+
+    fn build(
+        self,
+        device: &wgpu::Device,
+        bind_group_layouts: &Vec<wgpu::BindGroupLayout>,
+    ) -> SpritePipeline {
+        let constants_buffer = device.create_buffer_with_data(
+            bytemuck::bytes_of(&GlobalUniforms::default()),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layouts[0],
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &constants_buffer,
+                    range: 0..std::mem::size_of::<GlobalUniforms>() as u64,
+                },
+            }],
+            label: None,
+        });
+
+        let vertex_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&QUAD_VERTICES),
+            wgpu::BufferUsage::VERTEX,
+        );
+        let index_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&QUAD_INDICES),
+            wgpu::BufferUsage::INDEX,
+        );
+
+        let instance_capacity = 256;
+        let instance_buffer = SpritePipeline::create_instance_buffer(device, instance_capacity);
+
+        SpritePipeline {
+            constants_buffer,
+            global_bind_group,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            instance_capacity,
+            instances: Vec::new(),
+            batches: Vec::new(),
+            image_bind_groups: HashMap::new(),
+        }
+    }
+}
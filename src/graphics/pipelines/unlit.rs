@@ -1,9 +1,10 @@
 use specs::RunNow;
 use std::mem;
 
-use super::GlobalUniforms;
+use super::{alpha_blend_color_state, GlobalUniforms};
 use crate::{
     graphics::{
+        material::UnlitUniform,
         mesh::MeshVertexData,
         pipeline::VertexStateBuilder,
         renderer::DEPTH_FORMAT,
@@ -19,6 +20,8 @@ use crate::{
 
 #[derive(Debug)]
 pub struct UnlitPipeline {
+    /// Name of the draw items in the render queue this pipeline draws.
+    pipeline_name: String,
     constants_buffer: wgpu::Buffer,
     global_bind_group: wgpu::BindGroup,
 }
@@ -26,12 +29,19 @@ pub struct UnlitPipeline {
 impl SimplePipeline for UnlitPipeline {
     fn prepare(
         &mut self,
-        _asset_manager: &mut AssetManager,
+        asset_manager: &mut AssetManager,
         device: &mut wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        _pipeline: &Pipeline,
+        pipeline: &Pipeline,
         world: &mut specs::World,
     ) {
+        asset_manager.update_materials(
+            &self.pipeline_name,
+            device,
+            encoder,
+            pipeline.bind_group_layout(2),
+        );
+
         let mut prepare_unlit = PrepareUnlit {
             device,
             encoder,
@@ -47,7 +57,7 @@ impl SimplePipeline for UnlitPipeline {
         depth: Option<&wgpu::TextureView>,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
         _input: Option<&RenderTarget>,
         _output: Option<&RenderTarget>,
         pipeline: &Pipeline,
@@ -55,9 +65,10 @@ impl SimplePipeline for UnlitPipeline {
     ) -> Option<RenderTarget> {
         let mut render_unlit = RenderUnlit {
             device,
+            pipeline_name: &self.pipeline_name,
             asset_manager: asset_manager,
             encoder,
-            frame_view: &frame.as_ref().unwrap().view,
+            frame_view: frame.unwrap(),
             pipeline,
             constants_buffer: &self.constants_buffer,
             global_bind_group: &self.global_bind_group,
@@ -70,8 +81,69 @@ impl SimplePipeline for UnlitPipeline {
     }
 }
 
-#[derive(Debug, Default)]
-pub struct UnlitPipelineDesc;
+#[derive(Debug)]
+pub struct UnlitPipelineDesc {
+    pipeline_name: String,
+    shader: String,
+    transparent: bool,
+    features: Vec<String>,
+    /// Whether the shader's uniforms are checked against the unlit pipeline's. Custom shaders
+    /// don't have to declare the globals and their material uniform comes from their own
+    /// `MaterialType`.
+    check_uniforms: bool,
+}
+
+impl Default for UnlitPipelineDesc {
+    fn default() -> Self {
+        Self {
+            pipeline_name: "unlit".to_string(),
+            shader: "unlit.shader".to_string(),
+            transparent: false,
+            features: vec!["ALPHA_TEST".to_string()],
+            check_uniforms: true,
+        }
+    }
+}
+
+impl UnlitPipelineDesc {
+    /// A pipeline for alpha blended materials. It doesn't write depth, so it should run after
+    /// the opaque pipelines.
+    pub fn transparent() -> Self {
+        Self {
+            pipeline_name: "unlit_transparent".to_string(),
+            transparent: true,
+            features: Vec::new(),
+            ..Self::default()
+        }
+    }
+
+    /// A pipeline for a custom `MaterialType` that draws meshes like the unlit pipeline with
+    /// another shader. `pipeline_name` has to match both the render graph node's name and the
+    /// material's `pipeline_name`.
+    ///
+    /// The shader gets the same inputs as `unlit.shader`: mesh vertices, the local uniform at
+    /// set 0 and the globals at set 1. Set 2 is the material, laid out like
+    /// `material_bind_group_layout` describes with as many texture slots as the shader declares.
+    pub fn custom<T, T2>(pipeline_name: T, shader: T2, transparent: bool) -> Self
+    where
+        T: Into<String>,
+        T2: Into<String>,
+    {
+        Self {
+            pipeline_name: pipeline_name.into(),
+            shader: shader.into(),
+            transparent,
+            features: Vec::new(),
+            check_uniforms: false,
+        }
+    }
+
+    /// Compiles the shader with these features from its manifest defined.
+    pub fn with_features(mut self, features: &[&str]) -> Self {
+        self.features = features.iter().map(|feature| feature.to_string()).collect();
+        self
+    }
+}
 
 impl SimplePipelineDesc for UnlitPipelineDesc {
     type Pipeline = UnlitPipeline;
@@ -80,48 +152,23 @@ impl SimplePipelineDesc for UnlitPipelineDesc {
         &self,
         asset_manager: &'a crate::AssetManager,
     ) -> &'a crate::graphics::material::Shader {
-        asset_manager.get_shader("unlit.shader")
+        asset_manager.get_shader(self.shader.clone())
     }
 
-    fn create_layout(&self, device: &mut wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
-        // We can create whatever layout we want here.
-        let global_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX,
-                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                }],
-                label: None,
-            });
-
-        let material_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::VERTEX,
-                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                    },
-                ],
-                label: None,
-            });
-        vec![global_bind_group_layout, material_bind_group_layout]
+    fn shader_features(&self) -> Vec<String> {
+        self.features.clone()
     }
+
+    fn uniform_sizes(&self) -> Vec<(u32, u32, u64)> {
+        if !self.check_uniforms {
+            return Vec::new();
+        }
+        vec![
+            (1, 0, mem::size_of::<GlobalUniforms>() as u64),
+            (2, 0, mem::size_of::<UnlitUniform>() as u64),
+        ]
+    }
+
     fn rasterization_state_desc(&self) -> wgpu::RasterizationStateDescriptor {
         wgpu::RasterizationStateDescriptor {
             front_face: wgpu::FrontFace::Cw,
@@ -138,6 +185,9 @@ impl SimplePipelineDesc for UnlitPipelineDesc {
         &self,
         sc_desc: &wgpu::SwapChainDescriptor,
     ) -> Vec<wgpu::ColorStateDescriptor> {
+        if self.transparent {
+            return vec![alpha_blend_color_state(sc_desc.format)];
+        }
         vec![wgpu::ColorStateDescriptor {
             format: sc_desc.format,
             color_blend: wgpu::BlendDescriptor::REPLACE,
@@ -149,7 +199,7 @@ impl SimplePipelineDesc for UnlitPipelineDesc {
     fn depth_stencil_state_desc(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
         Some(wgpu::DepthStencilStateDescriptor {
             format: DEPTH_FORMAT,
-            depth_write_enabled: true,
+            depth_write_enabled: !self.transparent,
             depth_compare: wgpu::CompareFunction::Greater,
             stencil_front: wgpu::StencilStateFaceDescriptor::IGNORE,
             stencil_back: wgpu::StencilStateFaceDescriptor::IGNORE,
@@ -219,6 +269,7 @@ impl SimplePipelineDesc for UnlitPipelineDesc {
         });
 
         UnlitPipeline {
+            pipeline_name: self.pipeline_name,
             constants_buffer,
             global_bind_group,
         }
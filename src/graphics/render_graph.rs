@@ -1,7 +1,10 @@
-use super::{resources::RenderTarget, Pipeline, Renderer, SimplePipeline, SimplePipelineDesc};
-use crate::AssetManager;
+use super::{
+    renderer::DEPTH_FORMAT, resources::RenderTarget, Pipeline, Renderer, SimplePipeline,
+    SimplePipelineDesc,
+};
+use crate::{scene::resources::CurrentCamera, AssetManager};
 use solvent::DepGraph;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // TODO: handle node dependencies somehow.
 #[derive(Debug)]
@@ -11,32 +14,32 @@ pub struct RenderGraphNode {
     pub use_output_from_dependency: bool,
 }
 
+#[derive(Debug)]
+struct DepthTexture {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
 pub struct RenderGraph {
     nodes: HashMap<String, RenderGraphNode>,
     pub(crate) outputs: HashMap<String, Option<RenderTarget>>,
     dep_graph: DepGraph<String>,
-    pub(crate) local_bind_group_layout: wgpu::BindGroupLayout,
+    // Cameras draw one after another so every camera with the same target size shares a depth texture.
+    depth_textures: HashMap<(u32, u32), DepthTexture>,
+    used_depth_sizes: HashSet<(u32, u32)>,
 }
 
 impl RenderGraph {
-    pub(crate) fn new(device: &wgpu::Device) -> Self {
+    pub(crate) fn new() -> Self {
         let mut dep_graph = DepGraph::new();
         dep_graph.register_node("root".to_string());
-        let local_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                bindings: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX,
-                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                }],
-                label: None,
-            });
 
         RenderGraph {
             nodes: HashMap::new(),
             outputs: HashMap::new(),
             dep_graph,
-            local_bind_group_layout,
+            depth_textures: HashMap::new(),
+            used_depth_sizes: HashSet::new(),
         }
     }
 
@@ -54,15 +57,7 @@ impl RenderGraph {
         use_output_from_dependency: bool,
     ) {
         let name = name.into();
-        let pipeline = pipeline_desc.pipeline(
-            asset_manager,
-            renderer,
-            if include_local_bindings {
-                Some(&self.local_bind_group_layout)
-            } else {
-                None
-            },
-        );
+        let pipeline = pipeline_desc.pipeline(asset_manager, renderer, include_local_bindings);
         let built_pipeline: Box<dyn SimplePipeline> =
             Box::new(pipeline_desc.build(&renderer.device, &pipeline.bind_group_layouts));
         let node = RenderGraphNode {
@@ -101,13 +96,56 @@ impl RenderGraph {
         self.nodes.get(&name.into()).unwrap()
     }
 
+    /// Drops depth textures no camera has drawn with since the last call, e.g. after a resize.
+    pub(crate) fn finish_frame(&mut self) {
+        let used_depth_sizes = &self.used_depth_sizes;
+        self.depth_textures
+            .retain(|size, _| used_depth_sizes.contains(size));
+        self.used_depth_sizes.clear();
+    }
+
+    fn get_depth<'a>(
+        depth_textures: &'a mut HashMap<(u32, u32), DepthTexture>,
+        device: &wgpu::Device,
+        size: (u32, u32),
+    ) -> &'a wgpu::TextureView {
+        let depth_texture = depth_textures.entry(size).or_insert_with(|| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: DEPTH_FORMAT,
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                label: None,
+            });
+            let view = texture.create_default_view();
+            DepthTexture {
+                _texture: texture,
+                view,
+            }
+        });
+        &depth_texture.view
+    }
+
+    /// Runs every node once. When a `CurrentCamera` is in the world the nodes draw for that
+    /// camera and get a depth texture the size of its target, otherwise the size of the window.
     pub(crate) fn render(
         &mut self,
         renderer: &mut Renderer,
         asset_manager: &mut AssetManager,
         world: &mut specs::World,
-        frame: Option<&wgpu::SwapChainOutput>,
+        frame: Option<&wgpu::TextureView>,
     ) -> wgpu::CommandBuffer {
+        let target_size = world
+            .try_fetch::<CurrentCamera>()
+            .map(|current_camera| current_camera.target_size)
+            .unwrap_or((renderer.size.width, renderer.size.height));
+
         let mut encoder = renderer
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -131,6 +169,17 @@ impl RenderGraph {
             }
         }
 
+        let depth = if frame.is_some() {
+            self.used_depth_sizes.insert(target_size);
+            Some(Self::get_depth(
+                &mut self.depth_textures,
+                &renderer.device,
+                target_size,
+            ))
+        } else {
+            None
+        };
+
         for name in order {
             let node = self.nodes.get_mut(&name).unwrap();
             let mut input = None;
@@ -157,7 +206,7 @@ impl RenderGraph {
 
             let output = node.simple_pipeline.render(
                 asset_manager,
-                Some(&renderer.forward_depth),
+                depth,
                 &renderer.device,
                 &mut encoder,
                 frame,
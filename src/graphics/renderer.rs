@@ -1,4 +1,6 @@
 pub(crate) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// Format of the swap chain, render textures use it as well so every scene pipeline can draw to them.
+pub(crate) const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
 
 pub struct Renderer {
     pub(crate) surface: wgpu::Surface,
@@ -9,7 +11,9 @@ pub struct Renderer {
     pub(crate) swap_chain: wgpu::SwapChain,
     pub(crate) window: winit::window::Window,
     pub(crate) sc_desc: wgpu::SwapChainDescriptor,
-    pub(crate) forward_depth: wgpu::TextureView,
+    /// Layout for per object data. Shared by every pipeline that asks for local bindings
+    /// so objects only need one bind group no matter which pipeline draws them.
+    pub(crate) local_bind_group_layout: wgpu::BindGroupLayout,
 }
 
 impl Renderer {
@@ -39,26 +43,22 @@ impl Renderer {
 
         let sc_desc = wgpu::SwapChainDescriptor {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            format: FRAME_FORMAT,
             width: size.width,
             height: size.height,
             present_mode: wgpu::PresentMode::Fifo,
         };
         let swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
-        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: sc_desc.width,
-                height: sc_desc.height,
-                depth: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: DEPTH_FORMAT,
-            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            label: None,
-        });
+        let local_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                bindings: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: true },
+                }],
+                label: None,
+            });
 
         Self {
             surface,
@@ -69,7 +69,7 @@ impl Renderer {
             swap_chain,
             window,
             sc_desc,
-            forward_depth: depth_texture.create_default_view(),
+            local_bind_group_layout,
         }
     }
 
@@ -0,0 +1,111 @@
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// One uniform buffer that holds many `T`'s, each in its own aligned slot.
+/// Slots are bound with a dynamic offset so every object can share a single bind group.
+#[derive(Debug)]
+pub struct DynamicUniformBuffer<T> {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+    stride: wgpu::BufferAddress,
+    capacity: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Pod> DynamicUniformBuffer<T> {
+    /// `layout` must have a single dynamic uniform buffer at binding 0.
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let stride = Self::aligned_size();
+        let (buffer, bind_group) = Self::create(device, layout, stride, DEFAULT_CAPACITY);
+        Self {
+            buffer,
+            bind_group,
+            stride,
+            capacity: DEFAULT_CAPACITY,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The dynamic offset to pass to `set_bind_group` for the given slot.
+    pub fn offset(&self, slot: usize) -> wgpu::DynamicOffset {
+        (slot as wgpu::BufferAddress * self.stride) as wgpu::DynamicOffset
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Writes `items` into slots `0..items.len()` using a single staging buffer and copy.
+    /// The buffer grows if it's too small, which also recreates the bind group.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        encoder: &mut wgpu::CommandEncoder,
+        items: &[T],
+    ) {
+        if items.is_empty() {
+            return;
+        }
+
+        if items.len() > self.capacity {
+            self.capacity = items.len().next_power_of_two();
+            let (buffer, bind_group) = Self::create(device, layout, self.stride, self.capacity);
+            self.buffer = buffer;
+            self.bind_group = bind_group;
+        }
+
+        let size = std::mem::size_of::<T>();
+        let total_size = items.len() as wgpu::BufferAddress * self.stride;
+        let mut temp_buf_data = device.create_buffer_mapped(&wgpu::BufferDescriptor {
+            size: total_size,
+            usage: wgpu::BufferUsage::COPY_SRC,
+            label: None,
+        });
+
+        for (item, slot) in items
+            .iter()
+            .zip(temp_buf_data.data().chunks_exact_mut(self.stride as usize))
+        {
+            slot[..size].copy_from_slice(bytemuck::bytes_of(item));
+        }
+
+        let temp_buf = temp_buf_data.finish();
+        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.buffer, 0, total_size);
+    }
+
+    fn aligned_size() -> wgpu::BufferAddress {
+        let size = std::mem::size_of::<T>() as wgpu::BufferAddress;
+        let alignment = wgpu::BIND_BUFFER_ALIGNMENT;
+        ((size + alignment - 1) / alignment) * alignment
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: wgpu::BufferAddress,
+        capacity: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: stride * capacity as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            label: None,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &buffer,
+                    range: 0..std::mem::size_of::<T>() as wgpu::BufferAddress,
+                },
+            }],
+            label: None,
+        });
+
+        (buffer, bind_group)
+    }
+}
@@ -0,0 +1,52 @@
+use super::StorageBuffer;
+use crate::graphics::pipelines::{
+    clusters::{LightClusters, CLUSTER_COUNT},
+    PointLight, SpotLight,
+};
+
+/// The current camera's `LightClusters` on the GPU. A world resource shared by every PBR
+/// pipeline, `UpdateLightClusters` uploads it once per camera.
+#[derive(Debug)]
+pub struct LightBuffers {
+    pub(crate) point_lights: StorageBuffer<PointLight>,
+    pub(crate) spot_lights: StorageBuffer<SpotLight>,
+    pub(crate) clusters: StorageBuffer<[u32; 4]>,
+    pub(crate) indices: StorageBuffer<u32>,
+    // Bumped whenever a buffer grows, bind groups made before point at the old buffers.
+    pub(crate) generation: u32,
+}
+
+impl LightBuffers {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            point_lights: StorageBuffer::new(device, 64),
+            spot_lights: StorageBuffer::new(device, 64),
+            clusters: StorageBuffer::new(device, CLUSTER_COUNT),
+            indices: StorageBuffer::new(device, 1024),
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        light_clusters: &LightClusters,
+    ) {
+        let mut resized = self
+            .point_lights
+            .upload(device, encoder, &light_clusters.point_lights);
+        resized |= self
+            .spot_lights
+            .upload(device, encoder, &light_clusters.spot_lights);
+        resized |= self
+            .clusters
+            .upload(device, encoder, &light_clusters.clusters);
+        resized |= self
+            .indices
+            .upload(device, encoder, &light_clusters.indices);
+        if resized {
+            self.generation += 1;
+        }
+    }
+}
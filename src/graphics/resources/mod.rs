@@ -1,5 +1,11 @@
+mod dynamic_uniform_buffer;
+mod light_buffers;
 mod render_target;
 mod resource_manager;
+mod storage_buffer;
 mod texture;
 
+pub use dynamic_uniform_buffer::DynamicUniformBuffer;
+pub use light_buffers::LightBuffers;
 pub use render_target::RenderTarget;
+pub use storage_buffer::StorageBuffer;
@@ -0,0 +1,66 @@
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// A read only storage buffer holding a variable amount of `T`'s.
+/// The buffer only ever grows, so bind groups only need to be recreated when `upload` says so.
+#[derive(Debug)]
+pub struct StorageBuffer<T> {
+    pub(crate) buffer: wgpu::Buffer,
+    capacity: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        // Empty buffers can't be bound.
+        let capacity = capacity.max(1);
+        Self {
+            buffer: Self::create(device, capacity),
+            capacity,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Size of the whole buffer in bytes, use this as the binding range.
+    pub fn size(&self) -> wgpu::BufferAddress {
+        (self.capacity * std::mem::size_of::<T>()) as wgpu::BufferAddress
+    }
+
+    /// Copies `items` to the start of the buffer.
+    /// Returns true if the buffer had to grow, which means any bind group using it is stale.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        items: &[T],
+    ) -> bool {
+        let mut resized = false;
+        if items.len() > self.capacity {
+            self.capacity = items.len().next_power_of_two();
+            self.buffer = Self::create(device, self.capacity);
+            resized = true;
+        }
+
+        if !items.is_empty() {
+            let temp_buf = device
+                .create_buffer_with_data(bytemuck::cast_slice(items), wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(
+                &temp_buf,
+                0,
+                &self.buffer,
+                0,
+                (items.len() * std::mem::size_of::<T>()) as wgpu::BufferAddress,
+            );
+        }
+
+        resized
+    }
+
+    fn create(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            size: (capacity * std::mem::size_of::<T>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            label: None,
+        })
+    }
+}
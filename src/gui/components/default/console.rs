@@ -6,11 +6,25 @@ use crate::{
         core::{Color, Rectangle},
         renderables::{Renderable, Text},
     },
+    scene::resources::DebugViewMode,
     AssetManager,
 };
 use glyph_brush::GlyphCruncher;
 use nalgebra_glm::{Vec2, Vec4};
 use std::cell::{RefCell, RefMut};
+use winit::event::VirtualKeyCode;
+
+// While the console is open F1 to F8 pick the debug view mode in this order.
+const VIEW_MODE_KEYS: [VirtualKeyCode; 8] = [
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+];
 
 #[derive(Clone)]
 pub enum ModuleType {
@@ -25,6 +39,7 @@ pub struct Console {
     log: Log,
     lines: Vec<(ModuleType, String)>,
     measure_brush: Option<RefCell<glyph_brush::GlyphBrush<'static, ()>>>,
+    view_mode: Option<DebugViewMode>,
 }
 
 impl Console {
@@ -36,6 +51,7 @@ impl Console {
             log,
             lines: Vec::new(),
             measure_brush: None,
+            view_mode: None,
         }
     }
 
@@ -45,13 +61,17 @@ impl Console {
             glyph_brush::GlyphBrushBuilder::using_font_bytes(font.data.clone()).build(),
         );
         self.measure_brush = Some(measure_brush);
+        self.info(
+            ModuleType::Renderer,
+            "F1 to F8 switch the debug view mode while the console is open.",
+        );
     }
 
     fn get_module_info(module: ModuleType) -> (String, Color) {
         match module {
             ModuleType::Asset => ("[Asset]".to_string(), Color::from_rgb(1.0, 0.529, 0.149)),
             ModuleType::GUI => ("[GUI]".to_string(), Color::from_rgb(0.313, 0.998, 0.705)),
-            _ => ("[None]".to_string(), Color::from_rgb(1.0, 1.0, 1.0)),
+            ModuleType::Renderer => ("[Renderer]".to_string(), Color::from_rgb(0.545, 0.678, 1.0)),
         }
     }
 
@@ -114,8 +134,26 @@ impl Console {
         }
     }
 
+    /// The debug view mode picked since the last call, if any.
+    pub(crate) fn take_view_mode(&mut self) -> Option<DebugViewMode> {
+        self.view_mode.take()
+    }
+
     pub fn update(&mut self, input: &Input, delta_time: f32) {
-        if input.is_key_pressed(winit::event::VirtualKeyCode::Grave) {
+        if self.open {
+            for (key, mode) in VIEW_MODE_KEYS.iter().zip(DebugViewMode::ALL.iter()) {
+                if input.is_key_just_pressed(*key) {
+                    self.view_mode = Some(*mode);
+                    self.info(
+                        ModuleType::Renderer,
+                        format!("Debug view mode: {}", mode.name()),
+                    );
+                    log::info!("Debug view mode: {}", mode.name());
+                }
+            }
+        }
+
+        if input.is_key_just_pressed(VirtualKeyCode::Grave) {
             self.components.clear();
             let mut measure_brush = self.measure_brush.as_mut().unwrap().borrow_mut();
             self.log.clear();
@@ -4,6 +4,7 @@ use std::convert::TryInto;
 use std::mem;
 use zerocopy::AsBytes;
 
+use crate::graphics::material::ShaderStage;
 use crate::gui::core::Rectangle;
 use crate::gui::renderables::Quad;
 use crate::AssetManager;
@@ -102,16 +103,17 @@ impl QuadRenderer {
         });
 
         let shader = asset_mananger.get_shader(String::from("gui_quad.shader"));
+        let shader_variant = shader.variant::<&str>(device, &[]);
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             layout: &layout,
             vertex_stage: wgpu::ProgrammableStageDescriptor {
-                module: &shader.vertex,
-                entry_point: "main",
+                module: &shader_variant.vertex,
+                entry_point: shader.entry_point(ShaderStage::Vertex),
             },
             fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                module: &shader.fragment,
-                entry_point: "main",
+                module: &shader_variant.fragment,
+                entry_point: shader.entry_point(ShaderStage::Fragment),
             }),
             rasterization_state: Some(wgpu::RasterizationStateDescriptor {
                 front_face: wgpu::FrontFace::Cw,
@@ -13,5 +13,5 @@ mod assets;
 mod winit_state;
 
 pub use application::{AppState, Application};
-pub use assets::AssetManager;
+pub use assets::{AssetManager, AtlasBuilder, AtlasRegion, GltfNode, GltfScene, TextureAtlas};
 pub use winit_state::WinitState;
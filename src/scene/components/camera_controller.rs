@@ -0,0 +1,127 @@
+use crate::core::input::MouseButton;
+use nalgebra_glm::{Quat, Vec3};
+use specs::{Component, DenseVecStorage};
+
+/// Direction a yaw and pitch look towards. A yaw and pitch of zero look down +z.
+pub(crate) fn look_direction(yaw: f32, pitch: f32) -> Vec3 {
+    Vec3::new(
+        pitch.cos() * yaw.sin(),
+        pitch.sin(),
+        pitch.cos() * yaw.cos(),
+    )
+}
+
+/// Rotation that turns +z into `look_direction(yaw, pitch)`.
+pub(crate) fn look_rotation(yaw: f32, pitch: f32) -> Quat {
+    nalgebra_glm::quat_angle_axis(yaw, &Vec3::new(0.0, 1.0, 0.0))
+        * nalgebra_glm::quat_angle_axis(-pitch, &Vec3::new(1.0, 0.0, 0.0))
+}
+
+/// A free flying camera moved with WASD, Q and E and turned with the mouse.
+/// Hold shift to move faster. Add it next to `CameraData` and run `UpdateFlyCameras`.
+#[derive(Debug, Clone)]
+pub struct FlyCamera {
+    pub position: Vec3,
+    /// Radians around the y axis.
+    pub yaw: f32,
+    /// Radians up or down, kept just short of straight up or down.
+    pub pitch: f32,
+    /// Units per second.
+    pub speed: f32,
+    /// Speed multiplier while shift is held.
+    pub boost: f32,
+    /// Radians per pixel of mouse movement.
+    pub sensitivity: f32,
+    /// The camera only turns while this button is held. `None` turns with every mouse movement.
+    pub look_button: Option<MouseButton>,
+}
+
+impl FlyCamera {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 5.0,
+            boost: 3.0,
+            sensitivity: 0.003,
+            look_button: Some(MouseButton::Right),
+        }
+    }
+
+    /// Creates a fly camera at `position` facing `target`.
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        let direction = (target - position).normalize();
+        Self {
+            yaw: direction.x.atan2(direction.z),
+            pitch: direction.y.asin(),
+            ..Self::new(position)
+        }
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        look_direction(self.yaw, self.pitch)
+    }
+
+    pub fn rotation(&self) -> Quat {
+        look_rotation(self.yaw, self.pitch)
+    }
+}
+
+impl Component for FlyCamera {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A camera that circles around a target. Drag with the left mouse button to orbit,
+/// drag with the right or middle button to pan and scroll to zoom.
+/// Add it next to `CameraData` and run `UpdateOrbitCameras`.
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub distance: f32,
+    /// Radians around the y axis.
+    pub yaw: f32,
+    /// Radians the camera looks up, negative values look down on the target.
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Radians per pixel of mouse movement.
+    pub rotate_sensitivity: f32,
+    /// Fraction of the distance moved per pixel, so panning feels the same at any zoom.
+    pub pan_speed: f32,
+    /// Fraction of the distance zoomed per line scrolled.
+    pub zoom_speed: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            rotate_sensitivity: 0.005,
+            pan_speed: 0.0015,
+            zoom_speed: 0.1,
+        }
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        look_direction(self.yaw, self.pitch)
+    }
+
+    pub fn rotation(&self) -> Quat {
+        look_rotation(self.yaw, self.pitch)
+    }
+
+    /// Where the camera sits.
+    pub fn eye(&self) -> Vec3 {
+        self.target - self.forward() * self.distance
+    }
+}
+
+impl Component for OrbitCamera {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -1,12 +1,48 @@
-use nalgebra_glm::{Mat4, Vec3};
+use crate::{core::Ray, gui::core::Rectangle};
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
 use specs::{Component, DenseVecStorage};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionType {
+    Perspective {
+        fov: f32,
+    },
+    /// `size` is half of the view's height in world units. The width follows the aspect ratio.
+    Orthographic {
+        size: f32,
+    },
+}
+
+/// What a camera clears its viewport to before drawing.
+/// Depth is always cleared, every camera gets a fresh depth buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraClear {
+    Color(Vec4),
+    /// Keep what is already in the target, useful for overlays drawn on top of another camera.
+    DepthOnly,
+}
+
+impl Default for CameraClear {
+    fn default() -> Self {
+        CameraClear::Color(Vec4::new(0.0, 0.0, 0.0, 1.0))
+    }
+}
+
 pub struct CameraData {
     pub active: bool,
     pub projection: Mat4,
     pub view: Mat4,
+    /// Area of the target this camera draws to from 0 to 1, (0, 0) is the top left.
+    pub viewport: Rectangle,
+    /// Cameras are drawn from the lowest priority to the highest. Cameras that render to
+    /// a texture should come before the cameras that show that texture.
+    pub priority: i32,
+    pub clear: CameraClear,
+    /// Name of a render texture created with `AssetManager::create_render_texture`.
+    /// When none is set the camera draws to the window.
+    pub target: Option<String>,
 
-    fov: f32,
+    projection_type: ProjectionType,
     z_near: f32,
     z_far: f32,
 }
@@ -17,7 +53,11 @@ impl Default for CameraData {
             active: false,
             projection: Mat4::identity(),
             view: Mat4::identity(),
-            fov: 70.0,
+            viewport: Self::full_viewport(),
+            priority: 0,
+            clear: CameraClear::default(),
+            target: None,
+            projection_type: ProjectionType::Perspective { fov: 70.0 },
             z_near: 0.1,
             z_far: 100.0,
         }
@@ -30,15 +70,43 @@ impl CameraData {
             projection: nalgebra_glm::perspective_fov_lh_no(fov, width, height, z_near, z_far),
             view: Mat4::identity(),
             active: true,
-            fov,
+            viewport: Self::full_viewport(),
+            priority: 0,
+            clear: CameraClear::default(),
+            target: None,
+            projection_type: ProjectionType::Perspective { fov },
             z_near,
             z_far,
         }
     }
 
+    /// Creates an orthographic camera that shows `size * 2` world units vertically.
+    pub fn new_orthographic(size: f32, width: f32, height: f32, z_near: f32, z_far: f32) -> Self {
+        Self {
+            projection: Self::orthographic(size, width, height, z_near, z_far),
+            view: Mat4::identity(),
+            active: true,
+            viewport: Self::full_viewport(),
+            priority: 0,
+            clear: CameraClear::default(),
+            target: None,
+            projection_type: ProjectionType::Orthographic { size },
+            z_near,
+            z_far,
+        }
+    }
+
+    /// `width` and `height` should be the size of the viewport rather than the whole target
+    /// or the image will be stretched.
     pub fn resize(&mut self, width: f32, height: f32) {
-        self.projection =
-            nalgebra_glm::perspective_fov_lh_no(self.fov, width, height, self.z_near, self.z_far);
+        self.projection = match self.projection_type {
+            ProjectionType::Perspective { fov } => {
+                nalgebra_glm::perspective_fov_lh_no(fov, width, height, self.z_near, self.z_far)
+            }
+            ProjectionType::Orthographic { size } => {
+                Self::orthographic(size, width, height, self.z_near, self.z_far)
+            }
+        };
     }
 
     pub fn update_view(&mut self, eye: Vec3, at: Vec3, up: Vec3) {
@@ -48,6 +116,63 @@ impl CameraData {
     pub fn get_matrix(&self) -> Mat4 {
         self.projection * self.view
     }
+
+    /// World space position the view matrix looks from.
+    pub fn position(&self) -> Vec3 {
+        let inverse = nalgebra_glm::inverse(&self.view);
+        Vec3::new(inverse[(0, 3)], inverse[(1, 3)], inverse[(2, 3)])
+    }
+
+    pub fn projection_type(&self) -> ProjectionType {
+        self.projection_type
+    }
+
+    pub fn z_near(&self) -> f32 {
+        self.z_near
+    }
+
+    pub fn z_far(&self) -> f32 {
+        self.z_far
+    }
+
+    /// The viewport in pixels for a target of the given size.
+    pub fn pixel_viewport(&self, width: u32, height: u32) -> Rectangle {
+        Rectangle {
+            x: self.viewport.x * width as f32,
+            y: self.viewport.y * height as f32,
+            width: self.viewport.width * width as f32,
+            height: self.viewport.height * height as f32,
+        }
+    }
+
+    /// The ray going through a point on the screen, e.g. `Input::mouse_position`.
+    /// `viewport` is this camera's area of the target in pixels, see `pixel_viewport`.
+    pub fn screen_point_to_ray(&self, point: Vec2, viewport: Rectangle) -> Ray {
+        let ndc = Vec2::new(
+            (point.x - viewport.x) / viewport.width * 2.0 - 1.0,
+            1.0 - (point.y - viewport.y) / viewport.height * 2.0,
+        );
+        let inverse = nalgebra_glm::inverse(&self.get_matrix());
+        let near = inverse * Vec4::new(ndc.x, ndc.y, -1.0, 1.0);
+        let far = inverse * Vec4::new(ndc.x, ndc.y, 1.0, 1.0);
+        let near = near.xyz() / near.w;
+        let far = far.xyz() / far.w;
+        Ray::new(near, far - near)
+    }
+
+    fn full_viewport() -> Rectangle {
+        Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0,
+        }
+    }
+
+    fn orthographic(size: f32, width: f32, height: f32, z_near: f32, z_far: f32) -> Mat4 {
+        let half_width = size * (width / height);
+        nalgebra_glm::ortho_lh_no(-half_width, half_width, -size, size, z_near, z_far)
+    }
 }
 
 impl Component for CameraData {
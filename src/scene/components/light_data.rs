@@ -4,6 +4,7 @@ use specs::{Component, DenseVecStorage};
 pub enum LightType {
     Directional(DirectionalLightData),
     Point(PointLightData),
+    Spot(SpotLightData),
 }
 
 pub struct DirectionalLightData {
@@ -24,16 +25,20 @@ impl Component for DirectionalLightData {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Light falls off with the inverse square of the distance and fades out smoothly at `range`.
+/// A range of zero or less means the light reaches everything.
 pub struct PointLightData {
     pub color: Vec3,
-    pub attenuation: f32,
+    pub intensity: f32,
+    pub range: f32,
 }
 
 impl Default for PointLightData {
     fn default() -> Self {
         Self {
             color: Vec3::zeros(),
-            attenuation: 0.0,
+            intensity: 1.0,
+            range: 10.0,
         }
     }
 }
@@ -41,3 +46,34 @@ impl Default for PointLightData {
 impl Component for PointLightData {
     type Storage = DenseVecStorage<Self>;
 }
+
+/// A point light that only shines in a cone around `direction`, which is rotated along with
+/// the entity's `Transform`. A zero direction points down.
+/// Cone angles are in radians measured from the direction. Light is at full strength inside of
+/// `inner_cone_angle` and fades out towards `outer_cone_angle`, an inner angle at or past the
+/// outer one gives a hard edge.
+pub struct SpotLightData {
+    pub color: Vec3,
+    pub intensity: f32,
+    pub range: f32,
+    pub direction: Vec3,
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
+}
+
+impl Default for SpotLightData {
+    fn default() -> Self {
+        Self {
+            color: Vec3::zeros(),
+            intensity: 1.0,
+            range: 10.0,
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            inner_cone_angle: 0.0,
+            outer_cone_angle: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
+
+impl Component for SpotLightData {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -1,5 +1,8 @@
-use specs::{Component, DenseVecStorage};
+use nalgebra_glm::Vec4;
+use specs::{Component, DenseVecStorage, FlaggedStorage};
 
+/// Draws every sub mesh of the entity's `Mesh` with one material. Entities without it draw
+/// each sub mesh with the material it was imported with.
 #[derive(Default)]
 pub struct Material {
     // We might have more than one material per mesh.
@@ -16,5 +19,30 @@ impl Material {
 }
 
 impl Component for Material {
+    // Flagged so the render queue only has to look at entities that changed.
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Changes how a single entity looks without touching the material it shares with others.
+pub struct MaterialOverride {
+    /// Multiplied with the material's color.
+    pub color: Vec4,
+}
+
+impl Default for MaterialOverride {
+    fn default() -> Self {
+        Self {
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl MaterialOverride {
+    pub fn tint(color: Vec4) -> Self {
+        Self { color }
+    }
+}
+
+impl Component for MaterialOverride {
     type Storage = DenseVecStorage<Self>;
 }
@@ -1,4 +1,4 @@
-use specs::{Component, DenseVecStorage};
+use specs::{Component, DenseVecStorage, FlaggedStorage};
 
 #[derive(Default)]
 pub struct Mesh {
@@ -17,5 +17,6 @@ impl Mesh {
 }
 
 impl Component for Mesh {
-    type Storage = DenseVecStorage<Self>;
+    // Flagged so the render queue only has to look at entities that changed.
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
 }
@@ -5,13 +5,22 @@ pub(crate) mod transform;
 pub use transform::Transform;
 
 pub(crate) mod camera_data;
-pub use camera_data::CameraData;
+pub use camera_data::{CameraClear, CameraData, ProjectionType};
+
+pub(crate) mod camera_controller;
+pub use camera_controller::{FlyCamera, OrbitCamera};
 
 pub(crate) mod material;
-pub use material::Material;
+pub use material::{Material, MaterialOverride};
 
 pub(crate) mod skybox;
 pub use skybox::SkyboxData;
 
+pub(crate) mod sprite;
+pub use sprite::Sprite;
+
+pub(crate) mod parent;
+pub use parent::Parent;
+
 pub(crate) mod light_data;
 pub use light_data::*;
@@ -0,0 +1,16 @@
+use specs::{Component, DenseVecStorage, Entity};
+
+/// Makes an entity's `Transform` relative to the transform of another entity.
+pub struct Parent {
+    pub entity: Entity,
+}
+
+impl Parent {
+    pub fn new(entity: Entity) -> Self {
+        Self { entity }
+    }
+}
+
+impl Component for Parent {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,61 @@
+use crate::{gui::core::Rectangle, AtlasRegion};
+use nalgebra_glm::{Vec2, Vec4};
+use specs::{Component, DenseVecStorage};
+
+/// A textured quad drawn by the sprite pipeline. The quad is the size of `source` divided by
+/// `pixels_per_unit`, use the entity's `Transform` to place and scale it in the world.
+///
+/// Sprites are drawn without a depth test, on top of whatever the pipelines before them
+/// drew. Only `z_order` decides how they overlap each other.
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    /// Name of the image in the asset manager.
+    pub image: String,
+    /// Region of the image to draw in pixels, `None` draws the whole image.
+    pub source: Option<Rectangle>,
+    /// Multiplied with the image's color.
+    pub tint: Vec4,
+    /// Point of the sprite that sits at the transform's position. (0, 0) is the bottom left
+    /// corner and (1, 1) is the top right.
+    pub pivot: Vec2,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// How many pixels of the image make up one world unit, 100 by default.
+    pub pixels_per_unit: f32,
+    /// Sprites with a higher z order are drawn on top.
+    pub z_order: i32,
+}
+
+impl Sprite {
+    pub fn new<T>(image: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            image: image.into(),
+            source: None,
+            tint: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            pivot: Vec2::new(0.5, 0.5),
+            flip_x: false,
+            flip_y: false,
+            pixels_per_unit: 100.0,
+            z_order: 0,
+        }
+    }
+
+    /// A sprite that draws a region of an atlas. Sprites sharing an atlas page are batched.
+    pub fn from_region(region: &AtlasRegion) -> Self {
+        let mut sprite = Self::new(region.page.clone());
+        sprite.source = Some(Rectangle {
+            x: region.x as f32,
+            y: region.y as f32,
+            width: region.width as f32,
+            height: region.height as f32,
+        });
+        sprite
+    }
+}
+
+impl Component for Sprite {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -1,12 +1,13 @@
-use crate::Application;
 use bytemuck::{Pod, Zeroable};
-use nalgebra_glm::{Mat4, Quat, Vec3};
+use nalgebra_glm::{Mat4, Quat, Vec3, Vec4};
 use specs::{Component, DenseVecStorage};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct LocalUniform {
     pub world: Mat4,
+    // Tint from the entity's `MaterialOverride`.
+    pub color: Vec4,
 }
 unsafe impl Zeroable for LocalUniform {}
 unsafe impl Pod for LocalUniform {}
@@ -15,6 +16,7 @@ impl Default for LocalUniform {
     fn default() -> Self {
         Self {
             world: Mat4::identity(),
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
         }
     }
 }
@@ -24,26 +26,29 @@ pub struct Transform {
     pub position: Vec3,
     pub scale: Vec3,
     pub rotation: Quat,
+    /// World matrix, includes the parents' transforms when the entity has a `Parent`.
     pub matrix: Mat4,
 
-    pub(crate) local_buffer: wgpu::Buffer,
-    pub(crate) bind_group: wgpu::BindGroup,
+    // Offset of this transform's slot in the shared local uniform buffer, assigned every frame.
+    pub(crate) dynamic_offset: wgpu::DynamicOffset,
 }
 
-impl Transform {
-    pub fn new(app: &mut Application) -> Self {
-        let (local_buffer, bind_group) = Self::create_bindings(app);
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl Transform {
+    pub fn new() -> Self {
         Self {
             position: Vec3::new(0.0, 0.0, 0.0),
             scale: Vec3::new(1.0, 1.0, 1.0),
             rotation: Quat::identity(),
             matrix: Mat4::identity(),
-            local_buffer,
-            bind_group,
+            dynamic_offset: 0,
         }
     }
-
     pub fn rotate_on_axis<'a>(&'a mut self, axis: Vec3, angle: f32) -> &'a mut Self {
         self.rotation = self.rotation * nalgebra_glm::quat_angle_axis(angle, &axis);
         self
@@ -83,30 +88,13 @@ impl Transform {
         self.matrix = translation * rotation * scale;
     }
 
-    pub(crate) fn create_bindings(app: &Application) -> (wgpu::Buffer, wgpu::BindGroup) {
-        let bind_group_layout = &app.render_graph.as_ref().unwrap().local_bind_group_layout;
-        // This data needs to be saved and passed onto the pipeline.
-        let local_buffer = app.renderer.device.create_buffer_with_data(
-            bytemuck::bytes_of(&LocalUniform::default()),
-            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        );
-
-        let local_bind_group = app
-            .renderer
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: bind_group_layout,
-                bindings: &[wgpu::Binding {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &local_buffer,
-                        range: 0..std::mem::size_of::<LocalUniform>() as u64,
-                    },
-                }],
-                label: None,
-            });
-
-        (local_buffer, local_bind_group)
+    /// Position in world space, read from `matrix` so it includes the parents' transforms.
+    pub fn world_position(&self) -> Vec3 {
+        Vec3::new(
+            self.matrix[(0, 3)],
+            self.matrix[(1, 3)],
+            self.matrix[(2, 3)],
+        )
     }
 }
 
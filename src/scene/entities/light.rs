@@ -5,5 +5,6 @@ pub fn create(world: &mut World, light_type: LightType, transform: Transform) ->
     match light_type {
         LightType::Directional(data) => world.create_entity().with(data).with(transform).build(),
         LightType::Point(data) => world.create_entity().with(data).with(transform).build(),
+        LightType::Spot(data) => world.create_entity().with(data).with(transform).build(),
     }
 }
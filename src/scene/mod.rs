@@ -1,5 +1,6 @@
 pub mod components;
 pub mod entities;
+pub mod picking;
 pub mod resources;
 pub mod systems;
 
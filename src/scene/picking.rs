@@ -0,0 +1,108 @@
+use crate::{
+    core::Ray,
+    scene::components::{Mesh, Transform},
+    AssetManager,
+};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use specs::{Entity, World, WorldExt};
+
+/// Where a ray hit an entity's mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct PickHit {
+    pub entity: Entity,
+    /// Distance along the ray in world units.
+    pub distance: f32,
+    pub position: Vec3,
+    /// World space normal of the triangle that was hit, facing the ray.
+    pub normal: Vec3,
+    /// Index of the sub mesh the triangle belongs to.
+    pub sub_mesh: usize,
+}
+
+/// Finds the closest entity with a `Mesh` the ray hits.
+///
+/// Each entity's bounding box is tested first and only the ones the ray passes through have
+/// their triangles tested. Transforms are used as of the last rendered frame and entities
+/// without one are treated as sitting at the origin. Only triangle lists can be hit.
+pub fn pick(world: &World, asset_manager: &AssetManager, ray: &Ray) -> Option<PickHit> {
+    use specs::Join;
+
+    let entities = world.entities();
+    let meshes = world.read_component::<Mesh>();
+    let transforms = world.read_component::<Transform>();
+
+    let mut candidates = Vec::new();
+    for (entity, mesh, transform) in (&entities, &meshes, transforms.maybe()).join() {
+        let asset_mesh = match asset_manager.get_mesh_option(mesh.mesh_name.clone()) {
+            Some(asset_mesh) => asset_mesh,
+            None => continue,
+        };
+        let matrix = transform.map_or(Mat4::identity(), |transform| transform.matrix);
+        let bounds = asset_mesh.bounds().transform(&matrix);
+        if let Some(distance) = ray.intersect_bounding_box(&bounds) {
+            candidates.push((distance, entity, asset_mesh, matrix));
+        }
+    }
+    candidates.sort_by(|(a, ..), (b, ..)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut closest: Option<PickHit> = None;
+    for (bounds_distance, entity, asset_mesh, matrix) in candidates {
+        // Nothing in this box can be closer than what we already hit.
+        if closest.map_or(false, |hit| hit.distance < bounds_distance) {
+            break;
+        }
+
+        let inverse = nalgebra_glm::inverse(&matrix);
+        let local_ray = ray.transform(&inverse);
+
+        for (sub_mesh_index, sub_mesh) in asset_mesh.sub_meshes.iter().enumerate() {
+            if sub_mesh.topology() != wgpu::PrimitiveTopology::TriangleList
+                || local_ray
+                    .intersect_bounding_box(&sub_mesh.bounds())
+                    .is_none()
+            {
+                continue;
+            }
+
+            let vertices = sub_mesh.vertices();
+            for triangle in sub_mesh.indices().chunks_exact(3) {
+                let a = &vertices[triangle[0] as usize].position;
+                let b = &vertices[triangle[1] as usize].position;
+                let c = &vertices[triangle[2] as usize].position;
+                let local_distance = match local_ray.intersect_triangle(a, b, c) {
+                    Some(local_distance) => local_distance,
+                    None => continue,
+                };
+
+                // The local ray is scaled with the mesh, so compare distances in world space.
+                let local_position = local_ray.point_at(local_distance);
+                let position = (matrix
+                    * Vec4::new(local_position.x, local_position.y, local_position.z, 1.0))
+                .xyz();
+                let distance = (position - ray.origin).magnitude();
+                if closest.map_or(false, |hit| hit.distance <= distance) {
+                    continue;
+                }
+
+                let local_normal = (b - a).cross(&(c - a));
+                let mut normal = (inverse.transpose()
+                    * Vec4::new(local_normal.x, local_normal.y, local_normal.z, 0.0))
+                .xyz()
+                .normalize();
+                if normal.dot(&ray.direction) > 0.0 {
+                    normal = -normal;
+                }
+
+                closest = Some(PickHit {
+                    entity,
+                    distance,
+                    position,
+                    normal,
+                    sub_mesh: sub_mesh_index,
+                });
+            }
+        }
+    }
+
+    closest
+}
@@ -0,0 +1,36 @@
+use crate::gui::core::Rectangle;
+use specs::Entity;
+
+/// The camera the render graph is drawing for right now.
+/// The application inserts it before every camera's pass through the graph.
+#[derive(Debug, Clone)]
+pub struct CurrentCamera {
+    pub entity: Entity,
+    /// Area of the target this camera draws to, in pixels.
+    pub viewport: Rectangle,
+    /// Width and height of the texture being drawn to.
+    pub target_size: (u32, u32),
+}
+
+impl CurrentCamera {
+    /// Restricts drawing to this camera's viewport.
+    /// Every render pass of a scene node should call this before drawing.
+    pub fn set_viewport(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_viewport(
+            self.viewport.x,
+            self.viewport.y,
+            self.viewport.width,
+            self.viewport.height,
+            0.0,
+            1.0,
+        );
+    }
+
+    /// True when the viewport covers the whole target.
+    pub fn is_fullscreen(&self) -> bool {
+        self.viewport.x <= 0.0
+            && self.viewport.y <= 0.0
+            && self.viewport.width >= self.target_size.0 as f32
+            && self.viewport.height >= self.target_size.1 as f32
+    }
+}
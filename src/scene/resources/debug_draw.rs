@@ -0,0 +1,226 @@
+use crate::core::BoundingBox;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+/// How a debug shape is drawn. A plain color converts into a style that lasts one frame and
+/// is hidden behind geometry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugStyle {
+    pub color: Vec4,
+    /// Seconds the shape stays on screen. Zero draws it for a single frame.
+    pub duration: f32,
+    /// When false the shape is drawn on top of everything.
+    pub depth_test: bool,
+}
+
+impl DebugStyle {
+    pub fn new(color: Vec4) -> Self {
+        Self {
+            color,
+            duration: 0.0,
+            depth_test: true,
+        }
+    }
+
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn on_top(mut self) -> Self {
+        self.depth_test = false;
+        self
+    }
+}
+
+impl From<Vec4> for DebugStyle {
+    fn from(color: Vec4) -> Self {
+        Self::new(color)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DebugLine {
+    pub(crate) start: Vec3,
+    pub(crate) end: Vec3,
+    pub(crate) color: Vec4,
+    pub(crate) depth_test: bool,
+    remaining: f32,
+}
+
+/// Immediate mode lines for debugging. Push shapes from any system and they show up in every
+/// camera the next time the scene is rendered.
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    pub(crate) lines: Vec<DebugLine>,
+}
+
+impl DebugDraw {
+    pub fn line<S>(&mut self, start: Vec3, end: Vec3, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        self.lines.push(DebugLine {
+            start,
+            end,
+            color: style.color,
+            depth_test: style.depth_test,
+            remaining: style.duration,
+        });
+    }
+
+    pub fn bounding_box<S>(&mut self, bounds: &BoundingBox, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        let corner = |index: usize| {
+            Vec3::new(
+                if index & 1 == 0 {
+                    bounds.min.x
+                } else {
+                    bounds.max.x
+                },
+                if index & 2 == 0 {
+                    bounds.min.y
+                } else {
+                    bounds.max.y
+                },
+                if index & 4 == 0 {
+                    bounds.min.z
+                } else {
+                    bounds.max.z
+                },
+            )
+        };
+        self.box_edges(corner, style);
+    }
+
+    pub fn sphere<S>(&mut self, center: Vec3, radius: f32, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        let x = Vec3::new(radius, 0.0, 0.0);
+        let y = Vec3::new(0.0, radius, 0.0);
+        let z = Vec3::new(0.0, 0.0, radius);
+        self.circle(center, x, y, style);
+        self.circle(center, y, z, style);
+        self.circle(center, z, x, style);
+    }
+
+    /// Draws the frustum of a view projection matrix, e.g. `CameraData::get_matrix`.
+    pub fn frustum<S>(&mut self, view_projection: &Mat4, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        let inverse = nalgebra_glm::inverse(view_projection);
+        let corner = |index: usize| {
+            let corner = inverse
+                * Vec4::new(
+                    if index & 1 == 0 { -1.0 } else { 1.0 },
+                    if index & 2 == 0 { -1.0 } else { 1.0 },
+                    if index & 4 == 0 { -1.0 } else { 1.0 },
+                    1.0,
+                );
+            corner.xyz() / corner.w
+        };
+        self.box_edges(corner, style);
+    }
+
+    /// A grid on the xz plane with `divisions` cells along each side.
+    pub fn grid<S>(&mut self, center: Vec3, size: f32, divisions: u32, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        let half = size * 0.5;
+        let divisions = divisions.max(1);
+        for i in 0..=divisions {
+            let offset = -half + size * i as f32 / divisions as f32;
+            self.line(
+                center + Vec3::new(offset, 0.0, -half),
+                center + Vec3::new(offset, 0.0, half),
+                style,
+            );
+            self.line(
+                center + Vec3::new(-half, 0.0, offset),
+                center + Vec3::new(half, 0.0, offset),
+                style,
+            );
+        }
+    }
+
+    /// Draws the axes of a transform, x in red, y in green and z in blue.
+    /// Only the duration and depth test of the style are used.
+    pub fn axes<S>(&mut self, matrix: &Mat4, size: f32, style: S)
+    where
+        S: Into<DebugStyle>,
+    {
+        let style = style.into();
+        let origin = (matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+        let axes = [
+            (
+                Vec4::new(size, 0.0, 0.0, 0.0),
+                Vec4::new(1.0, 0.0, 0.0, 1.0),
+            ),
+            (
+                Vec4::new(0.0, size, 0.0, 0.0),
+                Vec4::new(0.0, 1.0, 0.0, 1.0),
+            ),
+            (
+                Vec4::new(0.0, 0.0, size, 0.0),
+                Vec4::new(0.0, 0.0, 1.0, 1.0),
+            ),
+        ];
+        for (axis, color) in axes.iter() {
+            let end = origin + (matrix * axis).xyz();
+            self.line(
+                origin,
+                end,
+                DebugStyle {
+                    color: *color,
+                    ..style
+                },
+            );
+        }
+    }
+
+    /// Counts down shapes with a duration.
+    pub(crate) fn update(&mut self, delta_time: f32) {
+        for line in self.lines.iter_mut() {
+            line.remaining -= delta_time;
+        }
+    }
+
+    /// Drops every shape that has been on screen long enough. Called once per rendered frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.lines.retain(|line| line.remaining > 0.0);
+    }
+
+    // The 12 edges of a box given a way to look up its 8 corners, corner bits are x, y and z.
+    fn box_edges<F>(&mut self, corner: F, style: DebugStyle)
+    where
+        F: Fn(usize) -> Vec3,
+    {
+        for index in 0..8 {
+            for bit in [1, 2, 4].iter() {
+                if index & bit == 0 {
+                    self.line(corner(index), corner(index | bit), style);
+                }
+            }
+        }
+    }
+
+    fn circle(&mut self, center: Vec3, axis_a: Vec3, axis_b: Vec3, style: DebugStyle) {
+        const SEGMENTS: usize = 32;
+        let point = |segment: usize| {
+            let angle = segment as f32 / SEGMENTS as f32 * std::f32::consts::PI * 2.0;
+            center + axis_a * angle.cos() + axis_b * angle.sin()
+        };
+        for segment in 0..SEGMENTS {
+            self.line(point(segment), point(segment + 1), style);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::scene::components::{CameraData, ProjectionType};
+use nalgebra_glm::Vec4;
+
+/// What the PBR and unlit shaders output. Everything except `Lit` and `Wireframe` replaces the
+/// final shading with one of its inputs so they can be checked on their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugViewMode {
+    Lit,
+    Albedo,
+    /// World space normals mapped from -1..1 to 0..1.
+    Normals,
+    Uvs,
+    /// Metallic in red and roughness in green.
+    MetallicRoughness,
+    /// Point and spot lights reaching each pixel's cluster, blue is none and red is many.
+    LightCount,
+    /// Linear depth between the camera's near and far planes.
+    Depth,
+    /// Regular shading with mesh edges drawn on top.
+    Wireframe,
+}
+
+impl DebugViewMode {
+    pub const ALL: [DebugViewMode; 8] = [
+        DebugViewMode::Lit,
+        DebugViewMode::Albedo,
+        DebugViewMode::Normals,
+        DebugViewMode::Uvs,
+        DebugViewMode::MetallicRoughness,
+        DebugViewMode::LightCount,
+        DebugViewMode::Depth,
+        DebugViewMode::Wireframe,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugViewMode::Lit => "Lit",
+            DebugViewMode::Albedo => "Albedo",
+            DebugViewMode::Normals => "Normals",
+            DebugViewMode::Uvs => "UVs",
+            DebugViewMode::MetallicRoughness => "Metallic/Roughness",
+            DebugViewMode::LightCount => "Light count",
+            DebugViewMode::Depth => "Depth",
+            DebugViewMode::Wireframe => "Wireframe",
+        }
+    }
+
+    // Has to match the constants in library/debug_view.glsl.
+    // x: mode, y: z near, z: z far, w: 1 for orthographic cameras.
+    pub(crate) fn to_uniform(&self, camera_data: &CameraData) -> Vec4 {
+        let mode = Self::ALL.iter().position(|mode| mode == self).unwrap();
+        Vec4::new(
+            mode as f32,
+            camera_data.z_near(),
+            camera_data.z_far(),
+            match camera_data.projection_type() {
+                ProjectionType::Orthographic { .. } => 1.0,
+                ProjectionType::Perspective { .. } => 0.0,
+            },
+        )
+    }
+}
+
+impl Default for DebugViewMode {
+    fn default() -> Self {
+        DebugViewMode::Lit
+    }
+}
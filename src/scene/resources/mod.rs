@@ -1,2 +1,14 @@
+mod render_queue;
+pub use render_queue::{DrawItem, RenderQueue};
+
+mod current_camera;
+pub use current_camera::CurrentCamera;
+
+mod debug_draw;
+pub use debug_draw::{DebugDraw, DebugStyle};
+
+mod debug_view_mode;
+pub use debug_view_mode::DebugViewMode;
+
 #[derive(Default)]
 pub struct DeltaTime(pub f32);
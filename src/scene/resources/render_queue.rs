@@ -0,0 +1,200 @@
+use crate::{
+    scene::components::{Material, Mesh},
+    AssetManager,
+};
+use nalgebra_glm::{Mat4, Vec4};
+use specs::{
+    storage::ComponentEvent, BitSet, Entities, Entity, Join, ReadStorage, ReaderId, World, WorldExt,
+};
+use std::cmp::Ordering;
+
+/// A single thing to draw. One is created for every sub mesh of an entity's `Mesh`.
+/// Sub meshes use the entity's `Material` when it has one and their own material otherwise,
+/// so meshes with several materials imported from glTF draw each part correctly.
+#[derive(Debug, Clone)]
+pub struct DrawItem {
+    pub entity: Entity,
+    /// Name of the render graph node that draws this item.
+    pub pipeline: String,
+    pub material_index: i32,
+    pub mesh_name: String,
+    /// Index into the mesh's `sub_meshes`.
+    pub sub_mesh: usize,
+    /// Alpha blended items are drawn back to front instead of front to back.
+    pub transparent: bool,
+    /// View space depth of the entity, updated every frame.
+    pub depth: f32,
+}
+
+/// Draw items built once and kept up to date from `Mesh` and `Material` change events.
+/// Items are sorted by pipeline, then material, then mesh so render passes only switch
+/// bind groups when they have to. Inside of a material opaque items are drawn front to back
+/// and transparent items back to front.
+pub struct RenderQueue {
+    items: Vec<DrawItem>,
+    mesh_reader: ReaderId<ComponentEvent>,
+    material_reader: ReaderId<ComponentEvent>,
+    changed: BitSet,
+}
+
+impl RenderQueue {
+    /// Readers have to be registered before any meshes or materials are added to the world.
+    /// Otherwise those entities will never make it into the queue.
+    pub fn new(world: &mut World) -> Self {
+        let mesh_reader = world.write_storage::<Mesh>().register_reader();
+        let material_reader = world.write_storage::<Material>().register_reader();
+        Self {
+            items: Vec::new(),
+            mesh_reader,
+            material_reader,
+            changed: BitSet::new(),
+        }
+    }
+
+    /// Returns every item drawn by the given pipeline in the order they should be drawn.
+    pub fn get_items<T>(&self, pipeline: T) -> &[DrawItem]
+    where
+        T: AsRef<str>,
+    {
+        let pipeline = pipeline.as_ref();
+        match self.items.iter().position(|item| item.pipeline == pipeline) {
+            Some(start) => {
+                let end = self.items[start..]
+                    .iter()
+                    .position(|item| item.pipeline != pipeline)
+                    .map(|count| start + count)
+                    .unwrap_or_else(|| self.items.len());
+                &self.items[start..end]
+            }
+            None => &[],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Rebuilds the items of every entity whose mesh or material was inserted, modified or removed
+    /// since the last update. Entities that didn't change are left alone, apart from picking up
+    /// the pipeline of materials edited through `AssetManager::get_material_mut`.
+    pub(crate) fn update(
+        &mut self,
+        asset_manager: &AssetManager,
+        entities: &Entities<'_>,
+        meshes: &ReadStorage<'_, Mesh>,
+        materials: &ReadStorage<'_, Material>,
+    ) {
+        self.changed.clear();
+        for event in meshes.channel().read(&mut self.mesh_reader) {
+            Self::flag(&mut self.changed, event);
+        }
+        for event in materials.channel().read(&mut self.material_reader) {
+            Self::flag(&mut self.changed, event);
+        }
+
+        // Editing a material's alpha mode or sidedness moves it to another pipeline.
+        if !asset_manager.edited_materials.is_empty() {
+            for item in self.items.iter_mut() {
+                if !asset_manager
+                    .edited_materials
+                    .contains(&item.material_index)
+                {
+                    continue;
+                }
+                if let Some(asset_material) = asset_manager.materials.get(&item.material_index) {
+                    item.pipeline = asset_material.pipeline_name().to_string();
+                    item.transparent = asset_material.alpha_mode().is_transparent();
+                }
+            }
+        }
+
+        if self.changed.is_empty() {
+            return;
+        }
+
+        let changed = &self.changed;
+        self.items
+            .retain(|item| !changed.contains(item.entity.id()));
+
+        for (entity, mesh, material, _) in
+            (entities, meshes, materials.maybe(), &self.changed).join()
+        {
+            let asset_mesh = match asset_manager.get_mesh_option(mesh.mesh_name.clone()) {
+                Some(asset_mesh) => asset_mesh,
+                None => {
+                    log::warn!(
+                        "RenderQueue: entity {} uses mesh {} which doesn't exist.",
+                        entity.id(),
+                        mesh.mesh_name
+                    );
+                    continue;
+                }
+            };
+            for (sub_mesh_index, sub_mesh) in asset_mesh.sub_meshes.iter().enumerate() {
+                let material_index = material
+                    .map(|material| material.index)
+                    .unwrap_or(sub_mesh.material_index);
+                let asset_material = match asset_manager.materials.get(&material_index) {
+                    Some(asset_material) => asset_material,
+                    None => {
+                        log::warn!(
+                            "RenderQueue: entity {} uses material {} which doesn't exist.",
+                            entity.id(),
+                            material_index
+                        );
+                        continue;
+                    }
+                };
+                self.items.push(DrawItem {
+                    entity,
+                    pipeline: asset_material.pipeline_name().to_string(),
+                    material_index,
+                    mesh_name: mesh.mesh_name.clone(),
+                    sub_mesh: sub_mesh_index,
+                    transparent: asset_material.alpha_mode().is_transparent(),
+                    depth: 0.0,
+                });
+            }
+        }
+    }
+
+    /// Updates item depths from the given view matrix and sorts the queue.
+    pub(crate) fn sort<F>(&mut self, view: &Mat4, mut get_position: F)
+    where
+        F: FnMut(Entity) -> Option<Vec4>,
+    {
+        for item in self.items.iter_mut() {
+            if let Some(position) = get_position(item.entity) {
+                item.depth = (view * position).z;
+            }
+        }
+
+        self.items.sort_unstable_by(|a, b| {
+            let pipeline = a.pipeline.cmp(&b.pipeline);
+            // Blending only looks right if far away items are drawn first, which matters
+            // more than saving a few bind group changes.
+            if a.transparent {
+                return pipeline.then(b.depth.partial_cmp(&a.depth).unwrap_or(Ordering::Equal));
+            }
+            pipeline
+                .then(a.material_index.cmp(&b.material_index))
+                .then_with(|| a.mesh_name.cmp(&b.mesh_name))
+                .then(a.sub_mesh.cmp(&b.sub_mesh))
+                .then(a.depth.partial_cmp(&b.depth).unwrap_or(Ordering::Equal))
+        });
+    }
+
+    fn flag(changed: &mut BitSet, event: &ComponentEvent) {
+        match event {
+            ComponentEvent::Inserted(id)
+            | ComponentEvent::Modified(id)
+            | ComponentEvent::Removed(id) => {
+                changed.add(*id);
+            }
+        }
+    }
+}
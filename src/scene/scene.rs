@@ -1,5 +1,6 @@
 use super::components;
 use super::resources;
+use crate::core::input::Input;
 use specs::world::WorldExt;
 use specs::{Dispatcher, DispatcherBuilder, World};
 
@@ -13,13 +14,24 @@ impl<'a> Scene<'a> {
         // Add our components here
         let mut world = world.unwrap_or(World::new());
         world.insert(resources::DeltaTime(0.05));
+        world.insert(Input::new());
+        world.insert(resources::DebugDraw::default());
+        world.insert(resources::DebugViewMode::default());
         world.register::<components::Mesh>();
         world.register::<components::Material>();
+        world.register::<components::MaterialOverride>();
         world.register::<components::CameraData>();
         world.register::<components::Transform>();
+        world.register::<components::Parent>();
         world.register::<components::SkyboxData>();
         world.register::<components::DirectionalLightData>();
         world.register::<components::PointLightData>();
+        world.register::<components::SpotLightData>();
+        world.register::<components::Sprite>();
+        world.register::<components::FlyCamera>();
+        world.register::<components::OrbitCamera>();
+        let render_queue = resources::RenderQueue::new(&mut world);
+        world.insert(render_queue);
 
         // Add our systems here..
         let dispatch_buider = dispatch_buider.unwrap_or(DispatcherBuilder::new());
@@ -29,11 +41,16 @@ impl<'a> Scene<'a> {
         Scene { world, dispatcher }
     }
 
-    pub(crate) fn update(&mut self, delta_time: f32) {
+    pub(crate) fn update(&mut self, delta_time: f32, input: &Input) {
         {
             let mut delta = self.world.write_resource::<resources::DeltaTime>();
             *delta = resources::DeltaTime(delta_time);
         }
+        self.world
+            .write_resource::<resources::DebugDraw>()
+            .update(delta_time);
+        // Systems get a copy of this step's input.
+        self.world.insert(input.clone());
 
         self.dispatcher.dispatch(&mut self.world);
         self.world.maintain();
@@ -0,0 +1,66 @@
+use crate::{
+    scene::{
+        components::{Mesh, Transform},
+        resources::{DebugDraw, DebugStyle, DebugViewMode},
+    },
+    AssetManager,
+};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use specs::{Read, ReadStorage, System, Write};
+
+/// Pushes the edges of every mesh into `DebugDraw` while the wireframe view mode is on.
+pub struct DrawWireframes<'a> {
+    pub(crate) asset_manager: &'a AssetManager,
+}
+
+impl<'a> System<'a> for DrawWireframes<'a> {
+    type SystemData = (
+        ReadStorage<'a, Mesh>,
+        ReadStorage<'a, Transform>,
+        Option<Read<'a, DebugViewMode>>,
+        Option<Write<'a, DebugDraw>>,
+    );
+
+    fn run(&mut self, (meshes, transforms, debug_view_mode, debug_draw): Self::SystemData) {
+        use specs::Join;
+
+        if debug_view_mode.map_or(true, |mode| *mode != DebugViewMode::Wireframe) {
+            return;
+        }
+        let mut debug_draw = match debug_draw {
+            Some(debug_draw) => debug_draw,
+            None => return,
+        };
+
+        let style = DebugStyle::new(Vec4::new(0.0, 0.0, 0.0, 0.8)).on_top();
+        for (mesh, transform) in (&meshes, transforms.maybe()).join() {
+            let asset_mesh = match self.asset_manager.get_mesh_option(mesh.mesh_name.clone()) {
+                Some(asset_mesh) => asset_mesh,
+                None => continue,
+            };
+            let matrix = transform.map_or(Mat4::identity(), |transform| transform.matrix);
+
+            for sub_mesh in asset_mesh.sub_meshes.iter() {
+                if sub_mesh.topology() != wgpu::PrimitiveTopology::TriangleList {
+                    continue;
+                }
+                let positions: Vec<Vec3> = sub_mesh
+                    .vertices()
+                    .iter()
+                    .map(|vertex| {
+                        let position = &vertex.position;
+                        (matrix * Vec4::new(position.x, position.y, position.z, 1.0)).xyz()
+                    })
+                    .collect();
+                for triangle in sub_mesh.indices().chunks_exact(3) {
+                    let a = positions[triangle[0] as usize];
+                    let b = positions[triangle[1] as usize];
+                    let c = positions[triangle[2] as usize];
+                    debug_draw.line(a, b, style);
+                    debug_draw.line(b, c, style);
+                    debug_draw.line(c, a, style);
+                }
+            }
+        }
+    }
+}
@@ -7,3 +7,19 @@ mod prepare_pbr;
 mod render_pbr;
 pub use prepare_pbr::PreparePBR;
 pub use render_pbr::RenderPBR;
+
+mod prepare_sprites;
+pub use prepare_sprites::PrepareSprites;
+
+mod prepare_transforms;
+mod update_light_clusters;
+mod update_render_queue;
+pub use prepare_transforms::PrepareTransforms;
+pub use update_light_clusters::UpdateLightClusters;
+pub use update_render_queue::UpdateRenderQueue;
+
+mod update_camera_controllers;
+pub use update_camera_controllers::{UpdateFlyCameras, UpdateOrbitCameras};
+
+mod draw_wireframes;
+pub use draw_wireframes::DrawWireframes;
@@ -1,13 +1,15 @@
 use crate::{
     graphics::pipelines::{
-        DirectionalLight, GlobalUniforms, LightingUniform, PointLight, MAX_LIGHTS,
+        clusters::LightClusters, DirectionalLight, GlobalUniforms, LightingUniform,
+        MAX_DIRECTIONAL_LIGHTS,
     },
-    scene::components::{
-        transform::LocalUniform, CameraData, DirectionalLightData, PointLightData, Transform,
+    scene::{
+        components::{CameraData, DirectionalLightData, ProjectionType, Transform},
+        resources::{CurrentCamera, DebugViewMode},
     },
 };
 use nalgebra_glm::Vec4;
-use specs::{ReadStorage, System, WriteStorage};
+use specs::{Read, ReadStorage, System};
 use std::convert::TryInto;
 
 pub struct PreparePBR<'a> {
@@ -15,29 +17,37 @@ pub struct PreparePBR<'a> {
     pub(crate) encoder: &'a mut wgpu::CommandEncoder,
     pub(crate) constants_buffer: &'a wgpu::Buffer,
     pub(crate) lighting_buffer: &'a wgpu::Buffer,
+    pub(crate) warned_directional_lights: &'a mut bool,
 }
 
 impl<'a> System<'a> for PreparePBR<'a> {
     type SystemData = (
         ReadStorage<'a, CameraData>,
         ReadStorage<'a, DirectionalLightData>,
-        ReadStorage<'a, PointLightData>,
-        WriteStorage<'a, Transform>,
+        ReadStorage<'a, Transform>,
+        Option<Read<'a, CurrentCamera>>,
+        Option<Read<'a, DebugViewMode>>,
+        Read<'a, LightClusters>,
     );
 
     fn run(
         &mut self,
-        (camera_data, directional_lights, point_lights, mut transforms): Self::SystemData,
+        (
+            camera_data,
+            directional_lights,
+            transforms,
+            current_camera,
+            debug_view_mode,
+            light_clusters,
+        ): Self::SystemData,
     ) {
         use specs::Join;
         if transforms.count() == 0 {
             return;
         }
-        let filtered_camera_data: Vec<&CameraData> = camera_data
-            .join()
-            .filter(|data: &&CameraData| data.active)
-            .collect();
-        let camera_data = filtered_camera_data.first();
+        let camera_data = current_camera
+            .as_ref()
+            .and_then(|current_camera| camera_data.get(current_camera.entity));
 
         if camera_data.is_none() {
             return;
@@ -45,9 +55,18 @@ impl<'a> System<'a> for PreparePBR<'a> {
 
         let camera_data = camera_data.unwrap();
         let camera_matrix = camera_data.get_matrix();
+        let camera_position = camera_data.position();
 
+        let debug_view_mode = debug_view_mode.map_or(DebugViewMode::default(), |mode| *mode);
         let uniforms = GlobalUniforms {
             view_projection: camera_matrix,
+            debug_view: debug_view_mode.to_uniform(camera_data),
+            camera_position: Vec4::new(
+                camera_position.x,
+                camera_position.y,
+                camera_position.z,
+                1.0,
+            ),
         };
 
         let constants_buffer = self
@@ -71,31 +90,45 @@ impl<'a> System<'a> for PreparePBR<'a> {
             })
             .collect();
 
-        let mut point_light_data_vec: Vec<PointLight> = (&point_lights, &transforms)
-            .join()
-            .map(|(data, transform)| PointLight {
-                attenuation: Vec4::new(data.attenuation, 0.0, 0.0, 0.0),
-                color: Vec4::new(data.color.x, data.color.y, data.color.z, 1.0),
-                position: Vec4::new(
-                    transform.position.x,
-                    transform.position.y,
-                    transform.position.z,
-                    0.0,
-                ),
-            })
-            .collect();
+        if directional_light_data_vec.len() > MAX_DIRECTIONAL_LIGHTS {
+            if !*self.warned_directional_lights {
+                log::warn!(
+                    "PreparePBR: {} directional lights in the scene but only {} are supported, the rest are ignored.",
+                    directional_light_data_vec.len(),
+                    MAX_DIRECTIONAL_LIGHTS
+                );
+                *self.warned_directional_lights = true;
+            }
+        } else {
+            *self.warned_directional_lights = false;
+        }
 
-        let total_dir_lights = directional_light_data_vec.len() as u32;
-        let total_point_lights = point_light_data_vec.len() as u32;
+        let total_dir_lights = directional_light_data_vec.len().min(MAX_DIRECTIONAL_LIGHTS) as u32;
+        let total_point_lights = light_clusters.point_lights.len() as u32;
+        let total_spot_lights = light_clusters.spot_lights.len() as u32;
 
         // Fill in missing data if we don't have it.
-        point_light_data_vec.resize_with(MAX_LIGHTS / 2, || PointLight::default());
-        directional_light_data_vec.resize_with(MAX_LIGHTS / 2, || DirectionalLight::default());
+        directional_light_data_vec
+            .resize_with(MAX_DIRECTIONAL_LIGHTS, || DirectionalLight::default());
 
         let light_uniform = LightingUniform {
-            light_num: Vec4::new(total_dir_lights as f32, total_point_lights as f32, 0.0, 0.0),
+            light_num: Vec4::new(
+                total_dir_lights as f32,
+                total_point_lights as f32,
+                total_spot_lights as f32,
+                0.0,
+            ),
+            cluster_depth: Vec4::new(
+                camera_data.z_near(),
+                camera_data.z_far(),
+                match camera_data.projection_type() {
+                    ProjectionType::Orthographic { .. } => 1.0,
+                    ProjectionType::Perspective { .. } => 0.0,
+                },
+                0.0,
+            ),
             directional_lights: directional_light_data_vec.as_slice().try_into().unwrap(),
-            point_lights: point_light_data_vec.as_slice().try_into().unwrap(),
+            ..LightingUniform::default()
         };
 
         let lighting_buffer = self.device.create_buffer_with_data(
@@ -110,40 +143,5 @@ impl<'a> System<'a> for PreparePBR<'a> {
             0,
             std::mem::size_of::<LightingUniform>() as u64,
         );
-
-        // Update transform buffers.
-        {
-            let size = std::mem::size_of::<LocalUniform>();
-            let mut temp_buf_data = self.device.create_buffer_mapped(&wgpu::BufferDescriptor {
-                size: (transforms.count() * size) as u64,
-                usage: wgpu::BufferUsage::COPY_SRC,
-                label: None,
-            });
-
-            // FIXME: Align and use `LayoutVerified`
-            for (transform, slot) in (&mut transforms)
-                .join()
-                .zip(temp_buf_data.data().chunks_exact_mut(size))
-            {
-                transform.update();
-                slot.copy_from_slice(bytemuck::bytes_of(&LocalUniform {
-                    world: transform.matrix,
-                }));
-            }
-
-            let temp_buf = temp_buf_data.finish();
-
-            let mut i = 0;
-            for transform in transforms.join() {
-                self.encoder.copy_buffer_to_buffer(
-                    &temp_buf,
-                    (i * size) as wgpu::BufferAddress,
-                    &transform.local_buffer,
-                    0,
-                    size as wgpu::BufferAddress,
-                );
-                i += 1;
-            }
-        }
     }
 }
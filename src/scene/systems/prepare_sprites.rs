@@ -0,0 +1,126 @@
+use crate::{
+    graphics::pipelines::{GlobalUniforms, SpriteBatch, SpriteInstance},
+    scene::{
+        components::{CameraData, Sprite, Transform},
+        resources::CurrentCamera,
+    },
+    AssetManager,
+};
+use nalgebra_glm::{Vec3, Vec4};
+use specs::{Read, ReadStorage, System};
+
+pub struct PrepareSprites<'a> {
+    pub(crate) device: &'a wgpu::Device,
+    pub(crate) encoder: &'a mut wgpu::CommandEncoder,
+    pub(crate) asset_manager: &'a AssetManager,
+    pub(crate) constants_buffer: &'a wgpu::Buffer,
+    pub(crate) instances: &'a mut Vec<SpriteInstance>,
+    pub(crate) batches: &'a mut Vec<SpriteBatch>,
+}
+
+impl<'a> System<'a> for PrepareSprites<'a> {
+    type SystemData = (
+        ReadStorage<'a, CameraData>,
+        ReadStorage<'a, Sprite>,
+        ReadStorage<'a, Transform>,
+        Option<Read<'a, CurrentCamera>>,
+    );
+
+    fn run(&mut self, (camera_data, sprites, transforms, current_camera): Self::SystemData) {
+        use specs::Join;
+
+        self.instances.clear();
+        self.batches.clear();
+
+        let camera_data = current_camera
+            .as_ref()
+            .and_then(|current_camera| camera_data.get(current_camera.entity));
+        if camera_data.is_none() {
+            return;
+        }
+
+        let uniforms = GlobalUniforms {
+            view_projection: camera_data.unwrap().get_matrix(),
+            ..GlobalUniforms::default()
+        };
+
+        let constants_buffer = self
+            .device
+            .create_buffer_with_data(bytemuck::bytes_of(&uniforms), wgpu::BufferUsage::COPY_SRC);
+
+        self.encoder.copy_buffer_to_buffer(
+            &constants_buffer,
+            0,
+            &self.constants_buffer,
+            0,
+            std::mem::size_of::<GlobalUniforms>() as u64,
+        );
+
+        // Z order decides what ends up on top, inside of a z order sprites are grouped by
+        // image so each group is a single instanced draw.
+        let mut sprites: Vec<(&Sprite, &Transform)> = (&sprites, &transforms).join().collect();
+        sprites.sort_by(|(a, _), (b, _)| {
+            a.z_order
+                .cmp(&b.z_order)
+                .then_with(|| a.image.cmp(&b.image))
+        });
+
+        for (sprite, transform) in sprites {
+            let image = match self.asset_manager.images.get(&sprite.image) {
+                Some(image) => image,
+                None => {
+                    log::warn!("PrepareSprites: image {} doesn't exist.", sprite.image);
+                    continue;
+                }
+            };
+
+            let image_width = image.extent.width as f32;
+            let image_height = image.extent.height as f32;
+            let source = sprite.source.unwrap_or(crate::gui::core::Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: image_width,
+                height: image_height,
+            });
+
+            let mut uv_rect = Vec4::new(
+                source.x / image_width,
+                source.y / image_height,
+                source.width / image_width,
+                source.height / image_height,
+            );
+            if sprite.flip_x {
+                uv_rect.x += uv_rect.z;
+                uv_rect.z = -uv_rect.z;
+            }
+            if sprite.flip_y {
+                uv_rect.y += uv_rect.w;
+                uv_rect.w = -uv_rect.w;
+            }
+
+            let size = Vec3::new(
+                source.width / sprite.pixels_per_unit,
+                source.height / sprite.pixels_per_unit,
+                1.0,
+            );
+            let pivot = Vec3::new(-sprite.pivot.x * size.x, -sprite.pivot.y * size.y, 0.0);
+            let world =
+                transform.matrix * nalgebra_glm::translation(&pivot) * nalgebra_glm::scaling(&size);
+
+            let index = self.instances.len() as u32;
+            self.instances.push(SpriteInstance {
+                world,
+                uv_rect,
+                tint: sprite.tint,
+            });
+
+            match self.batches.last_mut() {
+                Some(batch) if batch.image == sprite.image => batch.instances.end = index + 1,
+                _ => self.batches.push(SpriteBatch {
+                    image: sprite.image.clone(),
+                    instances: index..index + 1,
+                }),
+            }
+        }
+    }
+}
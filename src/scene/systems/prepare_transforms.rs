@@ -0,0 +1,115 @@
+use crate::{
+    graphics::resources::DynamicUniformBuffer,
+    scene::components::{transform::LocalUniform, MaterialOverride, Parent, Transform},
+};
+use nalgebra_glm::{Mat4, Vec4};
+use specs::{Entities, Entity, ReadStorage, System, WriteExpect, WriteStorage};
+use std::collections::HashSet;
+
+/// Updates every transform's world matrix and uploads them all into the shared local uniform buffer.
+/// Transforms of entities with a `Parent` are relative to their parent's.
+pub struct PrepareTransforms<'a> {
+    pub(crate) device: &'a wgpu::Device,
+    pub(crate) encoder: &'a mut wgpu::CommandEncoder,
+    pub(crate) local_bind_group_layout: &'a wgpu::BindGroupLayout,
+    /// Entities whose parents formed a cycle last frame, so each cycle is only reported once.
+    pub(crate) warned_cycles: &'a mut HashSet<Entity>,
+}
+
+impl<'a> System<'a> for PrepareTransforms<'a> {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Transform>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, MaterialOverride>,
+        WriteExpect<'a, DynamicUniformBuffer<LocalUniform>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut transforms, parents, overrides, mut local_buffer): Self::SystemData,
+    ) {
+        use specs::Join;
+        if transforms.count() == 0 {
+            return;
+        }
+
+        for transform in (&mut transforms).join() {
+            transform.update();
+        }
+
+        // Every matrix is still local here, so children are computed before any are written.
+        let mut cycles = HashSet::new();
+        let world_matrices: Vec<(Entity, Mat4)> = (&entities, &parents, &transforms)
+            .join()
+            .map(|(entity, parent, transform)| {
+                let parent_matrix = Self::parent_matrix(&transforms, &parents, parent.entity)
+                    .unwrap_or_else(|matrix| {
+                        cycles.insert(entity);
+                        matrix
+                    });
+                (entity, parent_matrix * transform.matrix)
+            })
+            .collect();
+        for entity in cycles.iter() {
+            if !self.warned_cycles.contains(entity) {
+                log::warn!(
+                    "PrepareTransforms: the parents of entity {} form a cycle, they're ignored past it.",
+                    entity.id()
+                );
+            }
+        }
+        *self.warned_cycles = cycles;
+        for (entity, matrix) in world_matrices {
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.matrix = matrix;
+            }
+        }
+
+        let mut locals = Vec::with_capacity(transforms.count());
+        for (transform, material_override) in (&transforms, overrides.maybe()).join() {
+            locals.push(LocalUniform {
+                world: transform.matrix,
+                color: material_override
+                    .map(|material_override| material_override.color)
+                    .unwrap_or_else(|| Vec4::new(1.0, 1.0, 1.0, 1.0)),
+            });
+        }
+
+        local_buffer.upload(
+            self.device,
+            self.local_bind_group_layout,
+            self.encoder,
+            &locals,
+        );
+
+        // Joins always walk in the same order so slot `i` belongs to the `i`th transform.
+        for (slot, transform) in (&mut transforms).join().enumerate() {
+            transform.dynamic_offset = local_buffer.offset(slot);
+        }
+    }
+}
+
+impl<'a> PrepareTransforms<'a> {
+    // Walks up the hierarchy multiplying local matrices. Parents without a transform end the walk.
+    // A hierarchy can't be deeper than the number of transforms, so walking further means the
+    // parents form a cycle, the matrix up to that point is returned as the error.
+    fn parent_matrix(
+        transforms: &WriteStorage<'_, Transform>,
+        parents: &ReadStorage<'_, Parent>,
+        mut entity: Entity,
+    ) -> Result<Mat4, Mat4> {
+        let mut matrix = Mat4::identity();
+        for _ in 0..transforms.count() {
+            match transforms.get(entity) {
+                Some(transform) => matrix = transform.matrix * matrix,
+                None => return Ok(matrix),
+            }
+            match parents.get(entity) {
+                Some(parent) => entity = parent.entity,
+                None => return Ok(matrix),
+            }
+        }
+        Err(matrix)
+    }
+}
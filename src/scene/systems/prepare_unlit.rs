@@ -1,8 +1,12 @@
 use crate::{
     graphics::pipelines::GlobalUniforms,
-    scene::components::{transform::LocalUniform, CameraData, Transform},
+    scene::{
+        components::{CameraData, Transform},
+        resources::{CurrentCamera, DebugViewMode},
+    },
 };
-use specs::{ReadStorage, System, WriteStorage};
+use nalgebra_glm::Vec4;
+use specs::{Read, ReadStorage, System};
 
 pub struct PrepareUnlit<'a> {
     pub(crate) device: &'a wgpu::Device,
@@ -11,18 +15,23 @@ pub struct PrepareUnlit<'a> {
 }
 
 impl<'a> System<'a> for PrepareUnlit<'a> {
-    type SystemData = (ReadStorage<'a, CameraData>, WriteStorage<'a, Transform>);
+    type SystemData = (
+        ReadStorage<'a, CameraData>,
+        ReadStorage<'a, Transform>,
+        Option<Read<'a, CurrentCamera>>,
+        Option<Read<'a, DebugViewMode>>,
+    );
 
-    fn run(&mut self, (camera_data, mut transforms): Self::SystemData) {
-        use specs::Join;
+    fn run(
+        &mut self,
+        (camera_data, transforms, current_camera, debug_view_mode): Self::SystemData,
+    ) {
         if transforms.count() == 0 {
             return;
         }
-        let filtered_camera_data: Vec<&CameraData> = camera_data
-            .join()
-            .filter(|data: &&CameraData| data.active)
-            .collect();
-        let camera_data = filtered_camera_data.first();
+        let camera_data = current_camera
+            .as_ref()
+            .and_then(|current_camera| camera_data.get(current_camera.entity));
 
         if camera_data.is_none() {
             return;
@@ -30,9 +39,18 @@ impl<'a> System<'a> for PrepareUnlit<'a> {
 
         let camera_data = camera_data.unwrap();
         let camera_matrix = camera_data.get_matrix();
+        let camera_position = camera_data.position();
 
+        let debug_view_mode = debug_view_mode.map_or(DebugViewMode::default(), |mode| *mode);
         let uniforms = GlobalUniforms {
             view_projection: camera_matrix,
+            debug_view: debug_view_mode.to_uniform(camera_data),
+            camera_position: Vec4::new(
+                camera_position.x,
+                camera_position.y,
+                camera_position.z,
+                1.0,
+            ),
         };
 
         let constants_buffer = self
@@ -46,39 +64,5 @@ impl<'a> System<'a> for PrepareUnlit<'a> {
             0,
             std::mem::size_of::<GlobalUniforms>() as u64,
         );
-
-        {
-            let size = std::mem::size_of::<LocalUniform>();
-            let mut temp_buf_data = self.device.create_buffer_mapped(&wgpu::BufferDescriptor {
-                size: (transforms.count() * size) as u64,
-                usage: wgpu::BufferUsage::COPY_SRC,
-                label: None,
-            });
-
-            // FIXME: Align and use `LayoutVerified`
-            for (transform, slot) in (&mut transforms)
-                .join()
-                .zip(temp_buf_data.data().chunks_exact_mut(size))
-            {
-                transform.update();
-                slot.copy_from_slice(bytemuck::bytes_of(&LocalUniform {
-                    world: transform.matrix,
-                }));
-            }
-
-            let temp_buf = temp_buf_data.finish();
-
-            let mut i = 0;
-            for transform in transforms.join() {
-                self.encoder.copy_buffer_to_buffer(
-                    &temp_buf,
-                    (i * size) as wgpu::BufferAddress,
-                    &transform.local_buffer,
-                    0,
-                    size as wgpu::BufferAddress,
-                );
-                i += 1;
-            }
-        }
     }
 }
@@ -1,12 +1,16 @@
 use crate::AssetManager;
 use crate::{
-    graphics::{material::Material, Pipeline},
-    scene::components::{Mesh, Transform},
+    graphics::{resources::DynamicUniformBuffer, Pipeline},
+    scene::{
+        components::{transform::LocalUniform, Transform},
+        resources::{CurrentCamera, RenderQueue},
+    },
 };
-use specs::{ReadStorage, System};
+use specs::{Read, ReadStorage, System};
 
 pub struct RenderUnlit<'a> {
     pub(crate) device: &'a wgpu::Device,
+    pub(crate) pipeline_name: &'a str,
     pub(crate) asset_manager: &'a AssetManager,
     pub(crate) encoder: &'a mut wgpu::CommandEncoder,
     pub(crate) frame_view: &'a wgpu::TextureView,
@@ -18,13 +22,18 @@ pub struct RenderUnlit<'a> {
 
 impl<'a> System<'a> for RenderUnlit<'a> {
     type SystemData = (
-        ReadStorage<'a, Mesh>,
-        ReadStorage<'a, crate::scene::components::Material>,
         ReadStorage<'a, Transform>,
+        Option<Read<'a, RenderQueue>>,
+        Option<Read<'a, DynamicUniformBuffer<LocalUniform>>>,
+        Option<Read<'a, CurrentCamera>>,
     );
 
-    fn run(&mut self, (meshes, materials, transforms): Self::SystemData) {
-        use specs::Join;
+    fn run(&mut self, (transforms, render_queue, local_buffer, current_camera): Self::SystemData) {
+        if render_queue.is_none() || local_buffer.is_none() {
+            return;
+        }
+        let render_queue = render_queue.unwrap();
+        let local_buffer = local_buffer.unwrap();
 
         let mut render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
@@ -49,39 +58,38 @@ impl<'a> System<'a> for RenderUnlit<'a> {
                 clear_stencil: 0,
             }),
         });
+        if let Some(current_camera) = current_camera.as_ref() {
+            current_camera.set_viewport(&mut render_pass);
+        }
         render_pass.set_pipeline(&self.pipeline.pipeline);
         render_pass.set_bind_group(1, self.global_bind_group, &[]);
 
-        let asset_materials = self.asset_manager.get_materials();
-        /*
-            TODO: It's not very efficient to loop through each entity that has a material. Fix that.
-            Look into using: https://docs.rs/specs/0.16.1/specs/struct.FlaggedStorage.html
-        */
-        for asset_material in asset_materials {
-            let joined_data = (&meshes, &materials, &transforms).join();
-            match asset_material {
-                Material::Unlit(unlit_material) => {
-                    render_pass.set_bind_group(
-                        2,
-                        &unlit_material.bind_group_data.as_ref().unwrap().bind_group,
-                        &[],
-                    );
-                    for (mesh, _, transform) in joined_data
-                        .filter(|(_, material, _)| material.index == unlit_material.index)
-                    {
-                        render_pass.set_bind_group(0, &transform.bind_group, &[]);
-                        let mesh: &Mesh = mesh;
-                        let asset_mesh = self.asset_manager.get_mesh(mesh.mesh_name.clone());
-                        for sub_mesh in asset_mesh.sub_meshes.iter() {
-                            // render_pass.set_bind_group(1, &current_bind_group.bind_group, &[]);
-                            render_pass.set_index_buffer(&sub_mesh.index_buffer, 0, 0);
-                            render_pass.set_vertex_buffer(0, &sub_mesh.vertex_buffer, 0, 0);
-                            render_pass.draw_indexed(0..sub_mesh.index_count as u32, 0, 0..1);
-                        }
-                    }
+        // The queue is sorted by material so we only switch material bind groups when we have to.
+        let mut current_material = None;
+        for item in render_queue.get_items(self.pipeline_name) {
+            let transform = match transforms.get(item.entity) {
+                Some(transform) => transform,
+                None => continue,
+            };
+
+            if current_material != Some(item.material_index) {
+                match self
+                    .asset_manager
+                    .get_material(item.material_index)
+                    .bind_group()
+                {
+                    Some(bind_group) => render_pass.set_bind_group(2, bind_group, &[]),
+                    None => continue,
                 }
-                _ => (),
+                current_material = Some(item.material_index);
             }
+
+            render_pass.set_bind_group(0, &local_buffer.bind_group, &[transform.dynamic_offset]);
+            let asset_mesh = self.asset_manager.get_mesh(item.mesh_name.clone());
+            let sub_mesh = &asset_mesh.sub_meshes[item.sub_mesh];
+            render_pass.set_index_buffer(&sub_mesh.index_buffer, 0, 0);
+            render_pass.set_vertex_buffer(0, &sub_mesh.vertex_buffer, 0, 0);
+            render_pass.draw_indexed(0..sub_mesh.index_count as u32, 0, 0..1);
         }
     }
 }
@@ -0,0 +1,186 @@
+use crate::{
+    core::input::{Input, MouseButton},
+    scene::{
+        components::{
+            camera_controller::{look_direction, look_rotation},
+            CameraData, FlyCamera, OrbitCamera, Transform,
+        },
+        resources::DeltaTime,
+    },
+};
+use nalgebra_glm::Vec3;
+use specs::{Read, System, WriteStorage};
+use winit::event::VirtualKeyCode;
+
+// Just short of straight up or down so the view never flips.
+const MAX_PITCH: f32 = 1.55;
+
+/// Applies a camera's eye and rotation to its `CameraData` and, if it has one, its `Transform`.
+fn apply(
+    camera_data: &mut CameraData,
+    transform: Option<&mut Transform>,
+    eye: Vec3,
+    yaw: f32,
+    pitch: f32,
+) {
+    let forward = look_direction(yaw, pitch);
+    camera_data.update_view(eye, eye + forward, Vec3::new(0.0, 1.0, 0.0));
+    if let Some(transform) = transform {
+        transform.position = eye;
+        transform.rotation = look_rotation(yaw, pitch);
+    }
+}
+
+/// Moves every `FlyCamera` from this step's input.
+pub struct UpdateFlyCameras;
+
+impl<'a> System<'a> for UpdateFlyCameras {
+    type SystemData = (
+        Read<'a, DeltaTime>,
+        Option<Read<'a, Input>>,
+        WriteStorage<'a, FlyCamera>,
+        WriteStorage<'a, CameraData>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (delta_time, input, mut fly_cameras, mut camera_data, mut transforms): Self::SystemData,
+    ) {
+        use specs::Join;
+
+        if input.is_none() {
+            return;
+        }
+        let input = input.unwrap();
+
+        for (fly_camera, camera_data, transform) in (
+            &mut fly_cameras,
+            &mut camera_data,
+            (&mut transforms).maybe(),
+        )
+            .join()
+        {
+            let looking = fly_camera
+                .look_button
+                .map_or(true, |button| input.is_mouse_button_down(button));
+            if looking {
+                let mouse_delta = input.mouse_delta();
+                fly_camera.yaw += mouse_delta.x * fly_camera.sensitivity;
+                fly_camera.pitch = (fly_camera.pitch - mouse_delta.y * fly_camera.sensitivity)
+                    .max(-MAX_PITCH)
+                    .min(MAX_PITCH);
+            }
+
+            let forward = fly_camera.forward();
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let right = up.cross(&forward).normalize();
+
+            let mut movement = Vec3::zeros();
+            if input.is_key_down(VirtualKeyCode::W) {
+                movement += forward;
+            }
+            if input.is_key_down(VirtualKeyCode::S) {
+                movement -= forward;
+            }
+            if input.is_key_down(VirtualKeyCode::D) {
+                movement += right;
+            }
+            if input.is_key_down(VirtualKeyCode::A) {
+                movement -= right;
+            }
+            if input.is_key_down(VirtualKeyCode::E) {
+                movement += up;
+            }
+            if input.is_key_down(VirtualKeyCode::Q) {
+                movement -= up;
+            }
+
+            if movement.magnitude_squared() > 0.0 {
+                let mut speed = fly_camera.speed;
+                if input.is_key_down(VirtualKeyCode::LShift)
+                    || input.is_key_down(VirtualKeyCode::RShift)
+                {
+                    speed *= fly_camera.boost;
+                }
+                fly_camera.position += movement.normalize() * speed * delta_time.0;
+            }
+
+            apply(
+                camera_data,
+                transform,
+                fly_camera.position,
+                fly_camera.yaw,
+                fly_camera.pitch,
+            );
+        }
+    }
+}
+
+/// Orbits, pans and zooms every `OrbitCamera` from this step's input.
+pub struct UpdateOrbitCameras;
+
+impl<'a> System<'a> for UpdateOrbitCameras {
+    type SystemData = (
+        Option<Read<'a, Input>>,
+        WriteStorage<'a, OrbitCamera>,
+        WriteStorage<'a, CameraData>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (input, mut orbit_cameras, mut camera_data, mut transforms): Self::SystemData,
+    ) {
+        use specs::Join;
+
+        if input.is_none() {
+            return;
+        }
+        let input = input.unwrap();
+        let mouse_delta = input.mouse_delta();
+
+        for (orbit_camera, camera_data, transform) in (
+            &mut orbit_cameras,
+            &mut camera_data,
+            (&mut transforms).maybe(),
+        )
+            .join()
+        {
+            if input.is_mouse_button_down(MouseButton::Left) {
+                orbit_camera.yaw += mouse_delta.x * orbit_camera.rotate_sensitivity;
+                // Dragging down tips the scene towards the camera, so the camera rises.
+                orbit_camera.pitch = (orbit_camera.pitch
+                    - mouse_delta.y * orbit_camera.rotate_sensitivity)
+                    .max(-MAX_PITCH)
+                    .min(MAX_PITCH);
+            }
+
+            if input.is_mouse_button_down(MouseButton::Right)
+                || input.is_mouse_button_down(MouseButton::Middle)
+            {
+                let forward = orbit_camera.forward();
+                let right = Vec3::new(0.0, 1.0, 0.0).cross(&forward).normalize();
+                let up = forward.cross(&right);
+                let pan = orbit_camera.pan_speed * orbit_camera.distance;
+                orbit_camera.target += (-right * mouse_delta.x + up * mouse_delta.y) * pan;
+            }
+
+            let scroll = input.mouse_wheel_movement().y;
+            if scroll != 0.0 {
+                orbit_camera.distance = (orbit_camera.distance
+                    * (1.0 - orbit_camera.zoom_speed).powf(scroll))
+                .max(orbit_camera.min_distance)
+                .min(orbit_camera.max_distance);
+            }
+
+            apply(
+                camera_data,
+                transform,
+                orbit_camera.eye(),
+                orbit_camera.yaw,
+                orbit_camera.pitch,
+            );
+        }
+    }
+}
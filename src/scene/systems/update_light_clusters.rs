@@ -0,0 +1,107 @@
+use crate::{
+    graphics::{
+        pipelines::{
+            clusters::{ClusterDepth, LightClusters},
+            PointLight, SpotLight,
+        },
+        resources::LightBuffers,
+    },
+    scene::{
+        components::{CameraData, PointLightData, ProjectionType, SpotLightData, Transform},
+        resources::CurrentCamera,
+    },
+};
+use nalgebra_glm::{Vec3, Vec4};
+use specs::{Read, ReadStorage, System, Write, WriteExpect};
+
+/// Gathers the point and spot lights, assigns them to the current camera's clusters and
+/// uploads the result to the shared `LightBuffers`. Runs once per camera, before the render
+/// graph, so the PBR pipelines don't each redo it.
+pub struct UpdateLightClusters<'a> {
+    pub(crate) device: &'a wgpu::Device,
+    pub(crate) encoder: &'a mut wgpu::CommandEncoder,
+}
+
+impl<'a> System<'a> for UpdateLightClusters<'a> {
+    type SystemData = (
+        ReadStorage<'a, CameraData>,
+        ReadStorage<'a, PointLightData>,
+        ReadStorage<'a, SpotLightData>,
+        ReadStorage<'a, Transform>,
+        Option<Read<'a, CurrentCamera>>,
+        Write<'a, LightClusters>,
+        WriteExpect<'a, LightBuffers>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            camera_data,
+            point_lights,
+            spot_lights,
+            transforms,
+            current_camera,
+            mut light_clusters,
+            mut light_buffers,
+        ): Self::SystemData,
+    ) {
+        use specs::Join;
+        let camera_data = current_camera
+            .as_ref()
+            .and_then(|current_camera| camera_data.get(current_camera.entity));
+        if camera_data.is_none() {
+            return;
+        }
+        let camera_data = camera_data.unwrap();
+
+        let point_light_data_vec: Vec<PointLight> = (&point_lights, &transforms)
+            .join()
+            .map(|(data, transform)| {
+                let position = transform.world_position();
+                PointLight {
+                    position: Vec4::new(position.x, position.y, position.z, data.range),
+                    color: Vec4::new(data.color.x, data.color.y, data.color.z, data.intensity),
+                }
+            })
+            .collect();
+
+        let spot_light_data_vec: Vec<SpotLight> = (&spot_lights, &transforms)
+            .join()
+            .map(|(data, transform)| {
+                let direction = transform.matrix
+                    * Vec4::new(data.direction.x, data.direction.y, data.direction.z, 0.0);
+                let direction = if direction.xyz().norm_squared() > 0.0 {
+                    direction.xyz().normalize()
+                } else {
+                    Vec3::new(0.0, -1.0, 0.0)
+                };
+                let position = transform.world_position();
+                // The shader's smoothstep is undefined when both edges are equal.
+                let cos_outer = data.outer_cone_angle.cos();
+                let cos_inner = data.inner_cone_angle.cos().max(cos_outer + 0.0001);
+                SpotLight {
+                    position: Vec4::new(position.x, position.y, position.z, data.range),
+                    direction: Vec4::new(direction.x, direction.y, direction.z, 0.0),
+                    color: Vec4::new(data.color.x, data.color.y, data.color.z, data.intensity),
+                    cone: Vec4::new(cos_inner, cos_outer, 0.0, 0.0),
+                }
+            })
+            .collect();
+
+        light_clusters.build(
+            &camera_data.view,
+            &camera_data.projection,
+            ClusterDepth {
+                z_near: camera_data.z_near(),
+                z_far: camera_data.z_far(),
+                orthographic: match camera_data.projection_type() {
+                    ProjectionType::Orthographic { .. } => true,
+                    ProjectionType::Perspective { .. } => false,
+                },
+            },
+            point_light_data_vec,
+            spot_light_data_vec,
+        );
+        light_buffers.upload(self.device, self.encoder, &light_clusters);
+    }
+}
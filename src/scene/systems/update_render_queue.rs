@@ -0,0 +1,53 @@
+use crate::{
+    scene::{
+        components::{CameraData, Material, Mesh, Transform},
+        resources::{CurrentCamera, RenderQueue},
+    },
+    AssetManager,
+};
+use nalgebra_glm::Vec4;
+use specs::{Entities, Read, ReadStorage, System, Write};
+
+pub struct UpdateRenderQueue<'a> {
+    pub(crate) asset_manager: &'a AssetManager,
+}
+
+impl<'a> System<'a> for UpdateRenderQueue<'a> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Mesh>,
+        ReadStorage<'a, Material>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, CameraData>,
+        Option<Write<'a, RenderQueue>>,
+        Option<Read<'a, CurrentCamera>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, meshes, materials, transforms, camera_data, render_queue, current_camera): Self::SystemData,
+    ) {
+        if render_queue.is_none() {
+            return;
+        }
+        let mut render_queue = render_queue.unwrap();
+
+        render_queue.update(self.asset_manager, &entities, &meshes, &materials);
+
+        // Transparent items are sorted for whichever camera is about to draw.
+        let camera_data = current_camera
+            .as_ref()
+            .and_then(|current_camera| camera_data.get(current_camera.entity));
+        if camera_data.is_none() {
+            return;
+        }
+        let view = camera_data.unwrap().view;
+
+        render_queue.sort(&view, |entity| {
+            transforms.get(entity).map(|transform| {
+                let position = transform.world_position();
+                Vec4::new(position.x, position.y, position.z, 1.0)
+            })
+        });
+    }
+}